@@ -0,0 +1,57 @@
+#![feature(test)]
+
+extern crate test;
+extern crate panoradix;
+
+use test::{Bencher, black_box};
+
+use panoradix::RadixMap;
+use panoradix::hot::HotRadixMap;
+
+// 100k keys, and 10k lookups of which 80% are of the 100 hottest keys
+fn workload() -> (RadixMap<str, usize>, Vec<String>) {
+    let keys: Vec<_> = (0..100_000)
+        .map(|i| format!("/tenants/{}/users/{}/profile", i % 97, i))
+        .collect();
+
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+    let queries = (0..10_000)
+        .map(|_| {
+            let i = if next() % 10 < 8 { next() % 100 * 1000 } else { next() % keys.len() };
+            keys[i].clone()
+        })
+        .collect();
+
+    let map = keys.into_iter().enumerate().map(|(i, k)| (k, i)).collect();
+    (map, queries)
+}
+
+#[bench]
+fn hot_lookups(b: &mut Bencher) {
+    let (map, queries) = workload();
+    // room for the hottest keys, each of them having two slots to pick from
+    let mut map = HotRadixMap::with_map(map, 256);
+
+    b.iter(|| {
+        for query in queries.iter() {
+            black_box(map.get(query));
+        }
+    });
+}
+
+#[bench]
+fn hot_lookups_in_map(b: &mut Bencher) {
+    let (map, queries) = workload();
+
+    b.iter(|| {
+        for query in queries.iter() {
+            black_box(map.get(query));
+        }
+    });
+}
@@ -0,0 +1,41 @@
+#![feature(test)]
+
+extern crate test;
+extern crate panoradix;
+
+use test::{Bencher, black_box};
+
+use panoradix::RadixMap;
+
+// 100k keys, and a broad prefix followed by 1000 narrower ones below it
+fn overlapping_prefixes() -> (RadixMap<str, usize>, Vec<String>) {
+    let map = (0..100_000)
+        .map(|i| (format!("/api/v{}/users/{}", i % 2 + 1, i), i))
+        .collect();
+
+    let mut prefixes = vec!["/api/v1/".to_string()];
+    prefixes.extend((0..1000).map(|i| format!("/api/v1/users/{}", i * 7)));
+    (map, prefixes)
+}
+
+#[bench]
+fn find_batch_of_overlapping_prefixes(b: &mut Bencher) {
+    let (map, prefixes) = overlapping_prefixes();
+
+    b.iter(|| {
+        for matches in map.find_batch(&prefixes) {
+            black_box(matches.count());
+        }
+    });
+}
+
+#[bench]
+fn find_each_of_overlapping_prefixes(b: &mut Bencher) {
+    let (map, prefixes) = overlapping_prefixes();
+
+    b.iter(|| {
+        for prefix in prefixes.iter() {
+            black_box(map.find(prefix).count());
+        }
+    });
+}
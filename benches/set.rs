@@ -1,11 +1,14 @@
 #![feature(test)]
+#![allow(clippy::redundant_static_lifetimes, clippy::useless_vec, clippy::manual_repeat_n)]
+#![allow(clippy::almost_complete_range, unused_must_use)]
 
 extern crate test;
 extern crate panoradix;
 
+use std::collections::HashSet;
 use std::iter;
 
-use test::Bencher;
+use test::{Bencher, black_box};
 use panoradix::RadixSet;
 
 #[bench]
@@ -19,6 +22,19 @@ fn basic_lookup(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn lookup_in_tiny_sets(b: &mut Bencher) {
+    // a single key, then a chain of keys, like small sets of settings
+    let single: RadixSet<str> = vec!["log.level"].into_iter().collect();
+    let chain: RadixSet<str> = vec!["log", "log.level", "log.level.default"].into_iter().collect();
+    b.iter(|| {
+        black_box(single.contains("log.level"));
+        black_box(single.contains("log.format"));
+        black_box(chain.contains("log.level"));
+        black_box(chain.contains("log.level.default"));
+    });
+}
+
 #[bench]
 fn lookup_with_close_items(b: &mut Bencher) {
     let items: Vec<_> = (b'a'..b'z').map(|c| format!("{}-needle", c as char)).collect();
@@ -31,6 +47,95 @@ fn lookup_with_close_items(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn lookup_in_wide_nodes(b: &mut Bencher) {
+    let items: Vec<Vec<u8>> = (0..=255u8)
+        .flat_map(|x| (0..=255u8).step_by(8).map(move |y| vec![x, y, b'-']))
+        .collect();
+
+    let s: RadixSet<[u8]> = items.iter().collect();
+    b.iter(|| {
+        for item in items.iter() {
+            s.contains(item);
+        }
+    });
+}
+
+#[bench]
+fn lookup_words(b: &mut Bencher) {
+    let items: Vec<_> = WORDS_1.iter().chain(&WORDS_2).chain(&WORDS_3).chain(&WORDS_4).collect();
+
+    let s: RadixSet<str> = items.iter().collect();
+    b.iter(|| {
+        for item in items.iter() {
+            s.contains(item);
+        }
+    });
+}
+
+#[bench]
+fn lookup_words_in_batch(b: &mut Bencher) {
+    let items: Vec<_> = WORDS_1.iter().chain(&WORDS_2).chain(&WORDS_3).chain(&WORDS_4).collect();
+
+    let s: RadixSet<str> = items.iter().collect();
+    b.iter(|| s.contains_each(&items));
+}
+
+#[bench]
+fn lookup_sorted_words(b: &mut Bencher) {
+    let mut items: Vec<_> = WORDS_1.iter().chain(&WORDS_2).chain(&WORDS_3).chain(&WORDS_4).collect();
+    items.sort();
+
+    let s: RadixSet<str> = items.iter().collect();
+    b.iter(|| {
+        for item in items.iter() {
+            s.contains(item);
+        }
+    });
+}
+
+#[bench]
+fn lookup_sorted_words_in_batch(b: &mut Bencher) {
+    let mut items: Vec<_> = WORDS_1.iter().chain(&WORDS_2).chain(&WORDS_3).chain(&WORDS_4).collect();
+    items.sort();
+
+    let s: RadixSet<str> = items.iter().collect();
+    b.iter(|| s.contains_each(&items));
+}
+
+// short keys, half of them missing, to keep track of how far `contains` is from a `HashSet`
+// lookup: the descent goes through a few nodes here, and is expected to stay slower than hashing
+// the key (tests/allocations.rs checks that it doesn't allocate)
+fn short_keys() -> (Vec<String>, Vec<String>) {
+    let keys = (0..1000).map(|i| format!("id:{:x}", i * 7919)).collect();
+    let queries = (0..1000).map(|i| format!("id:{:x}", i * 7919 + i % 2)).collect();
+    (keys, queries)
+}
+
+#[bench]
+fn lookup_short_keys(b: &mut Bencher) {
+    let (keys, queries) = short_keys();
+
+    let s: RadixSet<str> = keys.iter().collect();
+    b.iter(|| {
+        for query in queries.iter() {
+            black_box(s.contains(query));
+        }
+    });
+}
+
+#[bench]
+fn lookup_short_keys_in_hash_set(b: &mut Bencher) {
+    let (keys, queries) = short_keys();
+
+    let s: HashSet<&str> = keys.iter().map(|k| k.as_str()).collect();
+    b.iter(|| {
+        for query in queries.iter() {
+            black_box(s.contains(query.as_str()));
+        }
+    });
+}
+
 fn repeating_characters() -> Vec<String> {
     const C: char = 'a';
     const N: usize = 20;
@@ -58,6 +163,15 @@ fn insert_repeating_characters_reversed(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn insert_words(b: &mut Bencher) {
+    let items: Vec<_> = WORDS_1.iter().chain(&WORDS_2).chain(&WORDS_3).chain(&WORDS_4).collect();
+
+    b.iter(|| {
+        items.iter().collect::<RadixSet<str>>()
+    });
+}
+
 #[bench]
 fn iteration(b: &mut Bencher) {
     {
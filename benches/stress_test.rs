@@ -1,4 +1,5 @@
 #![feature(test)]
+#![allow(clippy::large_const_arrays, clippy::redundant_static_lifetimes)]
 
 extern crate test;
 extern crate panoradix;
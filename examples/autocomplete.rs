@@ -0,0 +1,54 @@
+//! An autocompleter suggesting the most frequent words starting with what was typed so far.
+//!
+//! ```sh
+//! cargo run --example autocomplete
+//! ```
+
+extern crate panoradix;
+
+use panoradix::RadixMap;
+use panoradix::counter::RadixCounter;
+
+const TEXT: &str = "the quick brown fox jumps over the lazy dog then the fox thinks \
+                    that the dog is there then the dog thanks the fox and they sleep";
+
+fn main() {
+    // count the words of the corpus, then index the counts for completion
+    let counter: RadixCounter<str> = TEXT.split_whitespace().collect();
+    let frequencies: RadixMap<str, u64> = counter.iter().map(|(word, &count)| (word, count)).collect();
+
+    // the suggestions are refined as the user types
+    let mut completer = frequencies.completer();
+    let mut suggestions = Vec::new();
+    for c in "the".chars() {
+        completer.push_char(c);
+
+        let prefix = completer.prefix().to_string();
+        let top: Vec<_> = frequencies.find_by_weight(&prefix, |&count| count)
+            .take(3)
+            .map(|(word, _)| word)
+            .collect();
+        println!("{:>4} -> {:?}", prefix, top);
+        suggestions.push(top);
+    }
+
+    assert_eq!(suggestions[0], vec!["the", "then", "thanks"]);
+    assert_eq!(suggestions[2], vec!["the", "then", "there"]);
+
+    // completions are paginated by offset, without iterating over the previous pages
+    let page_size = 2;
+    let matches = completer.completions().len();
+    for page in 0..matches.div_ceil(page_size) {
+        let words: Vec<_> = (page * page_size..(page + 1) * page_size)
+            .filter_map(|n| frequencies.select_in("the", n))
+            .map(|(word, _)| word)
+            .collect();
+        println!("page {}: {:?}", page, words);
+    }
+
+    // typos can be erased, and nothing is suggested for unknown prefixes
+    completer.push_char('x');
+    assert_eq!(completer.completions().count(), 0);
+    completer.pop_char();
+    assert_eq!(completer.completions().count(), 4);
+}
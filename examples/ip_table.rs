@@ -0,0 +1,86 @@
+//! An IPv4 routing table, looking up the next hop of an address by longest prefix match.
+//!
+//! Keys are the bits of the addresses, so that a `/n` prefix is a key of `n` components.
+//!
+//! ```sh
+//! cargo run --example ip_table
+//! ```
+
+extern crate panoradix;
+
+use std::net::Ipv4Addr;
+
+use panoradix::RadixMap;
+
+fn bits(address: Ipv4Addr, len: usize) -> Vec<bool> {
+    let address = u32::from(address);
+    (0..len).map(|i| address & (1 << (31 - i)) != 0).collect()
+}
+
+fn parse_cidr(cidr: &str) -> (Ipv4Addr, usize) {
+    let mut parts = cidr.split('/');
+    let address = parts.next().unwrap().parse().unwrap();
+    let len = parts.next().map_or(32, |len| len.parse().unwrap());
+    (address, len)
+}
+
+fn to_cidr(bits: &[bool]) -> String {
+    let address = bits.iter().enumerate()
+        .filter(|&(_, &bit)| bit)
+        .fold(0u32, |address, (i, _)| address | (1 << (31 - i)));
+    format!("{}/{}", Ipv4Addr::from(address), bits.len())
+}
+
+struct RoutingTable {
+    routes: RadixMap<[bool], &'static str>,
+}
+
+impl RoutingTable {
+    fn new(routes: &[(&str, &'static str)]) -> RoutingTable {
+        let routes = routes.iter()
+            .map(|&(cidr, hop)| {
+                let (address, len) = parse_cidr(cidr);
+                (bits(address, len), hop)
+            })
+            .collect();
+
+        RoutingTable { routes }
+    }
+
+    fn next_hop(&self, address: Ipv4Addr) -> Option<(String, &'static str)> {
+        self.routes.ancestors(&bits(address, 32)[..])
+            .last()
+            .map(|(prefix, &hop)| (to_cidr(&prefix), hop))
+    }
+}
+
+fn main() {
+    let table = RoutingTable::new(&[
+        ("0.0.0.0/0", "upstream"),
+        ("10.0.0.0/8", "internal"),
+        ("10.1.0.0/16", "datacenter-1"),
+        ("10.1.2.0/24", "rack-12"),
+        ("192.168.0.0/16", "office"),
+    ]);
+
+    let lookups = [
+        ("10.1.2.3", "10.1.2.0/24", "rack-12"),
+        ("10.1.3.3", "10.1.0.0/16", "datacenter-1"),
+        ("10.200.0.1", "10.0.0.0/8", "internal"),
+        ("192.168.1.1", "192.168.0.0/16", "office"),
+        ("8.8.8.8", "0.0.0.0/0", "upstream"),
+    ];
+
+    for &(address, prefix, hop) in lookups.iter() {
+        let found = table.next_hop(address.parse().unwrap());
+        println!("{:<12} -> {:?}", address, found);
+        assert_eq!(found, Some((prefix.to_string(), hop)));
+    }
+
+    // the more specific routes of a network
+    let (network, len) = parse_cidr("10.0.0.0/8");
+    let subnets: Vec<_> = table.routes.find(&bits(network, len)[..])
+        .map(|(prefix, _)| to_cidr(&prefix))
+        .collect();
+    assert_eq!(subnets, vec!["10.0.0.0/8", "10.1.0.0/16", "10.1.2.0/24"]);
+}
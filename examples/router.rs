@@ -0,0 +1,84 @@
+//! A URL router dispatching requests to the handler of the longest matching route prefix, with
+//! a denylist of blocked paths.
+//!
+//! ```sh
+//! cargo run --example router
+//! ```
+
+extern crate panoradix;
+
+use panoradix::{RadixMap, RadixSet};
+
+type Handler = fn(&str) -> String;
+
+fn home(path: &str) -> String {
+    format!("home page ({})", path)
+}
+
+fn static_files(path: &str) -> String {
+    format!("file {}", &path["/static/".len()..])
+}
+
+fn api(path: &str) -> String {
+    format!("api call {}", &path["/api/".len()..])
+}
+
+fn users(path: &str) -> String {
+    format!("user {}", &path["/api/users/".len()..])
+}
+
+struct Router {
+    routes: RadixMap<str, Handler>,
+    denied: RadixSet<str>,
+}
+
+impl Router {
+    fn new() -> Router {
+        let mut routes: RadixMap<str, Handler> = RadixMap::new();
+        routes.insert("/", home);
+        routes.insert("/static/", static_files);
+        routes.insert("/api/", api);
+        routes.insert("/api/users/", users);
+
+        let denied = vec!["/api/internal/", "/static/.git"].into_iter().collect();
+
+        Router { routes, denied }
+    }
+
+    fn handle(&self, path: &str) -> Result<String, &'static str> {
+        if self.denied.is_prefix_of(path) {
+            return Err("403 forbidden");
+        }
+
+        // the routes prefixing the path are found in a single descent, the longest one wins
+        match self.routes.ancestors(path).last() {
+            Some((_, handler)) => Ok(handler(path)),
+            None               => Err("404 not found"),
+        }
+    }
+}
+
+fn main() {
+    let router = Router::new();
+
+    let requests = [
+        ("/", Ok("home page (/)")),
+        ("/about", Ok("home page (/about)")),
+        ("/static/app.js", Ok("file app.js")),
+        ("/api/health", Ok("api call health")),
+        ("/api/users/42", Ok("user 42")),
+        ("/api/internal/metrics", Err("403 forbidden")),
+        ("/static/.git/config", Err("403 forbidden")),
+        ("users", Err("404 not found")),
+    ];
+
+    for &(path, ref expected) in requests.iter() {
+        let response = router.handle(path);
+        println!("{:<24} -> {:?}", path, response);
+        assert_eq!(response.as_ref().map(|s| s.as_str()), expected.as_ref().map(|s| *s));
+    }
+
+    // the routes under a prefix are listed in order
+    let api_routes: Vec<_> = router.routes.find("/api").map(|(route, _)| route).collect();
+    assert_eq!(api_routes, vec!["/api/", "/api/users/"]);
+}
@@ -0,0 +1,145 @@
+use map::RadixMap;
+
+/// The letters of a word, sorted, which all of its anagrams share.
+fn signature(word: &str) -> String {
+    let mut letters: Vec<char> = word.chars().collect();
+    letters.sort_unstable();
+    letters.into_iter().collect()
+}
+
+/// A set of words indexed by their sorted letters, so that the anagrams of a word are found with a
+/// single lookup.
+///
+/// Both the words and their index are kept by this struct, so they can't get out of sync.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::anagram::AnagramIndex;
+///
+/// let mut index = AnagramIndex::new();
+/// for word in &["listen", "silent", "enlist", "tinsel", "list"] {
+///     index.insert(word);
+/// }
+///
+/// assert_eq!(index.anagrams("inlets"), vec!["enlist", "listen", "silent", "tinsel"]);
+/// assert_eq!(index.anagrams("slit"), vec!["list"]);
+///
+/// index.remove("silent");
+/// assert_eq!(index.anagrams("inlets"), vec!["enlist", "listen", "tinsel"]);
+/// ```
+pub struct AnagramIndex {
+    // sorted words, by signature
+    map: RadixMap<str, Vec<String>>,
+    len: usize,
+}
+
+impl AnagramIndex {
+    /// Makes a new empty index.
+    pub fn new() -> AnagramIndex {
+        AnagramIndex {
+            map: RadixMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of words in the index.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the index contains no words.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a word, returning `false` if it was already in the index.
+    pub fn insert(&mut self, word: &str) -> bool {
+        let key = signature(word);
+        let inserted = match self.map.get_mut(&key) {
+            Some(words) => match words.binary_search_by(|w| w.as_str().cmp(word)) {
+                Ok(_)  => false,
+                Err(i) => { words.insert(i, word.to_string()); true },
+            },
+            None => {
+                self.map.insert(&key, vec![word.to_string()]);
+                true
+            },
+        };
+
+        self.len += inserted as usize;
+        inserted
+    }
+
+    /// Removes a word, returning `false` if it wasn't in the index.
+    pub fn remove(&mut self, word: &str) -> bool {
+        let key = signature(word);
+        let (removed, now_empty) = match self.map.get_mut(&key) {
+            Some(words) => match words.binary_search_by(|w| w.as_str().cmp(word)) {
+                Ok(i)  => { words.remove(i); (true, words.is_empty()) },
+                Err(_) => (false, false),
+            },
+            None => (false, false),
+        };
+
+        if now_empty {
+            self.map.remove(&key);
+        }
+        self.len -= removed as usize;
+        removed
+    }
+
+    /// Returns if the word is in the index.
+    pub fn contains(&self, word: &str) -> bool {
+        self.map.get(&signature(word))
+            .is_some_and(|words| words.binary_search_by(|w| w.as_str().cmp(word)).is_ok())
+    }
+
+    /// Gets the words of the index made of the same letters as `letters` (which may or may not be
+    /// a word of the index), sorted.
+    pub fn anagrams(&self, letters: &str) -> Vec<&str> {
+        self.map.get(&signature(letters))
+            .map_or_else(Vec::new, |words| words.iter().map(|w| w.as_str()).collect())
+    }
+}
+
+impl Default for AnagramIndex {
+    fn default() -> AnagramIndex {
+        AnagramIndex::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnagramIndex;
+
+    #[test]
+    fn it_keeps_anagrams_in_sync_with_words() {
+        let mut index = AnagramIndex::new();
+        assert!(index.insert("stop"));
+        assert!(index.insert("pots"));
+        assert!(index.insert("tops"));
+        assert!(!index.insert("pots"));
+        assert!(index.insert("été"));
+        assert_eq!(index.len(), 4);
+
+        assert_eq!(index.anagrams("spot"), vec!["pots", "stop", "tops"]);
+        assert_eq!(index.anagrams("téé"), vec!["été"]);
+        assert!(index.anagrams("sto").is_empty());
+        assert!(index.contains("tops") && !index.contains("spot"));
+
+        assert!(index.remove("pots"));
+        assert!(!index.remove("pots"));
+        assert!(!index.remove("spot"));
+        assert!(index.remove("été"));
+        assert_eq!(index.len(), 2);
+        assert!(index.anagrams("téé").is_empty());
+        assert_eq!(index.anagrams("spot"), vec!["stop", "tops"]);
+
+        assert!(index.remove("stop") && index.remove("tops"));
+        assert!(index.is_empty());
+        assert!(index.map.is_empty());
+    }
+}
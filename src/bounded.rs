@@ -0,0 +1,272 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+
+use map::{RadixMap, Matches as MapMatches};
+use key::Key;
+
+/// A map holding a bounded amount of entries, evicting the least recently used ones.
+///
+/// The budget is either a number of entries (see [`new`]) or a total weight computed by a
+/// user-provided function, e.g. an estimation of the size in bytes of each entry (see
+/// [`with_weigher`]). Inserting an entry evicts the least recently used entries until the map is
+/// back within its budget, and an entry heavier than the whole budget is refused without evicting
+/// anything.
+///
+/// Entries are considered used when inserted or looked up through [`get`], the other lookups
+/// (e.g. [`find`]) don't affect the eviction order.
+///
+/// [`new`]: #method.new
+/// [`with_weigher`]: #method.with_weigher
+/// [`get`]: #method.get
+/// [`find`]: #method.find
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::bounded::BoundedRadixMap;
+///
+/// let mut cache = BoundedRadixMap::new(2);
+/// cache.insert("/users", 1).unwrap();
+/// cache.insert("/groups", 2).unwrap();
+///
+/// // "/users" is now the most recently used entry
+/// assert_eq!(cache.get("/users"), Some(&1));
+///
+/// cache.insert("/users/1", 3).unwrap();
+/// assert_eq!(cache.get("/groups"), None);
+/// assert_eq!(cache.find("/users").count(), 2);
+/// ```
+pub struct BoundedRadixMap<K: Key + ?Sized, V> {
+    map: RadixMap<K, Entry<V>>,
+    // keys by time of last use, the first one being the next to be evicted
+    recency: BTreeMap<u64, K::Owned>,
+    tick: u64,
+    weigher: fn(&K, &V) -> usize,
+    weight: usize,
+    max_weight: usize,
+}
+
+struct Entry<V> {
+    value: V,
+    last_used: u64,
+    weight: usize,
+}
+
+impl<K: Key + ?Sized, V> BoundedRadixMap<K, V> {
+    /// Makes a new empty map holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> BoundedRadixMap<K, V> {
+        BoundedRadixMap::with_weigher(capacity, |_, _| 1)
+    }
+
+    /// Makes a new empty map whose entries weigh at most `max_weight` in total.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::bounded::BoundedRadixMap;
+    ///
+    /// // keep at most 16 bytes of keys and values
+    /// let mut cache = BoundedRadixMap::with_weigher(16, |k: &str, v: &String| k.len() + v.len());
+    /// cache.insert("a", "foo".to_string()).unwrap();
+    /// cache.insert("b", "bar".to_string()).unwrap();
+    /// cache.insert("c", "a long value".to_string()).unwrap();
+    ///
+    /// assert_eq!(cache.weight(), 13);
+    /// assert!(!cache.contains_key("a"));
+    /// assert!(!cache.contains_key("b"));
+    ///
+    /// // too heavy to fit, even alone
+    /// let value = "an even longer value".to_string();
+    /// assert_eq!(cache.insert("d", value.clone()), Err(value));
+    /// assert_eq!(cache.weight(), 13);
+    /// ```
+    pub fn with_weigher(max_weight: usize, weigher: fn(&K, &V) -> usize) -> BoundedRadixMap<K, V> {
+        BoundedRadixMap {
+            map: RadixMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            weigher,
+            weight: 0,
+            max_weight,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the total weight of the entries in the map.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Inserts a key-value pair into the map, evicting the least recently used entries if the
+    /// map exceeds its budget.
+    ///
+    /// Returns the value previously stored at this key, or gives the value back as an error if the
+    /// entry alone weighs more than the whole budget, in which case the map is left untouched.
+    pub fn insert(&mut self, key: &K, value: V) -> Result<Option<V>, V> {
+        let weight = (self.weigher)(key, &value);
+        if weight > self.max_weight {
+            return Err(value);
+        }
+        let last_used = self.next_tick();
+
+        let old = self.map.insert(key, Entry { value, last_used, weight });
+        let owned = match old {
+            Some(ref old) => {
+                self.weight -= old.weight;
+                self.recency.remove(&old.last_used).unwrap()
+            }
+            None => key.to_owned(),
+        };
+        self.recency.insert(last_used, owned);
+        self.weight += weight;
+
+        self.evict();
+        Ok(old.map(|e| e.value))
+    }
+
+    /// Returns a reference to the value of the key, marking it as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let tick = self.next_tick();
+        let entry = self.map.get_mut(key)?;
+
+        let owned = self.recency.remove(&entry.last_used).unwrap();
+        self.recency.insert(tick, owned);
+        entry.last_used = tick;
+
+        Some(&entry.value)
+    }
+
+    /// Returns a reference to the value of the key, without marking it as used.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|e| &e.value)
+    }
+
+    /// Returns if the key is present in the map, without marking it as used.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Removes a key from the map, returning its value if it was in the map.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.map.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        self.weight -= entry.weight;
+        Some(entry.value)
+    }
+
+    /// Clears the map, removing all entries.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.recency.clear();
+        self.weight = 0;
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, sorted by key.
+    ///
+    /// The entries aren't marked as used.
+    pub fn find<'a>(&'a self, key: &K) -> Matches<'a, K, V> {
+        Matches {
+            matches: self.map.find(key),
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn evict(&mut self) {
+        while self.weight > self.max_weight {
+            let (_, key) = self.recency.pop_first().unwrap();
+            let entry = self.map.remove(key.borrow()).unwrap();
+            self.weight -= entry.weight;
+        }
+    }
+}
+
+/// An iterator over the entries matching a call to [`find`].
+///
+/// [`find`]: struct.BoundedRadixMap.html#method.find
+pub struct Matches<'a, K: 'a + Key + ?Sized, V: 'a> {
+    matches: MapMatches<'a, K, Entry<V>>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] Matches<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Matches<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next().map(|(k, e)| (k, &e.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundedRadixMap;
+
+    #[test]
+    fn it_evicts_the_least_recently_used_entries() {
+        let mut map = BoundedRadixMap::new(3);
+        map.insert("a", 0).unwrap();
+        map.insert("b", 1).unwrap();
+        map.insert("c", 2).unwrap();
+
+        map.get("a");
+        map.insert("b", 3).unwrap();
+        map.insert("d", 4).unwrap();
+
+        assert_eq!(map.len(), 3);
+        assert!(!map.contains_key("c"));
+        assert_eq!(map.peek("a"), Some(&0));
+        assert_eq!(map.peek("b"), Some(&3));
+        assert_eq!(map.peek("d"), Some(&4));
+    }
+
+    #[test]
+    fn it_keeps_track_of_the_weight() {
+        let mut map = BoundedRadixMap::with_weigher(10, |_: &[u8], v: &Vec<u8>| v.len());
+        map.insert(b"a", vec![0; 4]).unwrap();
+        map.insert(b"b", vec![0; 4]).unwrap();
+        assert_eq!(map.weight(), 8);
+
+        map.insert(b"a", vec![0; 2]).unwrap();
+        assert_eq!(map.weight(), 6);
+
+        map.remove(b"b");
+        assert_eq!(map.weight(), 2);
+
+        // too heavy to be kept at all, and refused without evicting anything
+        assert_eq!(map.insert(b"c", vec![0; 11]), Err(vec![0; 11]));
+        assert_eq!(map.insert(b"a", vec![0; 11]), Err(vec![0; 11]));
+        assert_eq!(map.weight(), 2);
+        assert_eq!(map.peek(b"a"), Some(&vec![0; 2]));
+        assert!(!map.contains_key(b"c"));
+    }
+
+    #[test]
+    fn it_does_not_mark_entries_as_used_on_peek() {
+        let mut map = BoundedRadixMap::new(2);
+        map.insert("a", 0).unwrap();
+        map.insert("b", 1).unwrap();
+
+        map.peek("a");
+        assert_eq!(map.find("").count(), 2);
+        map.insert("c", 2).unwrap();
+
+        assert!(!map.contains_key("a"));
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use map::{RadixMap, Matches as MapMatches};
+
+/// An encoding of keys, e.g. to compress long keys sharing common substrings.
+///
+/// Encodings must be injective, so that keys can be decoded. Prefix searches also rely on
+/// [`encode_prefix`], for encodings where a prefix of a key isn't encoded like the start of the
+/// key.
+///
+/// [`encode_prefix`]: #tymethod.encode_prefix
+pub trait KeyCodec {
+    /// Encodes a key.
+    fn encode(&self, key: &[u8]) -> Vec<u8>;
+
+    /// Decodes an encoded key.
+    fn decode(&self, encoded: &[u8]) -> Vec<u8>;
+
+    /// Encodes the longest prefix of `prefix` whose encoding starts the encoding of every key
+    /// starting with `prefix`, returning that encoding and the length of the prefix it encodes.
+    fn encode_prefix(&self, prefix: &[u8]) -> (Vec<u8>, usize);
+}
+
+const ESCAPE: u8 = 0;
+
+/// A codec replacing substrings from a dictionary (e.g. `https://`, `.com/`) by 2 bytes.
+///
+/// Keys are scanned from left to right, the longest token starting at each position being
+/// replaced by an escape byte followed by its index. The escape byte itself (`\0`) takes 2 bytes,
+/// but is unusual in text keys.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::codec::{KeyCodec, DictionaryCodec};
+///
+/// let codec = DictionaryCodec::new(&["https://", "www.", ".com/"]);
+///
+/// let encoded = codec.encode(b"https://www.rust-lang.com/");
+/// assert_eq!(encoded.len(), 15);
+/// assert_eq!(codec.decode(&encoded), b"https://www.rust-lang.com/");
+/// ```
+#[derive(Clone, Debug)]
+pub struct DictionaryCodec {
+    tokens: Vec<Vec<u8>>,
+}
+
+impl DictionaryCodec {
+    /// Makes a codec replacing the given tokens.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 255 tokens, or if one of them is empty.
+    pub fn new<T: AsRef<[u8]>>(tokens: &[T]) -> DictionaryCodec {
+        assert!(tokens.len() < 256, "a dictionary holds at most 255 tokens");
+
+        let tokens: Vec<Vec<u8>> = tokens.iter().map(|t| t.as_ref().to_vec()).collect();
+        assert!(tokens.iter().all(|t| !t.is_empty()), "tokens can't be empty");
+
+        DictionaryCodec { tokens }
+    }
+
+    // the longest token starting `rest`, along with its index
+    fn longest_token(&self, rest: &[u8]) -> Option<(usize, &[u8])> {
+        self.tokens.iter().enumerate()
+            .filter(|(_, t)| rest.starts_with(t))
+            .max_by_key(|(_, t)| t.len())
+            .map(|(i, t)| (i, &t[..]))
+    }
+
+    fn encode_token(&self, rest: &[u8], encoded: &mut Vec<u8>) -> usize {
+        match self.longest_token(rest) {
+            Some((i, token)) => {
+                encoded.extend_from_slice(&[ESCAPE, i as u8 + 1]);
+                token.len()
+            }
+            None if rest[0] == ESCAPE => {
+                encoded.extend_from_slice(&[ESCAPE, ESCAPE]);
+                1
+            }
+            None => {
+                encoded.push(rest[0]);
+                1
+            }
+        }
+    }
+}
+
+impl KeyCodec for DictionaryCodec {
+    fn encode(&self, key: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(key.len());
+        let mut i = 0;
+        while i < key.len() {
+            i += self.encode_token(&key[i..], &mut encoded);
+        }
+        encoded
+    }
+
+    fn decode(&self, encoded: &[u8]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(encoded.len());
+        let mut i = 0;
+        while i < encoded.len() {
+            if encoded[i] != ESCAPE {
+                key.push(encoded[i]);
+                i += 1;
+                continue;
+            }
+
+            match encoded.get(i + 1) {
+                Some(&ESCAPE) => key.push(ESCAPE),
+                Some(&t)      => key.extend_from_slice(&self.tokens[t as usize - 1]),
+                None          => panic!("truncated escape sequence"),
+            }
+            i += 2;
+        }
+        key
+    }
+
+    fn encode_prefix(&self, prefix: &[u8]) -> (Vec<u8>, usize) {
+        let mut encoded = Vec::with_capacity(prefix.len());
+        let mut i = 0;
+        while i < prefix.len() {
+            // a longer token could start here in a key extending the prefix
+            let rest = &prefix[i..];
+            if self.tokens.iter().any(|t| t.len() > rest.len() && t.starts_with(rest)) {
+                break;
+            }
+            i += self.encode_token(rest, &mut encoded);
+        }
+        (encoded, i)
+    }
+}
+
+/// A codec permuting the first byte of keys by a keyed hash, keeping the rest of them as is.
+///
+/// The children of the root are then ordered by a secret permutation, so that attacker-chosen
+/// keys can't be crafted to pile up at predictable positions of the tree. The lexicographic order
+/// is restored by [`CodecMap::iter_sorted`].
+///
+/// [`CodecMap::iter_sorted`]: struct.CodecMap.html#method.iter_sorted
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::codec::{KeyCodec, FrontPermutation};
+///
+/// let codec = FrontPermutation::new();
+///
+/// let encoded = codec.encode(b"abc");
+/// assert_eq!(&encoded[1..], b"bc");
+/// assert_eq!(codec.decode(&encoded), b"abc");
+/// ```
+#[derive(Clone)]
+pub struct FrontPermutation {
+    forward: [u8; 256],
+    backward: [u8; 256],
+}
+
+impl FrontPermutation {
+    /// Makes a codec keyed by random SipHash keys, like a `HashMap`.
+    pub fn new() -> FrontPermutation {
+        FrontPermutation::with_hasher(&RandomState::new())
+    }
+
+    /// Makes a codec keyed by the given hasher builder, e.g. to share a permutation between
+    /// processes.
+    pub fn with_hasher<S: BuildHasher>(hash_builder: &S) -> FrontPermutation {
+        let mut bytes: Vec<u8> = (0..=255).collect();
+        bytes.sort_by_key(|&b| (hash_builder.hash_one(b), b));
+
+        let mut forward = [0; 256];
+        let mut backward = [0; 256];
+        for (i, &b) in bytes.iter().enumerate() {
+            forward[b as usize] = i as u8;
+            backward[i] = b;
+        }
+
+        FrontPermutation { forward, backward }
+    }
+
+    fn permute(&self, key: &[u8], permutation: &[u8; 256]) -> Vec<u8> {
+        let mut permuted = key.to_vec();
+        if let Some(first) = permuted.first_mut() {
+            *first = permutation[*first as usize];
+        }
+        permuted
+    }
+}
+
+impl Default for FrontPermutation {
+    fn default() -> FrontPermutation {
+        FrontPermutation::new()
+    }
+}
+
+impl KeyCodec for FrontPermutation {
+    fn encode(&self, key: &[u8]) -> Vec<u8> {
+        self.permute(key, &self.forward)
+    }
+
+    fn decode(&self, encoded: &[u8]) -> Vec<u8> {
+        self.permute(encoded, &self.backward)
+    }
+
+    fn encode_prefix(&self, prefix: &[u8]) -> (Vec<u8>, usize) {
+        (self.encode(prefix), prefix.len())
+    }
+}
+
+/// A map from `str` keys, storing them encoded by a [`KeyCodec`].
+///
+/// Keys are sorted by their encoding, which may not be their lexicographic order.
+///
+/// [`KeyCodec`]: trait.KeyCodec.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::codec::{CodecMap, DictionaryCodec};
+///
+/// let mut hits = CodecMap::new(DictionaryCodec::new(&["https://", "www.", "example.com/"]));
+/// hits.insert("https://www.example.com/", 1);
+/// hits.insert("https://www.example.com/about", 2);
+/// hits.insert("https://example.com/", 3);
+///
+/// assert_eq!(hits.get("https://www.example.com/about"), Some(&2));
+///
+/// let matches: Vec<_> = hits.find("https://www.ex").map(|(k, _)| k).collect();
+/// assert_eq!(matches, vec!["https://www.example.com/", "https://www.example.com/about"]);
+/// ```
+pub struct CodecMap<C: KeyCodec, V> {
+    map: RadixMap<[u8], V>,
+    codec: C,
+}
+
+impl<C: KeyCodec, V> CodecMap<C, V> {
+    /// Makes a new empty map, encoding its keys with `codec`.
+    pub fn new(codec: C) -> CodecMap<C, V> {
+        CodecMap {
+            map: RadixMap::new(),
+            codec,
+        }
+    }
+
+    /// Returns the codec of the map.
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns the number of keys in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        self.map.insert(&self.codec.encode(key.as_bytes()), value)
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.map.get(&self.codec.encode(key.as_bytes()))
+    }
+
+    /// Removes a key from the map, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.map.remove(&self.codec.encode(key.as_bytes()))
+    }
+
+    /// Gets an iterator over the entries whose key starts with `prefix`, sorted by encoded key.
+    ///
+    /// The map is descended along the encoding of the prefix (see `KeyCodec::encode_prefix`),
+    /// then the matches are filtered on the rest of the prefix.
+    pub fn find<'a>(&'a self, prefix: &str) -> Matches<'a, C, V> {
+        let (encoded, _) = self.codec.encode_prefix(prefix.as_bytes());
+        Matches {
+            matches: self.map.find(&encoded),
+            codec: &self.codec,
+            prefix: prefix.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl<V> CodecMap<FrontPermutation, V> {
+    /// Gets an iterator over the entries of the map, sorted by key.
+    ///
+    /// The subtrees of the first bytes are visited in their original order, which takes a lookup
+    /// for each of the 256 possible bytes.
+    pub fn iter_sorted<'a>(&'a self) -> impl Iterator<Item=(String, &'a V)> + 'a {
+        let empty = self.map.get(&[][..]).map(|value| (String::new(), value));
+        let rest = (0..=255u8).flat_map(move |b| {
+            self.map.find(&[self.codec.forward[b as usize]][..]).map(move |(encoded, value)| {
+                let key = self.codec.decode(&encoded);
+                (String::from_utf8(key).expect("keys should be valid UTF-8"), value)
+            })
+        });
+        empty.into_iter().chain(rest)
+    }
+}
+
+/// An iterator over the entries matching a call to [`find`].
+///
+/// [`find`]: struct.CodecMap.html#method.find
+pub struct Matches<'a, C: 'a + KeyCodec, V: 'a> {
+    matches: MapMatches<'a, [u8], V>,
+    codec: &'a C,
+    prefix: Vec<u8>,
+}
+
+debug_iterator!(['a, C: 'a + KeyCodec, V: 'a] Matches<'a, C, V>);
+
+impl<'a, C: 'a + KeyCodec, V: 'a> Iterator for Matches<'a, C, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (encoded, value) = self.matches.next()?;
+            let key = self.codec.decode(&encoded);
+            if key.starts_with(&self.prefix) {
+                let key = String::from_utf8(key).expect("keys should be valid UTF-8");
+                return Some((key, value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::RandomState;
+
+    use super::{KeyCodec, DictionaryCodec, FrontPermutation, CodecMap};
+
+    const URLS: [&str; 6] = [
+        "https://www.example.com/",
+        "https://www.example.com/index.html",
+        "https://www.example.org/",
+        "http://example.com/\0",
+        "https://docs.example.com/",
+        "ftp://files.example.com/",
+    ];
+
+    fn codec() -> DictionaryCodec {
+        DictionaryCodec::new(&["http://", "https://", "https://www.", "www.", ".com/", ".org/"])
+    }
+
+    #[test]
+    fn it_compresses_keys() {
+        let codec = codec();
+        for url in URLS.iter() {
+            assert_eq!(codec.decode(&codec.encode(url.as_bytes())), url.as_bytes());
+        }
+
+        let (raw, encoded) = URLS.iter()
+            .fold((0, 0), |(r, e), url| (r + url.len(), e + codec.encode(url.as_bytes()).len()));
+        assert!(encoded * 3 < raw * 2, "{} bytes encoded in {}", raw, encoded);
+    }
+
+    #[test]
+    fn it_finds_prefixes_ending_inside_tokens() {
+        let mut map = CodecMap::new(codec());
+        for (i, url) in URLS.iter().enumerate() {
+            map.insert(url, i);
+        }
+
+        for prefix in ["", "h", "http", "https:/", "https://", "https://w", "https://www.example.c", "ftp"].iter() {
+            let mut found: Vec<_> = map.find(prefix).map(|(k, _)| k).collect();
+            found.sort();
+
+            let mut expected: Vec<_> = URLS.iter()
+                .filter(|u| u.starts_with(prefix))
+                .map(|u| u.to_string())
+                .collect();
+            expected.sort();
+
+            assert_eq!(found, expected, "prefix {:?}", prefix);
+        }
+    }
+
+    #[test]
+    fn it_encodes_stable_prefixes() {
+        let codec = codec();
+        assert_eq!(codec.encode_prefix(b"https:/"), (vec![], 0));
+        assert_eq!(codec.encode_prefix(b"https://www.ex"), (codec.encode(b"https://www.ex"), 14));
+        assert_eq!(codec.encode_prefix(b"ab.co").1, 2);
+    }
+
+    #[test]
+    fn it_restores_the_order_of_permuted_keys() {
+        let hasher = RandomState::new();
+        let codec = FrontPermutation::with_hasher(&hasher);
+        assert_eq!(codec.encode(b"abc"), FrontPermutation::with_hasher(&hasher).encode(b"abc"));
+
+        let mut map = CodecMap::new(codec);
+        let mut keys: Vec<String> = (0..200u32)
+            .map(|i| format!("{}{}", (b' ' + (i * 7 % 95) as u8) as char, i))
+            .collect();
+        keys.push(String::new());
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(key, i);
+        }
+        keys.sort();
+
+        let sorted: Vec<_> = map.iter_sorted().map(|(k, _)| k).collect();
+        assert_eq!(sorted, keys);
+
+        let found: Vec<_> = map.find("a").map(|(k, _)| k).collect();
+        assert_eq!(found, keys.iter().filter(|k| k.starts_with('a')).cloned().collect::<Vec<_>>());
+    }
+}
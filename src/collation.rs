@@ -0,0 +1,216 @@
+use std::iter;
+
+use icu_collator::{Collator, CollatorBorrowed};
+
+use map::RadixMap;
+
+/// A collation, ordering strings by binary sort keys, e.g. following the rules of a locale.
+///
+/// Strings are sorted like their sort keys, compared as bytes. Several strings may share a sort
+/// key (e.g. when ignoring case), in which case they are sorted by their bytes.
+///
+/// This is implemented for the collators of [ICU4X], and for closures (see [`CollatedMap`]).
+///
+/// [ICU4X]: https://docs.rs/icu_collator
+/// [`CollatedMap`]: struct.CollatedMap.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// extern crate icu_collator;
+/// extern crate panoradix;
+///
+/// use icu_collator::Collator;
+/// use icu_collator::options::{CollatorOptions, Strength};
+/// use panoradix::collation::CollatedMap;
+///
+/// // the root collation, ignoring case and accents
+/// let mut options = CollatorOptions::default();
+/// options.strength = Some(Strength::Primary);
+/// let collator = Collator::try_new(Default::default(), options).unwrap();
+///
+/// let mut words = CollatedMap::new(collator);
+/// for word in &["zebra", "Äpfel", "apple", "Zürich"] {
+///     words.insert(word, ());
+/// }
+///
+/// let sorted: Vec<_> = words.iter().map(|(word, _)| word).collect();
+/// assert_eq!(sorted, vec!["Äpfel", "apple", "zebra", "Zürich"]);
+/// ```
+pub trait Collation {
+    /// Computes the sort key of a string.
+    fn sort_key(&self, s: &str) -> Vec<u8>;
+}
+
+impl<F: Fn(&str) -> Vec<u8>> Collation for F {
+    fn sort_key(&self, s: &str) -> Vec<u8> {
+        self(s)
+    }
+}
+
+impl<'a> Collation for CollatorBorrowed<'a> {
+    fn sort_key(&self, s: &str) -> Vec<u8> {
+        let mut key = Vec::new();
+        let Ok(()) = self.write_sort_key_to(s, &mut key);
+        key
+    }
+}
+
+impl Collation for Collator {
+    fn sort_key(&self, s: &str) -> Vec<u8> {
+        self.as_borrowed().sort_key(s)
+    }
+}
+
+/// A map from strings to values, sorted by a [`Collation`] of the strings.
+///
+/// The tree is keyed by the sort keys, which are computed once when inserting, while the strings
+/// are kept along with their value.
+///
+/// [`Collation`]: trait.Collation.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::collation::CollatedMap;
+///
+/// // letters first, ignoring case, then the rest
+/// let collation = |s: &str| s.chars()
+///     .flat_map(|c| if c.is_alphabetic() { c.to_lowercase().collect() } else { vec!['~', c] })
+///     .collect::<String>()
+///     .into_bytes();
+///
+/// let mut cities = CollatedMap::new(collation);
+/// for city in &["berlin", "Amsterdam", "Bruxelles", "_test", "athens"] {
+///     cities.insert(city, ());
+/// }
+///
+/// let sorted: Vec<_> = cities.iter().map(|(city, _)| city).collect();
+/// assert_eq!(sorted, vec!["Amsterdam", "athens", "berlin", "Bruxelles", "_test"]);
+///
+/// let from_b: Vec<_> = cities.range("B", "C").map(|(city, _)| city).collect();
+/// assert_eq!(from_b, vec!["berlin", "Bruxelles"]);
+/// ```
+pub struct CollatedMap<V, C: Collation> {
+    // entries by sort key, sorted by string
+    map: RadixMap<[u8], Vec<(String, V)>>,
+    collation: C,
+    len: usize,
+}
+
+impl<V, C: Collation> CollatedMap<V, C> {
+    /// Makes a new empty map, sorted by the given collation.
+    pub fn new(collation: C) -> CollatedMap<V, C> {
+        CollatedMap {
+            map: RadixMap::new(),
+            collation,
+            len: 0,
+        }
+    }
+
+    /// Returns the collation of the map.
+    pub fn collation(&self) -> &C {
+        &self.collation
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let sort_key = self.collation.sort_key(key);
+        let entries = match self.map.get_mut(&sort_key) {
+            Some(entries) => entries,
+            None => {
+                self.map.insert(&sort_key, vec![(key.to_string(), value)]);
+                self.len += 1;
+                return None;
+            },
+        };
+
+        match entries.binary_search_by(|(k, _)| k.as_str().cmp(key)) {
+            Ok(i)  => Some(::std::mem::replace(&mut entries[i].1, value)),
+            Err(i) => {
+                entries.insert(i, (key.to_string(), value));
+                self.len += 1;
+                None
+            },
+        }
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let entries = self.map.get(&self.collation.sort_key(key))?;
+        let i = entries.binary_search_by(|(k, _)| k.as_str().cmp(key)).ok()?;
+        Some(&entries[i].1)
+    }
+
+    /// Removes a key from the map, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let sort_key = self.collation.sort_key(key);
+        let entries = self.map.get_mut(&sort_key)?;
+        let i = entries.binary_search_by(|(k, _)| k.as_str().cmp(key)).ok()?;
+
+        let (_, value) = entries.remove(i);
+        if entries.is_empty() {
+            self.map.remove(&sort_key);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Gets an iterator over the entries of the map, sorted by the collation of their key.
+    pub fn iter(&self) -> impl Iterator<Item=(&str, &V)> {
+        self.map.values().flat_map(|entries| entries.iter().map(|(k, v)| (k.as_str(), v)))
+    }
+
+    /// Gets an iterator over the entries whose key collates between `start` (inclusive) and
+    /// `end` (exclusive), sorted by the collation of their key.
+    ///
+    /// The bounds are compared by their sort key only, e.g. keys sharing the sort key of `start`
+    /// are all included. The sort keys of the entries are compared as they are lent by the tree,
+    /// without being copied.
+    pub fn range(&self, start: &str, end: &str) -> impl Iterator<Item=(&str, &V)> {
+        let (start, end) = (self.collation.sort_key(start), self.collation.sort_key(end));
+        let mut sort_keys = self.map.tree.iter_from(&start);
+        iter::from_fn(move || sort_keys.next_raw().filter(|&(k, _)| k < &end[..]).map(|(_, e)| e))
+            .flat_map(|entries| entries.iter().map(|(k, v)| (k.as_str(), v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CollatedMap;
+
+    #[test]
+    fn it_keeps_keys_sharing_a_sort_key() {
+        let mut map = CollatedMap::new(|s: &str| s.to_lowercase().into_bytes());
+        assert_eq!(map.insert("b", 1), None);
+        assert_eq!(map.insert("B", 2), None);
+        assert_eq!(map.insert("a", 3), None);
+        assert_eq!(map.insert("b", 4), Some(1));
+        assert_eq!(map.len(), 3);
+
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![("a", &3), ("B", &2), ("b", &4)]);
+        assert_eq!(map.range("b", "c").count(), 2);
+        assert_eq!(map.get("B"), Some(&2));
+        assert_eq!(map.get("A"), None);
+
+        assert_eq!(map.remove("b"), Some(4));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.remove("B"), Some(2));
+        assert_eq!(map.len(), 1);
+        assert!(map.map.get(b"b").is_none());
+    }
+}
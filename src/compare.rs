@@ -0,0 +1,262 @@
+use std::ops::BitOr;
+use std::str;
+
+use key::Key;
+
+/// A tolerant comparison between a query and the keys of a map, see [`RadixMap::find_with`].
+///
+/// The comparison is driven by the tree: the components of the stored keys are fed one at a time
+/// to `step`, so that a query can match several branches of the tree without the stored keys
+/// being normalized.
+///
+/// [`RadixMap::find_with`]: ../map/struct.RadixMap.html#method.find_with
+pub trait Compare<K: Key + ?Sized> {
+    /// The state of a comparison, cloned wherever the tree branches.
+    type State: Clone;
+
+    /// Starts comparing a query.
+    fn start(&self, query: &K) -> Self::State;
+
+    /// Feeds the next component of a stored key, returning `false` if it can't match the query.
+    fn step(&self, query: &K, state: &mut Self::State, component: &K::Component) -> bool;
+
+    /// Returns if the whole query has been matched.
+    fn is_complete(&self, query: &K, state: &Self::State) -> bool;
+}
+
+/// Which differences between characters to ignore when comparing `str` keys.
+///
+/// Modes can be combined with `|`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::RadixSet;
+/// use panoradix::compare::CompareMode;
+///
+/// let set: RadixSet<str> = vec!["Élan", "elastic", "Eleven"].into_iter().collect();
+///
+/// let matches: Vec<_> = set.find_with("ela", CompareMode::IGNORE_CASE).collect();
+/// assert_eq!(matches, vec!["elastic"]);
+///
+/// let matches: Vec<_> = set.find_with("ela", CompareMode::IGNORE_CASE | CompareMode::IGNORE_DIACRITICS).collect();
+/// assert_eq!(matches, vec!["elastic", "Élan"]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompareMode {
+    ignore_case: bool,
+    ignore_diacritics: bool,
+}
+
+impl CompareMode {
+    /// Compares characters exactly.
+    pub const EXACT: CompareMode = CompareMode { ignore_case: false, ignore_diacritics: false };
+
+    /// Ignores the case of characters, as given by `char::to_lowercase`.
+    pub const IGNORE_CASE: CompareMode = CompareMode { ignore_case: true, ignore_diacritics: false };
+
+    /// Ignores diacritics on latin letters (e.g. `é` matches `e`), as well as combining marks.
+    pub const IGNORE_DIACRITICS: CompareMode = CompareMode { ignore_case: false, ignore_diacritics: true };
+
+    fn fold(&self, c: char) -> Folded {
+        let mut folded = Folded { chars: ['\0'; 3], len: 0, pos: 0 };
+
+        let c = if self.ignore_diacritics {
+            if is_combining_mark(c) {
+                return folded;
+            }
+            strip_diacritic(c)
+        } else {
+            c
+        };
+
+        if self.ignore_case {
+            for l in c.to_lowercase() {
+                folded.chars[folded.len] = l;
+                folded.len += 1;
+            }
+        } else {
+            folded.chars[0] = c;
+            folded.len = 1;
+        }
+
+        folded
+    }
+
+    /// Moves past the query characters that are folded to nothing.
+    fn skip_ignored(&self, query: &str, state: &mut FoldState) {
+        while state.folded_pos == 0 {
+            match query[state.query_pos..].chars().next() {
+                Some(c) if self.fold(c).len == 0 => state.query_pos += c.len_utf8(),
+                _                                => break,
+            }
+        }
+    }
+}
+
+impl BitOr for CompareMode {
+    type Output = CompareMode;
+
+    fn bitor(self, other: CompareMode) -> CompareMode {
+        CompareMode {
+            ignore_case: self.ignore_case || other.ignore_case,
+            ignore_diacritics: self.ignore_diacritics || other.ignore_diacritics,
+        }
+    }
+}
+
+/// The state of a comparison of `str` keys with a [`CompareMode`].
+///
+/// [`CompareMode`]: struct.CompareMode.html
+#[derive(Clone, Copy, Debug)]
+pub struct FoldState {
+    // position of the query character being matched, and of the next folded char within it
+    query_pos: usize,
+    folded_pos: usize,
+    // bytes of a stored character split between several edges
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl Compare<str> for CompareMode {
+    type State = FoldState;
+
+    fn start(&self, query: &str) -> FoldState {
+        let mut state = FoldState { query_pos: 0, folded_pos: 0, pending: [0; 4], pending_len: 0 };
+        self.skip_ignored(query, &mut state);
+        state
+    }
+
+    fn step(&self, query: &str, state: &mut FoldState, byte: &u8) -> bool {
+        state.pending[state.pending_len] = *byte;
+        state.pending_len += 1;
+        if state.pending_len < utf8_width(state.pending[0]) {
+            return true;
+        }
+
+        let stored = match str::from_utf8(&state.pending[..state.pending_len]) {
+            Ok(s)  => s.chars().next().unwrap(),
+            Err(_) => return false,
+        };
+        state.pending_len = 0;
+
+        for c in self.fold(stored) {
+            let q = match query[state.query_pos..].chars().next() {
+                Some(q) => q,
+                // the query ends in the middle of the folded character, which still matches
+                None    => return true,
+            };
+
+            let folded = self.fold(q);
+            if folded.chars[state.folded_pos] != c {
+                return false;
+            }
+
+            state.folded_pos += 1;
+            if state.folded_pos == folded.len {
+                state.query_pos += q.len_utf8();
+                state.folded_pos = 0;
+                self.skip_ignored(query, state);
+            }
+        }
+
+        true
+    }
+
+    fn is_complete(&self, query: &str, state: &FoldState) -> bool {
+        state.query_pos == query.len() && state.pending_len == 0
+    }
+}
+
+/// The up to 3 characters a character is folded to.
+struct Folded {
+    chars: [char; 3],
+    len: usize,
+    pos: usize,
+}
+
+impl Iterator for Folded {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.pos < self.len {
+            self.pos += 1;
+            Some(self.chars[self.pos - 1])
+        } else {
+            None
+        }
+    }
+}
+
+fn utf8_width(first: u8) -> usize {
+    match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _           => 4,
+    }
+}
+
+fn is_combining_mark(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+// base letters of the Latin-1 Supplement and Latin Extended-A blocks
+const LATIN_1: &str = "AAAAAAÆCEEEEIIIIÐNOOOOO×OUUUUYÞßaaaaaaæceeeeiiiiðnooooo÷ouuuuyþy";
+const LATIN_EXTENDED_A: &str = "AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIiĲĳJjKkĸLlLlLlLlLlNnNnNnŉŊŋOoOoOoŒœRrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs";
+
+fn strip_diacritic(c: char) -> char {
+    let (table, first) = match c {
+        '\u{c0}'..='\u{ff}'   => (LATIN_1, 0xc0),
+        '\u{100}'..='\u{17f}' => (LATIN_EXTENDED_A, 0x100),
+        _                     => return c,
+    };
+    table.chars().nth(c as usize - first).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use map::RadixMap;
+    use super::CompareMode;
+
+    fn find(map: &RadixMap<str, usize>, query: &str, mode: CompareMode) -> Vec<String> {
+        map.find_with(query, mode).map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn it_matches_exactly() {
+        let map: RadixMap<str, usize> = vec![("abc", 0), ("Abc", 1), ("abd", 2)].into_iter().collect();
+        assert_eq!(find(&map, "ab", CompareMode::EXACT), vec!["abc", "abd"]);
+        assert_eq!(find(&map, "", CompareMode::EXACT), vec!["Abc", "abc", "abd"]);
+    }
+
+    #[test]
+    fn it_branches_on_characters_folded_together() {
+        let map: RadixMap<str, usize> = vec![("ÉTÉ", 0), ("été", 1), ("ete", 2), ("Etna", 3), ("eu", 4)]
+            .into_iter()
+            .collect();
+
+        assert_eq!(find(&map, "ét", CompareMode::IGNORE_CASE), vec!["ÉTÉ", "été"]);
+        assert_eq!(find(&map, "et", CompareMode::IGNORE_DIACRITICS), vec!["ete", "été"]);
+        assert_eq!(find(&map, "ETE", CompareMode::IGNORE_CASE | CompareMode::IGNORE_DIACRITICS),
+                   vec!["ete", "ÉTÉ", "été"]);
+    }
+
+    #[test]
+    fn it_ignores_combining_marks() {
+        // "e" followed by a combining acute accent
+        let map: RadixMap<str, usize> = vec![("e\u{301}t", 0), ("eu", 1)].into_iter().collect();
+        assert_eq!(find(&map, "et", CompareMode::IGNORE_DIACRITICS), vec!["e\u{301}t"]);
+        assert_eq!(find(&map, "ét", CompareMode::IGNORE_DIACRITICS), vec!["e\u{301}t"]);
+    }
+
+    #[test]
+    fn it_matches_characters_split_between_edges() {
+        // "é" and "è" share their first byte, which ends up alone on an edge
+        let map: RadixMap<str, usize> = vec![("é", 0), ("è", 1)].into_iter().collect();
+        assert_eq!(find(&map, "E", CompareMode::IGNORE_CASE | CompareMode::IGNORE_DIACRITICS), vec!["è", "é"]);
+        assert_eq!(find(&map, "É", CompareMode::IGNORE_CASE), vec!["é"]);
+    }
+}
@@ -0,0 +1,96 @@
+use std::cell::Cell;
+
+thread_local! {
+    static OPS: Cell<OpCounts> = const { Cell::new(OpCounts { edges: 0, components: 0 }) };
+}
+
+/// Record an edge of a tree compared with a key, `matched` components of the key being found
+/// equal to its label.
+pub(crate) fn count_edge(matched: usize) {
+    OPS.with(|ops| {
+        let mut counts = ops.get();
+        counts.edges += 1;
+        counts.components += matched as u64;
+        ops.set(counts);
+    });
+}
+
+/// Counts of the work done by the lookups and updates of the maps, to check their complexity.
+///
+/// Each edge compared with a key along a descent is counted, along with the components of the key
+/// found equal to its label. Looking up a key of length `L` compares at most `L + 1` edges and
+/// matches at most `L` components, whatever the size of the map: the counts only grow with the
+/// length of the keys. Finding the edge to follow at each node is a binary search on the first
+/// components of its edges, which isn't counted.
+///
+/// The counters are kept per thread, see [`measure`] and [`op_counts`].
+///
+/// [`measure`]: fn.measure.html
+/// [`op_counts`]: fn.op_counts.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::RadixMap;
+/// use panoradix::complexity;
+///
+/// let map: RadixMap<str, i32> = (0..1000).map(|i| (format!("key/{}", i), i)).collect();
+///
+/// let (value, counts) = complexity::measure(|| map.get("key/999").copied());
+/// assert_eq!(value, Some(999));
+/// assert_eq!(counts.components(), 7);
+/// assert!(counts.edges() <= 8);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpCounts {
+    edges: u64,
+    components: u64,
+}
+
+impl OpCounts {
+    /// Returns the number of edges compared with a key.
+    pub fn edges(&self) -> u64 {
+        self.edges
+    }
+
+    /// Returns the number of components of a key found equal to the labels of the edges.
+    pub fn components(&self) -> u64 {
+        self.components
+    }
+}
+
+/// Returns the counts of the work done by the maps on the current thread since it started.
+pub fn op_counts() -> OpCounts {
+    OPS.with(Cell::get)
+}
+
+/// Calls `f`, returning its result with the counts of the work done by the maps on the current
+/// thread meanwhile.
+pub fn measure<T, F: FnOnce() -> T>(f: F) -> (T, OpCounts) {
+    let before = op_counts();
+    let result = f();
+    let after = op_counts();
+    (result, OpCounts {
+        edges: after.edges - before.edges,
+        components: after.components - before.components,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_edge, measure};
+
+    #[test]
+    fn it_measures_the_work_of_a_closure() {
+        let ((), counts) = measure(|| {
+            count_edge(3);
+            count_edge(0);
+        });
+        assert_eq!((counts.edges(), counts.components()), (2, 3));
+
+        let ((), counts) = measure(|| ());
+        assert_eq!((counts.edges(), counts.components()), (0, 0));
+    }
+}
@@ -0,0 +1,290 @@
+use std::cmp::{self, Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::iter::FromIterator;
+
+use map::{RadixMap, Iter as MapIter};
+use key::Key;
+
+/// A map counting occurrences of keys, e.g. n-grams.
+///
+/// Counts are `u64`, missing keys having a count of 0.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::counter::RadixCounter;
+///
+/// let text = "the cat and the hat";
+/// let counter: RadixCounter<str> = text.split(' ').collect();
+///
+/// assert_eq!(counter.get("the"), 2);
+/// assert_eq!(counter.get("dog"), 0);
+/// assert_eq!(counter.prefix_sum("th"), 2);
+/// assert_eq!(counter.top_k(2), vec![("the".to_string(), 2), ("and".to_string(), 1)]);
+/// ```
+pub struct RadixCounter<K: Key + ?Sized> {
+    map: RadixMap<K, u64>,
+}
+
+impl<K: Key + ?Sized> RadixCounter<K> {
+    /// Makes a new empty counter.
+    pub fn new() -> RadixCounter<K> {
+        RadixCounter { map: RadixMap::new() }
+    }
+
+    /// Returns the number of keys counted.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no key was counted.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the count of a key.
+    pub fn get(&self, key: &K) -> u64 {
+        self.map.get(key).copied().unwrap_or(0)
+    }
+
+    /// Adds `by` to the count of a key, saturating at `u64::MAX`, and returns the new count.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::counter::RadixCounter;
+    ///
+    /// let mut counter = RadixCounter::new();
+    /// assert_eq!(counter.increment("a", 1), 1);
+    /// assert_eq!(counter.increment("a", u64::MAX), u64::MAX);
+    /// ```
+    pub fn increment(&mut self, key: &K, by: u64) -> u64 {
+        self.update(key, |count| count.saturating_add(by))
+    }
+
+    /// Adds `by` to the count of a key, wrapping around at `u64::MAX`, and returns the new count
+    /// along with whether it overflowed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::counter::RadixCounter;
+    ///
+    /// let mut counter = RadixCounter::new();
+    /// assert_eq!(counter.overflowing_increment("a", u64::MAX), (u64::MAX, false));
+    /// assert_eq!(counter.overflowing_increment("a", 2), (1, true));
+    /// ```
+    pub fn overflowing_increment(&mut self, key: &K, by: u64) -> (u64, bool) {
+        let mut overflowed = false;
+        let count = self.update(key, |count| {
+            let (count, o) = count.overflowing_add(by);
+            overflowed = o;
+            count
+        });
+        (count, overflowed)
+    }
+
+    /// Removes a key, returning its count.
+    pub fn remove(&mut self, key: &K) -> u64 {
+        let count = match self.map.get(key) {
+            Some(&count) => count,
+            None => return 0,
+        };
+        self.map.tree.add_to_sums(key.as_slice(), u128::from(count).wrapping_neg());
+        self.map.remove(key);
+        count
+    }
+
+    /// Returns the sum of the counts of the keys starting with `prefix`, saturating at
+    /// `u64::MAX`.
+    ///
+    /// The sums are maintained in the nodes of the tree, only the prefix is descended.
+    pub fn prefix_sum(&self, prefix: &K) -> u64 {
+        // the sums of the nodes are exact, `u128` can't wrap before `u64::MAX` keys
+        cmp::min(self.map.tree.sum(prefix.as_slice()), u128::from(u64::MAX)) as u64
+    }
+
+    /// Returns the `k` keys with the highest counts, sorted by decreasing count then by key.
+    ///
+    /// The counter is traversed once, and only the keys that are among the highest counts at
+    /// the time they are traversed are allocated.
+    pub fn top_k(&self, k: usize) -> Vec<(K::Owned, u64)> {
+        // there can't be more candidates than keys, whatever `k`
+        let k = k.min(self.len());
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // the top of the heap is the worst candidate so far
+        let mut heap: BinaryHeap<Candidate<K::Owned>> = BinaryHeap::with_capacity(k + 1);
+        let mut it = self.map.raw_iter();
+        let mut rank = 0;
+        while let Some((key, &count)) = it.next_entry() {
+            // keys are traversed in order, so ties are won by the candidates already in the heap
+            if heap.len() < k || heap.peek().is_some_and(|worst| count > worst.count) {
+                heap.push(Candidate { count, rank, key: K::from_vec(key.to_vec()) });
+                if heap.len() > k {
+                    heap.pop();
+                }
+            }
+            rank += 1;
+        }
+
+        heap.into_sorted_vec().into_iter()
+            .map(|c| (c.key, c.count))
+            .collect()
+    }
+
+    /// Gets an iterator over the keys and their counts, sorted by key.
+    pub fn iter(&self) -> MapIter<'_, K, u64> {
+        self.map.iter()
+    }
+
+    fn update<F>(&mut self, key: &K, f: F) -> u64
+        where F: FnOnce(u64) -> u64,
+    {
+        let (old, new) = match self.map.get_mut(key) {
+            Some(count) => {
+                let old = *count;
+                *count = f(old);
+                (old, *count)
+            },
+            None => {
+                let new = f(0);
+                self.map.insert(key, new);
+                (0, new)
+            },
+        };
+
+        self.map.tree.add_to_sums(key.as_slice(), u128::from(new).wrapping_sub(u128::from(old)));
+        new
+    }
+}
+
+impl<K: Key + ?Sized> Default for RadixCounter<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Key + ?Sized, T: AsRef<K>> FromIterator<T> for RadixCounter<K> {
+    fn from_iter<It>(iter: It) -> Self
+        where It: IntoIterator<Item=T>,
+    {
+        let mut counter = RadixCounter::new();
+        for key in iter {
+            counter.increment(key.as_ref(), 1);
+        }
+        counter
+    }
+}
+
+struct Candidate<O> {
+    count: u64,
+    rank: usize,
+    key: O,
+}
+
+impl<O> Candidate<O> {
+    fn order(&self) -> (Reverse<u64>, usize) {
+        (Reverse(self.count), self.rank)
+    }
+}
+
+impl<O> PartialEq for Candidate<O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.order() == other.order()
+    }
+}
+
+impl<O> Eq for Candidate<O> {}
+
+impl<O> PartialOrd for Candidate<O> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<O> Ord for Candidate<O> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order().cmp(&other.order())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RadixCounter;
+
+    #[test]
+    fn it_counts_keys() {
+        let mut counter = RadixCounter::<[u8]>::new();
+        counter.increment(b"ab", 2);
+        counter.increment(b"abc", 3);
+        counter.increment(b"ab", 1);
+        counter.increment(b"b", 1);
+
+        assert_eq!(counter.len(), 3);
+        assert_eq!(counter.get(b"ab"), 3);
+        assert_eq!(counter.prefix_sum(b"a"), 6);
+        assert_eq!(counter.prefix_sum(b""), 7);
+
+        assert_eq!(counter.remove(b"ab"), 3);
+        assert_eq!(counter.prefix_sum(b"a"), 3);
+    }
+
+    #[test]
+    fn it_saturates_prefix_sums() {
+        let mut counter = RadixCounter::<str>::new();
+        counter.increment("a", u64::MAX);
+        counter.increment("ab", 1);
+        assert_eq!(counter.prefix_sum("a"), u64::MAX);
+
+        // the sums aren't stuck at the saturation
+        counter.remove("a");
+        assert_eq!(counter.prefix_sum("a"), 1);
+    }
+
+    #[test]
+    fn it_maintains_prefix_sums_through_splits_and_merges() {
+        let mut counter = RadixCounter::<str>::new();
+        let mut expected = ::std::collections::BTreeMap::new();
+        let prefixes = ["", "a", "ab", "abc", "abcd", "abd", "b", "c"];
+
+        // "abcd" then "abc" and "ab" split its edge, "abd" branches inside it
+        for (i, &key) in ["abcd", "abc", "ab", "abd", "b", "abc", "a"].iter().enumerate() {
+            counter.increment(key, i as u64 + 1);
+            *expected.entry(key).or_insert(0) += i as u64 + 1;
+            for prefix in prefixes.iter() {
+                let sum: u64 = expected.iter().filter(|(k, _)| k.starts_with(prefix)).map(|(_, c)| c).sum();
+                assert_eq!(counter.prefix_sum(prefix), sum, "{:?}", prefix);
+            }
+        }
+
+        // removing "ab" then "abd" merges the edges back
+        for &key in ["ab", "abd", "abcd", "a"].iter() {
+            assert_eq!(counter.remove(key), expected.remove(key).unwrap());
+            for prefix in prefixes.iter() {
+                let sum: u64 = expected.iter().filter(|(k, _)| k.starts_with(prefix)).map(|(_, c)| c).sum();
+                assert_eq!(counter.prefix_sum(prefix), sum, "{:?}", prefix);
+            }
+        }
+        assert_eq!(counter.remove("ab"), 0);
+    }
+
+    #[test]
+    fn it_finds_the_highest_counts() {
+        let counter: RadixCounter<str> = "d c b a c b d d e".split(' ').collect();
+
+        assert_eq!(counter.top_k(0), vec![]);
+        assert_eq!(counter.top_k(1), vec![("d".to_string(), 3)]);
+        assert_eq!(counter.top_k(3), vec![("d".to_string(), 3), ("b".to_string(), 2), ("c".to_string(), 2)]);
+        assert_eq!(counter.top_k(10).len(), 5);
+        assert_eq!(counter.top_k(usize::MAX).len(), 5);
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use map::{RadixMap, Matches as MapMatches};
+
+/// An error returned when encoding a sequence holding something other than a nucleotide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidNucleotide {
+    /// The position of the invalid byte in the sequence.
+    pub position: usize,
+    /// The invalid byte.
+    pub byte: u8,
+}
+
+impl fmt::Display for InvalidNucleotide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid nucleotide {:?} at position {}", self.byte as char, self.position)
+    }
+}
+
+const NUCLEOTIDES: [u8; 4] = *b"ACGT";
+
+fn code(position: usize, byte: u8) -> Result<u8, InvalidNucleotide> {
+    match byte {
+        b'A' | b'a' => Ok(0),
+        b'C' | b'c' => Ok(1),
+        b'G' | b'g' => Ok(2),
+        b'T' | b't' => Ok(3),
+        _           => Err(InvalidNucleotide { position, byte }),
+    }
+}
+
+// packs up to 4 nucleotides in the high bits of a byte
+fn pack(position: usize, chunk: &[u8]) -> Result<u8, InvalidNucleotide> {
+    let mut byte = 0;
+    for (i, &b) in chunk.iter().enumerate() {
+        byte |= code(position + i, b)? << (6 - 2 * i);
+    }
+    Ok(byte)
+}
+
+/// Packs a sequence of nucleotides (`ACGT`, in any case), 4 per byte.
+///
+/// The last 0 to 3 nucleotides are packed in an extra byte, in its high bits, along with their
+/// number in its 2 low bits: a sequence of `n` nucleotides always takes `n / 4 + 1` bytes.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::dna;
+///
+/// assert_eq!(dna::encode(b"ACGTAC"), Ok(vec![0b00_01_10_11, 0b00_01_00_10]));
+/// assert_eq!(dna::encode(b""), Ok(vec![0]));
+/// assert!(dna::encode(b"ACNT").is_err());
+/// ```
+pub fn encode(sequence: &[u8]) -> Result<Vec<u8>, InvalidNucleotide> {
+    let mut packed = Vec::with_capacity(sequence.len() / 4 + 1);
+
+    let chunks = sequence.chunks_exact(4);
+    let tail = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        packed.push(pack(4 * i, chunk)?);
+    }
+    packed.push(pack(sequence.len() - tail.len(), tail)? | tail.len() as u8);
+
+    Ok(packed)
+}
+
+/// Unpacks a sequence packed by [`encode`], as upper-case ASCII.
+///
+/// [`encode`]: fn.encode.html
+pub fn decode(packed: &[u8]) -> Vec<u8> {
+    let (&tail, chunks) = match packed.split_last() {
+        Some(split) => split,
+        None        => return Vec::new(),
+    };
+
+    let mut sequence = Vec::with_capacity(4 * packed.len());
+    let unpack = |sequence: &mut Vec<u8>, byte: u8, count: usize| {
+        for i in 0..count {
+            sequence.push(NUCLEOTIDES[(byte >> (6 - 2 * i) & 0b11) as usize]);
+        }
+    };
+
+    for &byte in chunks.iter() {
+        unpack(&mut sequence, byte, 4);
+    }
+    unpack(&mut sequence, tail, (tail & 0b11) as usize);
+
+    sequence
+}
+
+/// A map from nucleotide sequences, e.g. k-mers, to values, storing keys packed by [`encode`].
+///
+/// Prefix searches work on nucleotides even when the prefix ends in the middle of a byte. Note
+/// that entries are sorted by packed key, which isn't the lexicographic order of the sequences:
+/// a sequence sorts after its extensions by 4 or more nucleotides, e.g. `A` after `AAAA`.
+///
+/// [`encode`]: fn.encode.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::dna::DnaMap;
+///
+/// let mut kmers = DnaMap::new();
+/// kmers.insert(b"ACGTACG", 1).unwrap();
+/// kmers.insert(b"ACGTTT", 2).unwrap();
+/// kmers.insert(b"ACTT", 3).unwrap();
+///
+/// assert_eq!(kmers.get(b"acttt"), None);
+/// assert_eq!(kmers.get(b"actt"), Some(&3));
+///
+/// let matches: Vec<_> = kmers.find(b"ACGTT").collect();
+/// assert_eq!(matches, vec![(b"ACGTTT".to_vec(), &2)]);
+/// ```
+pub struct DnaMap<V> {
+    map: RadixMap<[u8], V>,
+}
+
+impl<V> DnaMap<V> {
+    /// Makes a new empty map.
+    pub fn new() -> DnaMap<V> {
+        DnaMap { map: RadixMap::new() }
+    }
+
+    /// Returns the number of sequences in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no sequences.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Inserts a sequence-value pair into the map, returning the value previously stored for the
+    /// sequence, or an error if it isn't made of nucleotides.
+    pub fn insert(&mut self, sequence: &[u8], value: V) -> Result<Option<V>, InvalidNucleotide> {
+        Ok(self.map.insert(&encode(sequence)?, value))
+    }
+
+    /// Returns a reference to the value of a sequence.
+    pub fn get(&self, sequence: &[u8]) -> Option<&V> {
+        self.map.get(&encode(sequence).ok()?)
+    }
+
+    /// Removes a sequence from the map, returning its value.
+    pub fn remove(&mut self, sequence: &[u8]) -> Option<V> {
+        self.map.remove(&encode(sequence).ok()?)
+    }
+
+    /// Gets an iterator over the sequences starting with `prefix`, along with their
+    /// values, sorted by packed key.
+    ///
+    /// The map is descended along the bytes fully packed by the prefix, then the matches are
+    /// filtered on its last nucleotides.
+    pub fn find<'a>(&'a self, prefix: &[u8]) -> Matches<'a, V> {
+        let (full, tail) = match encode(prefix) {
+            Ok(mut packed) => { let tail = packed.pop().unwrap(); (packed, tail) },
+            Err(_)         => return Matches { matches: None, depth: 0, tail: 0 },
+        };
+
+        Matches {
+            matches: Some(self.map.find(&full)),
+            depth: full.len(),
+            tail,
+        }
+    }
+}
+
+impl<V> Default for DnaMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An iterator over the entries matching a call to [`find`].
+///
+/// [`find`]: struct.DnaMap.html#method.find
+pub struct Matches<'a, V: 'a> {
+    matches: Option<MapMatches<'a, [u8], V>>,
+    depth: usize,
+    tail: u8,
+}
+
+debug_iterator!(['a, V: 'a] Matches<'a, V>);
+
+impl<'a, V: 'a> Matches<'a, V> {
+    fn is_match(&self, packed: &[u8]) -> bool {
+        let count = self.tail & 0b11;
+        let mask = !(0xff >> (2 * count));
+
+        // shorter keys whose last byte packs the same bits as the prefix's full bytes
+        if packed.len() == self.depth {
+            return false;
+        }
+
+        // the byte after the prefix's full bytes is either fully packed or the key's last byte
+        let byte = packed[self.depth];
+        let is_last = packed.len() == self.depth + 1;
+        (!is_last || byte & 0b11 >= count) && byte & mask == self.tail & mask
+    }
+}
+
+impl<'a, V: 'a> Iterator for Matches<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (packed, value) = self.matches.as_mut()?.next()?;
+            if self.is_match(&packed) {
+                return Some((decode(&packed), value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, decode, DnaMap, InvalidNucleotide};
+
+    #[test]
+    fn it_packs_sequences() {
+        for sequence in ["", "A", "ACG", "ACGT", "TTTTG", "GATTACAGATTACA"].iter() {
+            let packed = encode(sequence.as_bytes()).unwrap();
+            assert_eq!(packed.len(), sequence.len() / 4 + 1);
+            assert_eq!(decode(&packed), sequence.as_bytes());
+        }
+
+        assert_eq!(encode(b"acgt"), encode(b"ACGT"));
+        assert_eq!(encode(b"ACGTU"), Err(InvalidNucleotide { position: 4, byte: b'U' }));
+    }
+
+    #[test]
+    fn it_finds_prefixes_ending_inside_a_byte() {
+        let sequences = ["", "A", "AC", "ACG", "ACGT", "ACGTA", "ACGTCA", "ACTT", "AAAA", "C", "CA"];
+        let mut map = DnaMap::new();
+        for (i, sequence) in sequences.iter().enumerate() {
+            map.insert(sequence.as_bytes(), i).unwrap();
+        }
+
+        for prefix in ["", "A", "AC", "ACG", "ACGT", "ACGTC", "ACT", "C", "G", "AAAAA"].iter() {
+            let mut found: Vec<_> = map.find(prefix.as_bytes()).map(|(k, _)| k).collect();
+            found.sort();
+
+            let mut expected: Vec<_> = sequences.iter()
+                .filter(|s| s.starts_with(prefix))
+                .map(|s| s.as_bytes().to_vec())
+                .collect();
+            expected.sort();
+
+            assert_eq!(found, expected, "prefix {:?}", prefix);
+        }
+
+        assert_eq!(map.find(b"AN").count(), 0);
+    }
+}
@@ -0,0 +1,149 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use key::Key;
+
+/// A [Bloom filter](https://en.wikipedia.org/wiki/Bloom_filter) of keys, built by
+/// [`RadixSet::to_filter`].
+///
+/// A filter answers membership queries with no false negatives and a small rate of false
+/// positives, while being far more compact than the set it was built from. Its contents are
+/// plain words (see [`bits`] and [`from_parts`]) and the hashing doesn't depend on the platform,
+/// so it can be shipped to other processes.
+///
+/// [`RadixSet::to_filter`]: ../set/struct.RadixSet.html#method.to_filter
+/// [`bits`]: #method.bits
+/// [`from_parts`]: #method.from_parts
+pub struct BloomFilter<K: Key + ?Sized> {
+    bits: Vec<u64>,
+    num_hashes: u32,
+    _key: PhantomData<K>,
+}
+
+impl<K: Key + ?Sized> BloomFilter<K>
+    where K::Component: Hash,
+{
+    pub(crate) fn with_capacity(num_keys: usize, bits_per_key: usize) -> BloomFilter<K> {
+        let bits_per_key = bits_per_key.max(1);
+        let num_words = (num_keys * bits_per_key).div_ceil(64).max(1);
+
+        // k = ln(2) * m/n minimizes the false positive rate
+        let num_hashes = (bits_per_key as f64 * 0.69).round().clamp(1.0, 30.0) as u32;
+
+        BloomFilter {
+            bits: vec![0; num_words],
+            num_hashes,
+            _key: PhantomData,
+        }
+    }
+
+    /// Rebuilds a filter from its words and number of hash functions.
+    pub fn from_parts(bits: Vec<u64>, num_hashes: u32) -> BloomFilter<K> {
+        assert!(!bits.is_empty(), "a filter holds at least one word");
+        BloomFilter { bits, num_hashes, _key: PhantomData }
+    }
+
+    /// Gets the words of the filter.
+    pub fn bits(&self) -> &[u64] {
+        &self.bits
+    }
+
+    /// Gets the number of hash functions of the filter.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    pub(crate) fn insert_components(&mut self, key: &[K::Component]) {
+        let num_bits = self.bits.len() as u64 * 64;
+        for bit in probes(key, self.num_hashes, num_bits) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns if the key may have been in the set, `false` meaning it definitely wasn't.
+    pub fn contains(&self, key: &K) -> bool {
+        let num_bits = self.bits.len() as u64 * 64;
+        probes(key.as_slice(), self.num_hashes, num_bits)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// Bit positions of a key, derived from two hashes (Kirsch-Mitzenmacher).
+fn probes<C: Hash>(key: &[C], num_hashes: u32, num_bits: u64) -> impl Iterator<Item=u64> {
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    for component in key {
+        component.hash(&mut hasher);
+    }
+    hasher.write_u64(key.len() as u64);
+
+    let h1 = hasher.finish();
+    let h2 = mix(h1) | 1;
+    (0..num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+}
+
+/// FNV-1a, a simple hash that doesn't depend on the platform nor the compiler version.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // integers are written as little endian regardless of the platform
+    fn write_u16(&mut self, i: u16) { self.write(&i.to_le_bytes()) }
+    fn write_u32(&mut self, i: u32) { self.write(&i.to_le_bytes()) }
+    fn write_u64(&mut self, i: u64) { self.write(&i.to_le_bytes()) }
+    fn write_usize(&mut self, i: usize) { self.write_u64(i as u64) }
+}
+
+/// The finalizer of splitmix64.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use set::RadixSet;
+
+    #[test]
+    fn it_has_no_false_negatives() {
+        let keys: Vec<String> = (0..1000).map(|i| format!("key-{}", i)).collect();
+        let set: RadixSet<str> = keys.iter().collect();
+
+        let filter = set.to_filter(10);
+        assert!(keys.iter().all(|k| filter.contains(k)));
+    }
+
+    #[test]
+    fn it_has_few_false_positives() {
+        let set: RadixSet<str> = (0..1000).map(|i| format!("key-{}", i)).collect();
+        let filter = set.to_filter(10);
+
+        // ~1% expected with 10 bits per key
+        let false_positives = (0..10000)
+            .filter(|i| filter.contains(&format!("other-{}", i)))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn it_can_be_rebuilt_from_its_parts() {
+        use super::BloomFilter;
+
+        let set: RadixSet<[u8]> = vec![&b"abc"[..], b"abd"].into_iter().collect();
+        let filter = set.to_filter(8);
+
+        let rebuilt = BloomFilter::<[u8]>::from_parts(filter.bits().to_vec(), filter.num_hashes());
+        assert!(rebuilt.contains(b"abc"));
+        assert!(rebuilt.contains(b"abd"));
+    }
+}
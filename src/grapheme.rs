@@ -0,0 +1,161 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use map::{RadixMap, Matches as MapMatches};
+
+/// Splits a string in extended grapheme clusters, the components of the keys of a
+/// [`GraphemeMap`].
+///
+/// [`GraphemeMap`]: struct.GraphemeMap.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::grapheme::graphemes;
+///
+/// let clusters: Vec<Box<str>> = vec!["e\u{301}".into(), "t".into(), "\u{e9}".into()];
+/// assert_eq!(graphemes("e\u{301}t\u{e9}"), clusters);
+/// assert_eq!(graphemes("👍🏽!").len(), 2);
+/// ```
+pub fn graphemes(s: &str) -> Vec<Box<str>> {
+    s.graphemes(true).map(Box::from).collect()
+}
+
+/// A map from strings to values, whose keys are made of grapheme clusters instead of bytes.
+///
+/// Prefixes are matched on whole user-perceived characters: a prefix never ends in the middle of
+/// an emoji sequence or before the combining marks of a character. Note that strings aren't
+/// normalized, e.g. a precomposed `é` and an `e` followed by a combining acute accent are
+/// distinct clusters.
+///
+/// Entries are sorted by the sequence of their clusters, each cluster comparing by its bytes.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::grapheme::GraphemeMap;
+///
+/// let mut map = GraphemeMap::new();
+/// map.insert("👍", "thumbs up");
+/// map.insert("👍🏽", "thumbs up: medium skin tone");
+/// map.insert("e\u{301}clair", "pastry");
+///
+/// let matches: Vec<_> = map.find("👍").map(|(_, &v)| v).collect();
+/// assert_eq!(matches, vec!["thumbs up"]);
+/// assert_eq!(map.find("e").count(), 0);
+/// assert_eq!(map.find("e\u{301}").count(), 1);
+/// ```
+pub struct GraphemeMap<V> {
+    map: RadixMap<[Box<str>], V>,
+}
+
+impl<V> GraphemeMap<V> {
+    /// Makes a new empty map.
+    pub fn new() -> GraphemeMap<V> {
+        GraphemeMap { map: RadixMap::new() }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        self.map.insert(&graphemes(key), value)
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.map.get(&graphemes(key))
+    }
+
+    /// Removes a key from the map, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.map.remove(&graphemes(key))
+    }
+
+    /// Gets an iterator over the entries whose key starts with the grapheme clusters of
+    /// `prefix`, sorted by key.
+    pub fn find<'a>(&'a self, prefix: &str) -> Matches<'a, V> {
+        Matches {
+            matches: self.map.find(&graphemes(prefix)),
+        }
+    }
+
+    /// Gets an iterator over the entries of the map, sorted by key.
+    pub fn iter(&self) -> Matches<'_, V> {
+        self.find("")
+    }
+}
+
+impl<V> Default for GraphemeMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An iterator over the entries of a [`GraphemeMap`].
+///
+/// [`GraphemeMap`]: struct.GraphemeMap.html
+pub struct Matches<'a, V: 'a> {
+    matches: MapMatches<'a, [Box<str>], V>,
+}
+
+debug_iterator!(['a, V: 'a] Matches<'a, V>);
+
+impl<'a, V: 'a> Iterator for Matches<'a, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next().map(|(k, v)| (k.concat(), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.matches.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphemeMap;
+    use RadixMap;
+
+    #[test]
+    fn it_never_splits_grapheme_clusters() {
+        let keys = ["🇫🇷", "🇫🇮", "a\u{308}", "a", "ab", "👩‍👩‍👧"];
+
+        let mut bytes: RadixMap<str, usize> = RadixMap::new();
+        let mut map = GraphemeMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            bytes.insert(key, i);
+            assert_eq!(map.insert(key, i), None);
+        }
+        assert_eq!(map.len(), keys.len());
+
+        // the regional indicator of F is a prefix of both flags, but not a character
+        assert_eq!(bytes.find("🇫").count(), 2);
+        assert_eq!(map.find("🇫").count(), 0);
+        assert_eq!(map.find("🇫🇷").map(|(k, _)| k).collect::<Vec<_>>(), vec!["🇫🇷"]);
+
+        // so is the woman of a family
+        assert_eq!(bytes.find("👩").count(), 1);
+        assert_eq!(map.find("👩").count(), 0);
+
+        let found: Vec<_> = map.find("a").map(|(k, &v)| (k, v)).collect();
+        assert_eq!(found, vec![("a".to_string(), 3), ("ab".to_string(), 4)]);
+        assert_eq!(map.get("a\u{308}"), Some(&2));
+
+        assert_eq!(map.remove("ab"), Some(4));
+        assert_eq!(map.remove("ab"), None);
+        assert_eq!(map.iter().count(), keys.len() - 1);
+    }
+}
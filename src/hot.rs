@@ -0,0 +1,296 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
+
+use map::{RadixMap, Matches, NodeId};
+use key::{AsKey, Key};
+
+/// A map keeping handles to the nodes of its most frequently looked up keys in a small hash
+/// table, for read-mostly workloads where a few keys make up most of the lookups.
+///
+/// Looking up a cached key hashes it, compares it with the key of the cached handle and follows
+/// the handle's path down the tree (see [`RadixMap::node_id`]), instead of comparing the labels
+/// of the edges along the way. The values stay in the map: replacing the value of a key keeps its
+/// handle valid, while adding or removing a key invalidates all of them, and the keys are searched
+/// again on their next lookup.
+///
+/// The cache has `capacity` slots, and each key can take one of two of them, picked by its hash
+/// (moving the key holding one of them to its other slot if needed).
+/// A key missing from the cache only leaves marks in its slots, and takes one of them if it's
+/// looked up again while one of the marks is there, so that keys looked up once, e.g. by a scan,
+/// don't flush the hot ones. A slot whose key was looked up since it took the slot or was last
+/// passed over is passed over once more, instead of being taken. Keys missing from the map are
+/// never cached.
+///
+/// The cache is only used by [`get`], which takes `&mut self` to keep track of the lookups: the
+/// other lookups go through the map, see [`as_map`].
+///
+/// [`RadixMap::node_id`]: ../map/struct.RadixMap.html#method.node_id
+/// [`get`]: #method.get
+/// [`as_map`]: #method.as_map
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::hot::HotRadixMap;
+///
+/// let mut routes = HotRadixMap::new(1);
+/// routes.insert("/api/v1/users", 1);
+/// routes.insert("/api/v1/groups", 2);
+///
+/// for _ in 0..10 {
+///     assert_eq!(routes.get("/api/v1/users"), Some(&1));
+/// }
+/// assert_eq!((routes.hits(), routes.misses()), (8, 2));
+///
+/// // replacing a value keeps the handle of its key
+/// routes.insert("/api/v1/users", 3);
+/// assert_eq!(routes.get("/api/v1/users"), Some(&3));
+/// assert_eq!((routes.hits(), routes.misses()), (9, 2));
+///
+/// // a key looked up once doesn't evict a hot one
+/// assert_eq!(routes.get("/api/v1/groups"), Some(&2));
+/// assert_eq!(routes.get("/api/v1/users"), Some(&3));
+/// assert_eq!((routes.hits(), routes.misses()), (10, 3));
+/// ```
+pub struct HotRadixMap<K: Key + ?Sized, V> {
+    map: RadixMap<K, V>,
+    // slots of the handles, indexed by the hashes of their keys
+    slots: Vec<Slot<K>>,
+    // unkeyed, as keys sharing slots only miss the cache
+    hasher: BuildHasherDefault<DefaultHasher>,
+    hits: u64,
+    misses: u64,
+}
+
+struct Slot<K: Key + ?Sized> {
+    // hash of the last key which missed the cache, taking the slot if it misses it again
+    mark: u64,
+    handle: Option<Handle<K>>,
+}
+
+struct Handle<K: Key + ?Sized> {
+    hash: u64,
+    id: NodeId<K>,
+    // whether the key was looked up since it took the slot or was last passed over
+    referenced: bool,
+}
+
+impl<K: Key + ?Sized, V> HotRadixMap<K, V>
+    where K::Component: Hash,
+{
+    /// Makes a new empty map, caching at most `capacity` keys.
+    pub fn new(capacity: usize) -> HotRadixMap<K, V> {
+        HotRadixMap::with_map(RadixMap::new(), capacity)
+    }
+
+    /// Makes a map of the entries of `map`, caching at most `capacity` keys.
+    pub fn with_map(map: RadixMap<K, V>, capacity: usize) -> HotRadixMap<K, V> {
+        HotRadixMap {
+            map,
+            slots: (0..capacity).map(|_| Slot { mark: 0, handle: None }).collect(),
+            hasher: BuildHasherDefault::default(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns a reference to the value of a key, through its cached handle if there is a valid
+    /// one.
+    pub fn get<Q: AsKey<K> + ?Sized>(&mut self, key: &Q) -> Option<&V> {
+        let key = key.as_key();
+        if self.slots.is_empty() {
+            self.misses += 1;
+            return self.map.get(key);
+        }
+
+        // each key has two slots, so that a few hot keys sharing a slot don't evict each other
+        let hash = self.hasher.hash_one(key.as_slice());
+        let candidates = slots_of(&self.slots, hash);
+
+        let mut stale = None;
+        for &i in &candidates {
+            if let Some(ref mut handle) = self.slots[i].handle {
+                // the handle may belong to another key with the same hash, or be stale
+                if handle.hash == hash && handle.id.components() == key.as_slice() {
+                    if let Some(value) = self.map.resolve_value(&handle.id) {
+                        self.hits += 1;
+                        handle.referenced = true;
+                        return Some(value);
+                    }
+                    stale = Some(i);
+                }
+            }
+        }
+
+        self.misses += 1;
+        if stale.is_none() && candidates.iter().all(|&i| self.slots[i].mark != hash) {
+            for &i in &candidates {
+                self.slots[i].mark = hash;
+            }
+            return self.map.get(key);
+        }
+
+        let slots = &mut self.slots;
+        let free = stale.or_else(|| candidates.iter().cloned().find(|&i| is_free(slots, i)));
+        let i = match free.or_else(|| make_room(slots, candidates)) {
+            Some(i) => i,
+            None => {
+                // both slots hold hot keys, pass over them
+                for &i in &candidates {
+                    self.slots[i].handle.as_mut().unwrap().referenced = false;
+                }
+                return self.map.get(key);
+            },
+        };
+
+        let id = self.map.node_id(key).filter(|id| id.components() == key.as_slice())?;
+        let value = self.map.resolve_value(&id)?;
+        self.slots[i].handle = Some(Handle { hash, id, referenced: false });
+        Some(value)
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert<Q: AsKey<K> + ?Sized>(&mut self, key: &Q, value: V) -> Option<V> {
+        self.map.insert(key.as_key(), value)
+    }
+
+    /// Removes a key from the map, returning its value.
+    pub fn remove<Q: AsKey<K> + ?Sized>(&mut self, key: &Q) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, sorted by key.
+    pub fn find<'a, Q: AsKey<K> + ?Sized>(&'a self, key: &Q) -> Matches<'a, K, V> {
+        self.map.find(key)
+    }
+
+    /// Returns the number of lookups through [`get`] that found their key in the cache.
+    ///
+    /// [`get`]: #method.get
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups through [`get`] that searched the tree.
+    ///
+    /// [`get`]: #method.get
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the map, for the lookups that don't go through the cache.
+    pub fn as_map(&self) -> &RadixMap<K, V> {
+        &self.map
+    }
+
+    /// Returns the map, dropping the cache.
+    pub fn into_map(self) -> RadixMap<K, V> {
+        self.map
+    }
+}
+
+fn slots_of<K: Key + ?Sized>(slots: &[Slot<K>], hash: u64) -> [usize; 2] {
+    let n = slots.len() as u64;
+    [(hash % n) as usize, ((hash >> 32) % n) as usize]
+}
+
+fn is_free<K: Key + ?Sized>(slots: &[Slot<K>], i: usize) -> bool {
+    slots[i].handle.as_ref().is_none_or(|handle| !handle.referenced)
+}
+
+/// Move the key of one of the `candidates` slots to its other slot, if it's free.
+fn make_room<K: Key + ?Sized>(slots: &mut [Slot<K>], candidates: [usize; 2]) -> Option<usize> {
+    for &i in &candidates {
+        let hash = slots[i].handle.as_ref().unwrap().hash;
+        let [a, b] = slots_of(slots, hash);
+        let j = if a == i { b } else { a };
+        if j != i && is_free(slots, j) {
+            slots[j].handle = slots[i].handle.take();
+            return Some(i);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HotRadixMap;
+    use RadixMap;
+
+    #[test]
+    fn it_caches_the_most_frequent_lookups() {
+        let map: RadixMap<str, i32> = vec![("a", 1), ("ab", 2), ("abc", 3), ("b", 4)].into_iter().collect();
+        // a single slot, shared by all the keys
+        let mut map = HotRadixMap::with_map(map, 1);
+
+        for _ in 0..5 {
+            assert_eq!(map.get("ab"), Some(&2));
+        }
+        assert_eq!((map.hits(), map.misses()), (3, 2));
+
+        // a key looked up once doesn't evict a hot one, a key looked up repeatedly does
+        assert_eq!(map.get("b"), Some(&4));
+        assert_eq!(map.get("ab"), Some(&2));
+        assert_eq!((map.hits(), map.misses()), (4, 3));
+        for _ in 0..4 {
+            assert_eq!(map.get("b"), Some(&4));
+        }
+        assert_eq!((map.hits(), map.misses()), (6, 5));
+
+        // replacing a value keeps the handle, removing a key invalidates it until the next lookup
+        map.insert("b", 40);
+        assert_eq!(map.get("b"), Some(&40));
+        assert_eq!((map.hits(), map.misses()), (7, 5));
+        map.remove("a");
+        assert_eq!(map.get("b"), Some(&40));
+        assert_eq!(map.get("b"), Some(&40));
+        assert_eq!((map.hits(), map.misses()), (8, 6));
+
+        // missing keys aren't cached
+        assert_eq!(map.get("c"), None);
+        assert_eq!(map.get("c"), None);
+        assert_eq!(map.get("c"), None);
+        assert_eq!(map.get("b"), Some(&40));
+        assert_eq!((map.hits(), map.misses()), (9, 9));
+
+        map.insert("abd", 5);
+        for _ in 0..4 {
+            assert_eq!(map.get("abd"), Some(&5));
+        }
+        assert_eq!((map.hits(), map.misses()), (10, 12));
+
+        let mut uncached = HotRadixMap::with_map(map.into_map(), 0);
+        assert_eq!(uncached.get("abc"), Some(&3));
+        assert_eq!(uncached.get("abc"), Some(&3));
+        assert_eq!((uncached.hits(), uncached.misses()), (0, 2));
+        assert_eq!(uncached.find("ab").count(), 3);
+    }
+
+    #[test]
+    fn it_caches_the_hot_keys() {
+        let map: RadixMap<str, usize> = (0..1000).map(|i| (format!("key/{}", i), i)).collect();
+        let mut map = HotRadixMap::with_map(map, 64);
+
+        // keys looked up once, in between lookups of the hot keys, only leave marks
+        for i in 10..1000 {
+            assert_eq!(map.get(&format!("key/{}", i)), Some(&i));
+            for j in 0..10 {
+                assert_eq!(map.get(&format!("key/{}", j)), Some(&j));
+            }
+        }
+        // the hot keys only missed the cache twice each, when first looked up
+        assert_eq!(map.misses(), 990 + 2 * 10);
+    }
+}
@@ -1,12 +1,45 @@
+use std::any::{Any, TypeId};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
 pub trait KeyComponent: Ord + Eq + Clone {}
 impl<T: Ord + Eq + Clone> KeyComponent for T {}
 
-pub trait Key: ToOwned {
+pub trait Key: ToOwned + AsKey<Self> {
     type Component: KeyComponent;
 
     fn as_slice(&self) -> &[Self::Component];
 
     fn from_vec(v: Vec<Self::Component>) -> Self::Owned;
+
+    /// Writes a label of the tree, when dumping its structure.
+    fn fmt_label<W: fmt::Write>(label: &[Self::Component], rendering: KeyRendering, w: &mut W) -> fmt::Result
+        where Self::Component: fmt::Debug + 'static,
+    {
+        match rendering {
+            KeyRendering::Hex => write!(w, "{:x?}", label),
+            _                 => write!(w, "{:?}", label),
+        }
+    }
+
+    /// Writes a whole key, when formatting a map with `Debug`.
+    fn fmt_key<W: fmt::Write>(key: &[Self::Component], rendering: KeyRendering, w: &mut W) -> fmt::Result
+        where Self::Component: fmt::Debug + 'static,
+    {
+        Self::fmt_label(key, rendering, w)
+    }
+
+    /// Returns whether the component continues the one before it, so that keys can't be cut
+    /// before it, e.g. a continuation byte of a UTF-8 character.
+    fn continues(_component: &Self::Component) -> bool {
+        false
+    }
+
+    /// Like `from_vec`, but the components may be a truncated key.
+    fn from_partial_vec(v: Vec<Self::Component>) -> Self::Owned {
+        Self::from_vec(v)
+    }
 }
 
 impl Key for str {
@@ -16,11 +49,45 @@ impl Key for str {
         self.as_bytes()
     }
 
+    #[cfg(not(feature = "forbid-unsafe"))]
     fn from_vec(v: Vec<u8>) -> String {
         unsafe {
             String::from_utf8_unchecked(v)
         }
     }
+
+    #[cfg(feature = "forbid-unsafe")]
+    fn from_vec(v: Vec<u8>) -> String {
+        String::from_utf8(v).expect("keys should be valid UTF-8")
+    }
+
+    fn fmt_label<W: fmt::Write>(label: &[u8], rendering: KeyRendering, w: &mut W) -> fmt::Result {
+        match rendering {
+            KeyRendering::Text    => write!(w, "\"{}\"", String::from_utf8_lossy(label)),
+            KeyRendering::Escaped => write!(w, "\"{}\"", label.escape_ascii()),
+            KeyRendering::Hex     => write!(w, "{:x?}", label),
+        }
+    }
+
+    fn fmt_key<W: fmt::Write>(key: &[u8], rendering: KeyRendering, w: &mut W) -> fmt::Result {
+        // whole keys are valid UTF-8, unlike labels
+        match rendering {
+            KeyRendering::Hex => write!(w, "{:x?}", key),
+            _                 => write!(w, "{:?}", borrow_str(key)),
+        }
+    }
+
+    fn continues(byte: &u8) -> bool {
+        // 0b10xx_xxxx
+        (*byte as i8) < -0x40
+    }
+
+    fn from_partial_vec(v: Vec<u8>) -> String {
+        match String::from_utf8(v) {
+            Ok(s)  => s,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        }
+    }
 }
 
 impl<T: KeyComponent> Key for [T] {
@@ -33,6 +100,158 @@ impl<T: KeyComponent> Key for [T] {
     fn from_vec(v: Vec<T>) -> Vec<T> {
         v
     }
+
+    fn fmt_label<W: fmt::Write>(label: &[T], rendering: KeyRendering, w: &mut W) -> fmt::Result
+        where T: fmt::Debug + 'static,
+    {
+        // bytes are rendered like the bytes of `str` keys, the other components with `Debug`
+        if TypeId::of::<T>() == TypeId::of::<u8>() {
+            let bytes: Vec<u8> = label.iter()
+                .filter_map(|c| (c as &dyn Any).downcast_ref::<u8>().copied())
+                .collect();
+            return str::fmt_label(&bytes, rendering, w);
+        }
+        match rendering {
+            KeyRendering::Hex => write!(w, "{:x?}", label),
+            _                 => write!(w, "{:?}", label),
+        }
+    }
+
+    fn fmt_key<W: fmt::Write>(key: &[T], rendering: KeyRendering, w: &mut W) -> fmt::Result
+        where T: fmt::Debug + 'static,
+    {
+        // whole keys are rendered like std collections render them, bytes being numbers
+        match rendering {
+            KeyRendering::Hex => write!(w, "{:x?}", key),
+            _                 => write!(w, "{:?}", key),
+        }
+    }
+}
+
+/// A value that can be used to look a key up, like the `Borrow` bound of the lookups of std maps.
+///
+/// Every key can be looked up by itself, and `str` and `[T]` keys by their owners (e.g. `String`,
+/// `Vec<T>` or arrays) or by references to any of these, so that lookups don't need explicit
+/// conversions.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::{RadixMap, RadixSet};
+///
+/// let map: RadixMap<str, i32> = vec![("a", 1)].into_iter().collect();
+/// let key = String::from("a");
+/// assert_eq!(map.get("a"), map.get(&key));
+///
+/// let set: RadixSet<[u8]> = vec![&b"ab"[..]].into_iter().collect();
+/// assert!(set.contains(b"ab") && set.contains(&vec![b'a', b'b']) && set.contains(&b"ab"[..]));
+/// ```
+pub trait AsKey<K: ?Sized> {
+    /// Borrows the value as a key.
+    fn as_key(&self) -> &K;
+}
+
+impl AsKey<str> for str {
+    fn as_key(&self) -> &str {
+        self
+    }
+}
+
+impl<T: KeyComponent> AsKey<[T]> for [T] {
+    fn as_key(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: ExtensibleKey> AsKey<T> for T {
+    fn as_key(&self) -> &T {
+        self
+    }
+}
+
+impl<Q: AsKey<str> + ?Sized> AsKey<str> for &Q {
+    fn as_key(&self) -> &str {
+        (**self).as_key()
+    }
+}
+
+impl<T: KeyComponent, Q: AsKey<[T]> + ?Sized> AsKey<[T]> for &Q {
+    fn as_key(&self) -> &[T] {
+        (**self).as_key()
+    }
+}
+
+impl AsKey<str> for String {
+    fn as_key(&self) -> &str {
+        self
+    }
+}
+
+impl AsKey<str> for Box<str> {
+    fn as_key(&self) -> &str {
+        self
+    }
+}
+
+impl<T: KeyComponent> AsKey<[T]> for Vec<T> {
+    fn as_key(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: KeyComponent> AsKey<[T]> for Box<[T]> {
+    fn as_key(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: KeyComponent, const N: usize> AsKey<[T]> for [T; N] {
+    fn as_key(&self) -> &[T] {
+        self
+    }
+}
+
+/// How the keys of a map are rendered in its debugging output, e.g. by
+/// [`RadixMap::dump_structure`].
+///
+/// Labels of the tree may end in the middle of a UTF-8 character, and keys may hold binary data:
+/// the policy of a map can be set with [`RadixMap::set_key_rendering`] to keep its output
+/// readable. The labels of `str` and `[u8]` keys are rendered as bytes, the others like with
+/// `Debug`, except with `Hex` which renders integer components in hexadecimal.
+///
+/// Formatting a map with `Debug` renders whole keys, like std collections do (e.g. `"café"` or
+/// `[1, 2]`) unless the policy is `Hex`.
+///
+/// [`RadixMap::dump_structure`]: map/struct.RadixMap.html#method.dump_structure
+/// [`RadixMap::set_key_rendering`]: map/struct.RadixMap.html#method.set_key_rendering
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum KeyRendering {
+    /// Bytes are rendered as text, invalid UTF-8 being replaced by `U+FFFD`.
+    Text,
+    /// Bytes are rendered in hexadecimal.
+    Hex,
+    /// Bytes are rendered as text, with non-printable and non-ASCII bytes escaped, e.g. `\n` or
+    /// `\xc3`. This is the default.
+    #[default]
+    Escaped,
+}
+
+/// Borrows the components of a `str` key stored in a tree as a `str`.
+///
+/// Like `from_vec`, this relies on the components being those of whole keys, which were valid
+/// UTF-8 when inserted. With the `forbid-unsafe` feature, the components are checked instead.
+#[cfg(not(feature = "forbid-unsafe"))]
+pub fn borrow_str(v: &[u8]) -> &str {
+    unsafe {
+        ::std::str::from_utf8_unchecked(v)
+    }
+}
+
+#[cfg(feature = "forbid-unsafe")]
+pub fn borrow_str(v: &[u8]) -> &str {
+    ::std::str::from_utf8(v).expect("keys should be valid UTF-8")
 }
 
 /// A key that can be inserted in both [`RadixSet`] and [`RadixMap`].
@@ -64,6 +283,77 @@ impl<T: ExtensibleKey> Key for T {
     }
 }
 
+/// A floating point number usable as a key component.
+///
+/// Floats aren't `Ord`, so slices of `f32`/`f64` can't be used as keys directly. This wrapper
+/// orders its value by the IEEE 754 `totalOrder` predicate (see [`f64::total_cmp`]), which means
+/// that:
+///
+/// - `-0.0` and `+0.0` are distinct keys, `-0.0` being the smallest of both,
+/// - NaNs are equal to themselves only if they have the same bit pattern, positive NaNs sort after
+///   `+inf` and negative NaNs sort before `-inf`.
+///
+/// Equality and hashing are consistent with this ordering, i.e. keys are compared bitwise.
+///
+/// [`f64::total_cmp`]: https://doc.rust-lang.org/std/primitive.f64.html#method.total_cmp
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::{RadixSet, OrderedFloat};
+///
+/// let key: Vec<_> = [0.5f32, 1.5, 2.5].iter().cloned().map(OrderedFloat).collect();
+///
+/// let mut set: RadixSet<[OrderedFloat<f32>]> = RadixSet::new();
+/// set.insert(&key);
+/// assert!(set.contains(&key));
+/// assert_eq!(set.find(&key[..1]).count(), 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderedFloat<F>(pub F);
+
+macro_rules! impl_ordered_float {
+    ($($f:ty),*) => {
+        $(
+            impl Ord for OrderedFloat<$f> {
+                fn cmp(&self, other: &Self) -> Ordering {
+                    self.0.total_cmp(&other.0)
+                }
+            }
+
+            impl PartialOrd for OrderedFloat<$f> {
+                fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+
+            impl PartialEq for OrderedFloat<$f> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.cmp(other) == Ordering::Equal
+                }
+            }
+
+            impl Eq for OrderedFloat<$f> {}
+
+            impl Hash for OrderedFloat<$f> {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    self.0.to_bits().hash(state);
+                }
+            }
+
+            impl From<$f> for OrderedFloat<$f> {
+                fn from(f: $f) -> Self {
+                    OrderedFloat(f)
+                }
+            }
+        )*
+    }
+}
+
+impl_ordered_float!(f32, f64);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,7 +366,7 @@ mod tests {
         impl ExtensibleKey for Wrapper {
             type Component = ();
 
-            fn as_slice<'a>(&'a self) -> &'a [()] {
+            fn as_slice(&self) -> &[()] {
                 &self.0
             }
 
@@ -90,4 +380,33 @@ mod tests {
         let mut s = RadixSet::<Wrapper>::new();
         s.insert(&Wrapper(vec![()]));
     }
+
+    #[test]
+    fn it_orders_floats_totally() {
+        use std::f64;
+        use set::RadixSet;
+
+        let keys = [
+            [1.0, f64::NAN],
+            [1.0, f64::INFINITY],
+            [1.0, 0.0],
+            [1.0, -0.0],
+            [1.0, -f64::NAN],
+        ];
+
+        let mut s = RadixSet::<[OrderedFloat<f64>]>::new();
+        for key in keys.iter() {
+            let key: Vec<_> = key.iter().cloned().map(OrderedFloat).collect();
+            assert!(s.insert(&key));
+        }
+        assert_eq!(s.len(), 5);
+
+        let found: Vec<Vec<u64>> = s.iter()
+            .map(|k| k.iter().map(|f| f.0.to_bits()).collect())
+            .collect();
+        let expected: Vec<Vec<u64>> = [-f64::NAN, -0.0, 0.0, f64::INFINITY, f64::NAN].iter()
+            .map(|&f| vec![1.0f64.to_bits(), f.to_bits()])
+            .collect();
+        assert_eq!(found, expected);
+    }
 }
@@ -3,10 +3,47 @@
 //! "by prefix".
 
 #![deny(missing_docs)]
+#![cfg_attr(feature = "forbid-unsafe", forbid(unsafe_code))]
 
-pub use map::RadixMap;
-pub use set::RadixSet;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+
+#[cfg(feature = "unicode")]
+extern crate unicode_segmentation;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+#[cfg(feature = "icu")]
+extern crate icu_collator;
+
+// Implements `Debug` for an iterator, which can't show its items without consuming them: only
+// the number of items left is shown, for iterators knowing it (`exact`).
+macro_rules! debug_iterator {
+    (exact [$($generics:tt)*] $name:ident $(<$($args:tt),*>)*) => {
+        impl<$($generics)*> ::std::fmt::Debug for $name $(<$($args),*>)* {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("remaining", &::std::iter::ExactSizeIterator::len(self))
+                    .finish_non_exhaustive()
+            }
+        }
+    };
+    ([$($generics:tt)*] $name:ident $(<$($args:tt),*>)*) => {
+        impl<$($generics)*> ::std::fmt::Debug for $name $(<$($args),*>)* {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+pub use map::{RadixMap, StringRadixMap};
+pub use set::{RadixSet, StringRadixSet};
 pub use key::ExtensibleKey as RadixKey;
+pub use key::OrderedFloat;
+pub use key::KeyRendering;
+pub use key::AsKey;
 
 /// Module containing a map based on a [Radix tree](https://en.wikipedia.org/wiki/Radix_tree).
 pub mod map;
@@ -14,5 +51,79 @@ pub mod map;
 /// Module containing a set based on a [Radix tree](https://en.wikipedia.org/wiki/Radix_tree).
 pub mod set;
 
+/// Module containing a map with a bounded capacity, evicting the least recently used entries.
+pub mod bounded;
+
+/// Module containing a map whose entries expire after a time-to-live.
+pub mod ttl;
+
+/// Module containing a map counting occurrences of keys.
+pub mod counter;
+
+/// Module containing an inverted index, mapping terms to sorted lists of documents.
+pub mod postings;
+
+/// Module replaying recorded traces of operations, to turn bugs into regression tests.
+pub mod replay;
+
+/// Module containing a map storing metadata, e.g. flags, next to its values.
+pub mod meta;
+
+/// Module containing a map stamping its entries with the generation they were last modified at.
+pub mod stamped;
+
+/// Module containing a map maintaining the digests of the prefixes of its keys in its nodes.
+pub mod merkle;
+
+/// Module containing a map along with a reverse index from its values to their keys.
+pub mod reverse;
+
+/// Module containing an index of words by their letters, to look up anagrams.
+pub mod anagram;
+
+/// Module containing encodings of timestamps sorting like them, for time-bucketed keys.
+pub mod timestamp;
+
+/// Module containing encodings of keys, e.g. to compress long keys.
+pub mod codec;
+
+/// Module containing tolerant comparisons for prefix searches.
+pub mod compare;
+
+/// Module containing a map caching handles to the nodes of its most frequent lookups.
+pub mod hot;
+
+/// Module containing maps shared between threads: a single writer and many non-blocking readers,
+/// or writers locking disjoint prefixes.
+pub mod sync;
+
+/// Module containing statistics on the maps and sets, for monitoring.
+pub mod stats;
+
+/// Module containing metrics on the locks of the concurrent maps of `sync`.
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+/// Module containing counts of the work done by lookups, to check their complexity.
+#[cfg(feature = "complexity")]
+pub mod complexity;
+
+/// Module containing a compact probabilistic filter of keys, see `RadixSet::to_filter`.
+#[cfg(feature = "filter")]
+pub mod filter;
+
+/// Module containing a map from nucleotide sequences, packing their keys 4 nucleotides per byte.
+#[cfg(feature = "dna")]
+pub mod dna;
+
+/// Module containing a map whose keys are made of grapheme clusters, so that prefixes never split
+/// user-perceived characters.
+#[cfg(feature = "unicode")]
+pub mod grapheme;
+
+/// Module containing a map sorted by a collation of its keys, e.g. following locale rules.
+#[cfg(feature = "icu")]
+pub mod collation;
+
 mod key;
 mod tree;
@@ -1,12 +1,32 @@
+use std::fmt;
+use std::borrow::Borrow;
+use std::cmp::{Ordering, Reverse};
 use std::iter::FromIterator;
+use std::hash::{Hash, Hasher, BuildHasher};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, VecDeque};
+use std::mem;
+use std::iter::{self, Flatten};
+use std::vec;
+#[cfg(feature = "rayon")]
+use std::convert::Infallible;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use tree::{
     Tree,
     Iter as TreeIter,
+    IterMut as TreeIterMut,
     Matches as TreeMatches,
+    Cursor as TreeCursor,
+    PrefixCursor as TreePrefixCursor,
+    Ancestors as TreeAncestors,
+    Change,
+    ExtractIf as TreeExtractIf,
 };
 
-use key::Key;
+use key::{AsKey, Key, KeyComponent, KeyRendering, borrow_str};
+use compare::Compare;
 
 /// A map based on a [Radix tree](https://en.wikipedia.org/wiki/Radix_tree).
 ///
@@ -27,11 +47,104 @@ use key::Key;
 /// The `Key` trait is left private for safety (see the implementation for `str` for an
 /// explanation). You can think of it as an abstraction over both `T` slices and `str` slices.
 /// Therefore when specifying the type of `K`, you'll give either `[T]` or `str`.
+///
+/// # Ordering
+///
+/// Iteration (`iter`, `keys`, `find`, ...) yields keys in increasing lexicographic order of their
+/// components, which is the `Ord` of `[T]` (or of `str`, byte by byte):
+///
+/// - keys are compared component by component, using the components' `Ord`,
+/// - a key comes right before the keys it is a prefix of, e.g. `[1]` before `[1, 2]` before `[2]`,
+/// - keys of equal length are therefore sorted like tuples, e.g. `OrderedFloat` components by
+///   their total order.
+///
+/// The order only depends on the keys, not on the order of insertion, so it can be relied on to
+/// merge-join several maps or with other sorted collections. Debug builds check it while
+/// iterating.
+///
+/// # Complexity
+///
+/// Looking up, inserting or removing a key of length `L` descends at most `L` edges, finding the
+/// edge to follow at each node with a binary search on the first components of its edges. This is
+/// `O(L log A)` for an alphabet of `A` components, and doesn't depend on the number of keys in the
+/// map. Finding the keys starting with a prefix of length `L` descends the same way, then yields
+/// each of the `M` matches in `O(1)` amortized, rebuilding their keys: `O(L log A + M)` without
+/// counting the length of the keys yielded.
+///
+/// With the `complexity` feature, the edges and components compared by these operations are
+/// counted, see `complexity::OpCounts`. The crate's tests check the bounds above with these counts.
+///
+/// With the `paranoid` feature, the map also keeps its keys in a `BTreeSet` and checks after each
+/// modification (and when dropped) that the tree holds the same keys and that its invariants
+/// hold, panicking otherwise. This is very slow, and only meant to catch bugs of this crate in
+/// tests.
 pub struct RadixMap<K: Key + ?Sized, V> {
-    tree: Tree<<K as Key>::Component, V>,
+    pub(crate) tree: Tree<<K as Key>::Component, V>,
+    rendering: KeyRendering,
+    recent: Option<RecentInserts<K::Component>>,
+    #[cfg(feature = "paranoid")]
+    shadow: BTreeSet<Vec<K::Component>>,
+}
+
+/// A map keyed by strings, the most common kind of `RadixMap`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::StringRadixMap;
+///
+/// let mut ports: StringRadixMap<u16> = StringRadixMap::new();
+/// ports.insert("http", 80);
+/// ports.insert("https", 443);
+///
+/// assert_eq!(ports.find("http").count(), 2);
+/// ```
+pub type StringRadixMap<V> = RadixMap<str, V>;
+
+// the last keys inserted in a map, oldest first
+struct RecentInserts<C> {
+    capacity: usize,
+    keys: VecDeque<Vec<C>>,
+}
+
+impl<C: Clone> RecentInserts<C> {
+    fn push(&mut self, key: &[C]) {
+        // the allocation of the oldest key is reused once full
+        let mut buffer = if self.keys.len() == self.capacity {
+            self.keys.pop_front().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        buffer.clear();
+        buffer.extend_from_slice(key);
+        self.keys.push_back(buffer);
+    }
 }
 
 impl<K: Key + ?Sized, V> RadixMap<K, V> {
+    /// An empty map, e.g. to initialize a `static` or a `const` without wrapping it in an
+    /// `Option`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// static NO_ROUTES: RadixMap<str, u16> = RadixMap::EMPTY;
+    ///
+    /// fn routes(configured: Option<&RadixMap<str, u16>>) -> &RadixMap<str, u16> {
+    ///     configured.unwrap_or(&NO_ROUTES)
+    /// }
+    ///
+    /// assert!(routes(None).is_empty());
+    /// assert_eq!(routes(None).get("/"), None);
+    /// ```
+    pub const EMPTY: RadixMap<K, V> = RadixMap::new();
+
     /// Makes a new empty RadixMap.
     ///
     /// # Examples
@@ -46,8 +159,61 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// // entries can now be inserted into the empty map
     /// map.insert("a", 1);
     /// ```
-    pub fn new() -> RadixMap<K, V> {
-        RadixMap { tree: Tree::new() }
+    pub const fn new() -> RadixMap<K, V> {
+        RadixMap {
+            tree: Tree::new(),
+            rendering: KeyRendering::Escaped,
+            recent: None,
+            #[cfg(feature = "paranoid")]
+            shadow: BTreeSet::new(),
+        }
+    }
+
+    /// Makes a new empty map, which keeps the last `capacity` keys inserted in it (whether they
+    /// were already in the map or not), e.g. to look at the traffic of a map in production.
+    ///
+    /// See [`sample_recent_inserts`].
+    ///
+    /// [`sample_recent_inserts`]: #method.sample_recent_inserts
+    pub fn with_recent_inserts(capacity: usize) -> RadixMap<K, V> {
+        let mut map = RadixMap::new();
+        if capacity > 0 {
+            map.recent = Some(RecentInserts {
+                capacity,
+                keys: VecDeque::with_capacity(capacity),
+            });
+        }
+        map
+    }
+
+    /// Returns up to `k` of the last keys inserted in the map, the most recent first, if the map
+    /// keeps them (see [`with_recent_inserts`]).
+    ///
+    /// Removing a key doesn't remove it from the keys returned.
+    ///
+    /// [`with_recent_inserts`]: #method.with_recent_inserts
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut hits: RadixMap<str, ()> = RadixMap::with_recent_inserts(3);
+    /// for path in &["/", "/login", "/", "/admin", "/login"] {
+    ///     hits.insert(path, ());
+    /// }
+    ///
+    /// assert_eq!(hits.sample_recent_inserts(10), vec!["/login", "/admin", "/"]);
+    /// assert_eq!(hits.sample_recent_inserts(1), vec!["/login"]);
+    /// assert!(RadixMap::<str, ()>::new().sample_recent_inserts(10).is_empty());
+    /// ```
+    pub fn sample_recent_inserts(&self, k: usize) -> Vec<K::Owned> {
+        match self.recent {
+            Some(ref recent) => recent.keys.iter().rev().take(k).map(|key| K::from_vec(key.clone())).collect(),
+            None => Vec::new(),
+        }
     }
 
     /// Clears the map, removing all values.
@@ -66,6 +232,9 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// ```
     pub fn clear(&mut self) {
         self.tree.clear();
+        #[cfg(feature = "paranoid")]
+        self.shadow.clear();
+        self.verify();
     }
 
     /// Return the number of elements in the map.
@@ -109,7 +278,7 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// assert_eq!(map.insert("a", 1337), Some(42));
     /// ```
     pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
-        self.tree.insert(key.as_slice(), value)
+        self.insert_components(key.as_slice(), value)
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -126,18 +295,282 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// assert_eq!(map.get("a"), Some(&1));
     /// assert_eq!(map.get("b"), None);
     /// ```
-    pub fn get(&self, key: &K) -> Option<&V> {
-        self.tree.get(key.as_slice())
+    pub fn get<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> Option<&V> {
+        self.tree.get(key.as_key().as_slice())
+    }
+
+    /// Returns the key stored in the map along with its value, e.g. to get an owned key back from
+    /// a lookup by any [`AsKey`].
+    ///
+    /// [`AsKey`]: ../trait.AsKey.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("a", 1);
+    /// assert_eq!(map.get_key_value("a"), Some(("a".to_string(), &1)));
+    /// assert_eq!(map.get_key_value("b"), None);
+    /// ```
+    pub fn get_key_value<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> Option<(K::Owned, &V)> {
+        let key = key.as_key().as_slice();
+        self.tree.get(key).map(|value| (K::from_vec(key.to_vec()), value))
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key, to update it in place
+    /// instead of removing and re-inserting it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("a", vec![1]);
+    /// map.get_mut("a").unwrap().push(2);
+    /// assert_eq!(map.get("a"), Some(&vec![1, 2]));
+    /// assert_eq!(map.get_mut("b"), None);
+    /// ```
+    pub fn get_mut<Q: AsKey<K> + ?Sized>(&mut self, key: &Q) -> Option<&mut V> {
+        self.tree.get_mut(key.as_key().as_slice())
+    }
+
+    /// Swaps the values of two keys, without moving them out of the map.
+    ///
+    /// Returns `false`, leaving the map unchanged, unless both keys were in the map.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("primary", "db-1");
+    /// map.insert("replica", "db-2");
+    ///
+    /// assert!(map.swap("primary", "replica"));
+    /// assert_eq!(map.get("primary"), Some(&"db-2"));
+    /// assert_eq!(map.get("replica"), Some(&"db-1"));
+    ///
+    /// assert!(!map.swap("primary", "standby"));
+    /// assert_eq!(map.get("primary"), Some(&"db-2"));
+    /// ```
+    pub fn swap<Q: AsKey<K> + ?Sized, R: AsKey<K> + ?Sized>(&mut self, a: &Q, b: &R) -> bool {
+        self.tree.swap(a.as_key().as_slice(), b.as_key().as_slice())
+    }
+
+    /// Moves the value of a key to another key, without moving it out of the map.
+    ///
+    /// The keys are only descended once from the root down to where they diverge. Returns
+    /// `false`, leaving the map unchanged, if `old` isn't in the map or `new` already is.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("v1/users", 1);
+    /// map.insert("v2/groups", 2);
+    ///
+    /// assert!(map.rename_key("v1/users", "v2/users"));
+    /// assert_eq!(map.get("v1/users"), None);
+    /// assert_eq!(map.get("v2/users"), Some(&1));
+    ///
+    /// assert!(!map.rename_key("v2/users", "v2/groups"));
+    /// assert_eq!(map.get("v2/groups"), Some(&2));
+    /// ```
+    pub fn rename_key<Q, R>(&mut self, old: &Q, new: &R) -> bool
+        where Q: AsKey<K> + ?Sized,
+              R: AsKey<K> + ?Sized,
+    {
+        let (old, new) = (old.as_key().as_slice(), new.as_key().as_slice());
+        let renamed = self.tree.rename_key(old, new);
+        #[cfg(feature = "paranoid")]
+        {
+            if renamed && self.shadow.remove(old) {
+                self.shadow.insert(new.to_vec());
+            }
+        }
+        self.verify();
+        renamed
+    }
+
+    /// Removes the entries whose key starts with `prefix`, returning them as a new map.
+    ///
+    /// The subtree of these entries is detached as a whole, which only costs a descent from the
+    /// root instead of one per entry.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut cache = RadixMap::new();
+    /// cache.insert("user:42:name", "alice");
+    /// cache.insert("user:42:email", "alice@example.com");
+    /// cache.insert("user:43:name", "bob");
+    ///
+    /// let evicted = cache.remove_prefix("user:42:");
+    /// assert_eq!(evicted.keys().collect::<Vec<_>>(), vec!["user:42:email", "user:42:name"]);
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn remove_prefix<Q: AsKey<K> + ?Sized>(&mut self, prefix: &Q) -> RadixMap<K, V> {
+        let prefix = prefix.as_key().as_slice();
+        let tree = self.tree.remove_prefix(prefix);
+        #[cfg(feature = "paranoid")]
+        let shadow = {
+            let (removed, kept) = mem::take(&mut self.shadow).into_iter().partition(|key| key.starts_with(prefix));
+            self.shadow = kept;
+            removed
+        };
+
+        let removed = RadixMap {
+            tree,
+            rendering: self.rendering,
+            recent: None,
+            #[cfg(feature = "paranoid")]
+            shadow,
+        };
+        self.verify();
+        removed.verify();
+        removed
+    }
+
+    /// Moves all the entries of `other` into the map, leaving `other` empty. The values of
+    /// `other` replace those of the keys in both maps.
+    ///
+    /// The subtrees of `other` are grafted where they diverge from the map instead of being
+    /// inserted entry by entry, which makes appending maps of disjoint ranges of keys (e.g. shards
+    /// of sorted entries) cheap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut a: RadixMap<str, i32> = vec![("apple", 1), ("banana", 2)].into_iter().collect();
+    /// let mut b: RadixMap<str, i32> = vec![("banana", 3), ("cherry", 4)].into_iter().collect();
+    ///
+    /// a.append(&mut b);
+    /// assert!(b.is_empty());
+    /// assert_eq!(a.into_sorted_vec(), vec![
+    ///     ("apple".to_string(), 1),
+    ///     ("banana".to_string(), 3),
+    ///     ("cherry".to_string(), 4),
+    /// ]);
+    /// ```
+    pub fn append(&mut self, other: &mut RadixMap<K, V>) {
+        self.tree.append(other.tree.take());
+        #[cfg(feature = "paranoid")]
+        self.shadow.append(&mut other.shadow);
+        self.verify();
+        other.verify();
+    }
+
+    /// Replaces the entries whose key starts with `prefix` by a single entry at `prefix`, whose
+    /// value is the fold of their values in key order. Returns the number of entries replaced.
+    ///
+    /// If `fold` panics, the entries under the prefix are dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut hits = RadixMap::new();
+    /// hits.insert("2024-01-01T10:00", 3);
+    /// hits.insert("2024-01-01T10:01", 5);
+    /// hits.insert("2024-01-02T08:00", 1);
+    ///
+    /// // per-minute hits of the first day become daily hits
+    /// assert_eq!(hits.rollup_prefix("2024-01-01", |a, b| a + b), 2);
+    /// assert_eq!(hits.get("2024-01-01"), Some(&8));
+    /// assert_eq!(hits.len(), 2);
+    /// ```
+    pub fn rollup_prefix<F, Q: AsKey<K> + ?Sized>(&mut self, prefix: &Q, fold: F) -> usize
+        where F: FnMut(V, V) -> V,
+    {
+        let prefix = prefix.as_key().as_slice();
+        let rolled_up = self.tree.rollup_prefix(prefix, fold);
+        #[cfg(feature = "paranoid")]
+        {
+            if rolled_up > 0 {
+                self.shadow.retain(|key| !key.starts_with(prefix));
+                self.shadow.insert(prefix.to_vec());
+            }
+        }
+        self.verify();
+        rolled_up
     }
 
-    /// Returns a mutable reference to the value corresponding to the key.
-    /// map.insert("a", 0);
-    /// *map.get_mut("a").unwrap() += 1;
-    /// *map.get_mut("a").unwrap() += 1;
-    /// assert_eq!(map.get("a"), Some(&2));
+    /// Removes the entries whose key starts with one of the `pruned` prefixes, and those for which
+    /// `keep` returns `false`, in a single pass over the map. Returns the number of entries
+    /// removed.
+    ///
+    /// The pass also compacts the structure of the map, which removing entries one by one doesn't
+    /// do: nodes left without a value and with a single child are merged with it.
+    ///
+    /// If `keep` panics, the entries it already rejected are removed and all the others are kept,
+    /// the map being compacted as the panic unwinds.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut sessions = RadixMap::new();
+    /// sessions.insert("tmp/upload-1", 0);
+    /// sessions.insert("user/alice", 3);
+    /// sessions.insert("user/alice/old", 0);
+    /// sessions.insert("user/bob", 0);
+    ///
+    /// assert_eq!(sessions.retain_and_prune(&["tmp/"], |_, &mut hits| hits > 0), 3);
+    ///
+    /// let mut dump = String::new();
+    /// sessions.dump_structure(&mut dump).unwrap();
+    /// assert_eq!(dump, "\
+    /// (root) (1)
+    ///   \"user/alice\" * (1)
+    /// ");
     /// ```
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
-        self.tree.get_mut(key.as_slice())
+    pub fn retain_and_prune<F>(&mut self, pruned: &[&K], keep: F) -> usize
+        where F: FnMut(&[K::Component], &mut V) -> bool,
+    {
+        let pruned: Vec<&[K::Component]> = pruned.iter().map(|p| p.as_slice()).collect();
+        let removed = self.removing(|tree| tree.retain_and_prune(&pruned, keep));
+        self.verify();
+        removed
+    }
+
+    /// Like the `extract_if` of maps of `str` and slices, with the components of the keys, for
+    /// maps of other keys.
+    pub fn extract_components_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F>
+        where F: FnMut(&[K::Component], &mut V) -> bool,
+    {
+        ExtractIf {
+            iter: self.tree.extract_if(pred),
+            #[cfg(feature = "paranoid")]
+            shadow: &mut self.shadow,
+        }
     }
 
     /// Returns if the key was inserted in the map.
@@ -159,7 +592,7 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// assert!(!map.contains_key("c"));
     /// ```
     #[inline]
-    pub fn contains_key(&self, key: &K) -> bool {
+    pub fn contains_key<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> bool {
         self.get(key).is_some()
     }
 
@@ -196,8 +629,81 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// assert_eq!(map.remove("a"), Some(1));
     /// assert_eq!(map.remove("a"), None);
     /// ```
-    pub fn remove(&mut self, key: &K) -> Option<V> {
-        self.tree.remove(key.as_slice())
+    pub fn remove<Q: AsKey<K> + ?Sized>(&mut self, key: &Q) -> Option<V> {
+        self.remove_components(key.as_key().as_slice())
+    }
+
+    /// Removes a key from the map, returning the stored key along with its value if the key was
+    /// previously in the map.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("a", 1);
+    /// assert_eq!(map.remove_entry("a"), Some(("a".to_string(), 1)));
+    /// assert_eq!(map.remove_entry("a"), None);
+    /// ```
+    pub fn remove_entry<Q: AsKey<K> + ?Sized>(&mut self, key: &Q) -> Option<(K::Owned, V)> {
+        let key = key.as_key().as_slice();
+        self.remove_components(key).map(|value| (K::from_vec(key.to_vec()), value))
+    }
+
+    /// Updates, inserts or removes the entry of a key in a single descent of the tree, as decided
+    /// by `f` from the current value of the key (or `None` if it isn't in the map). Returns the
+    /// value that was replaced or removed.
+    ///
+    /// This is like `get_mut` followed by `insert` or `remove`, without looking the key up twice,
+    /// e.g. to merge updates received from other replicas.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    /// use panoradix::map::Outcome;
+    ///
+    /// // last-writer-wins registers, `None` being a deletion
+    /// let mut replica: RadixMap<str, (u64, &str)> = RadixMap::new();
+    /// let updates = [("a", 2, Some("x")), ("a", 1, Some("stale")), ("b", 3, Some("y")), ("b", 4, None)];
+    ///
+    /// for &(key, stamp, value) in &updates {
+    ///     replica.apply(key, |current| match (current, value) {
+    ///         (Some(&mut (seen, _)), _) if seen >= stamp => Outcome::Keep,
+    ///         (_, Some(value))                          => Outcome::Set((stamp, value)),
+    ///         (_, None)                                 => Outcome::Remove,
+    ///     });
+    /// }
+    ///
+    /// assert_eq!(replica.get("a"), Some(&(2, "x")));
+    /// assert_eq!(replica.get("b"), None);
+    /// ```
+    pub fn apply<F>(&mut self, key: &K, f: F) -> Option<V>
+        where F: FnOnce(Option<&mut V>) -> Outcome<V>
+    {
+        let key = key.as_slice();
+        let (old, change) = self.tree.apply(key, f);
+        match change {
+            Change::Inserted | Change::Replaced => {
+                if let Some(ref mut recent) = self.recent {
+                    recent.push(key);
+                }
+                #[cfg(feature = "paranoid")]
+                self.shadow.insert(key.to_vec());
+            },
+            Change::Removed => {
+                #[cfg(feature = "paranoid")]
+                self.shadow.remove(key);
+            },
+            Change::Kept => {},
+        }
+        self.verify();
+        old
     }
 
     /// Gets an iterator over the entries of the map, sorted by key.
@@ -221,13 +727,14 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// let (first_key, first_value) = map.iter().next().unwrap();
     /// assert_eq!((first_key, *first_value), ("a".to_string(), 1));
     /// ```
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
         Iter {
             iter: self.tree.iter(),
         }
     }
 
-    /// Gets an iterator over the keys of the map (sorted).
+    /// Gets an iterator over the entries of the map, sorted by key, with mutable references to
+    /// the values.
     ///
     /// # Examples
     ///
@@ -236,25 +743,52 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// ```
     /// use panoradix::RadixMap;
     ///
-    /// let mut map = RadixMap::new();
-    /// map.insert("c", 3);
-    /// map.insert("b", 2);
-    /// map.insert("a", 1);
+    /// let mut map: RadixMap<str, String> = RadixMap::new();
+    /// map.insert("a", "x".to_string());
+    /// map.insert("ab", "y".to_string());
     ///
-    /// for key in map.keys() {
-    ///     println!("{}", key);
+    /// for (key, value) in map.iter_mut() {
+    ///     value.insert_str(0, &key);
     /// }
+    /// assert_eq!(map.get("ab"), Some(&"aby".to_string()));
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            iter: self.tree.iter_mut(),
+        }
+    }
+
+    /// Gets an iterator over the entries whose key is in `start..end`, sorted by key.
+    ///
+    /// Only the keys from `start` on are visited, and the number of entries is known upfront.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
     ///
-    /// let first_key = map.keys().next().unwrap();
-    /// assert_eq!(first_key, "a".to_string());
     /// ```
-    pub fn keys(&self) -> Keys<K, V> {
-        Keys {
-            iter: self.iter(),
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, i32> = vec![("a", 1), ("ab", 2), ("b", 3), ("c", 4)].into_iter().collect();
+    ///
+    /// let range = map.range("aa", "c");
+    /// assert_eq!(range.len(), 2);
+    /// assert_eq!(range.map(|(k, _)| k).collect::<Vec<_>>(), vec!["ab", "b"]);
+    /// ```
+    pub fn range<'a, Q, R>(&'a self, start: &Q, end: &R) -> Range<'a, K, V>
+        where Q: AsKey<K> + ?Sized,
+              R: AsKey<K> + ?Sized,
+    {
+        Range {
+            iter: self.tree.range(start.as_key().as_slice(), end.as_key().as_slice()),
         }
     }
 
-    /// Gets an iterator over the values of the map, sorted by corresponding key.
+    /// Gets a lending iterator over the entries of the map, sorted by key.
+    ///
+    /// Unlike `iter()`, no key is allocated: each key is lent as a slice of its components,
+    /// borrowed from a buffer that is reused from one entry to the next. This is meant for
+    /// consumers like serializers or hashers that don't need owned keys.
     ///
     /// # Examples
     ///
@@ -264,27 +798,24 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// use panoradix::RadixMap;
     ///
     /// let mut map = RadixMap::new();
-    /// map.insert("c", 3);
     /// map.insert("b", 2);
     /// map.insert("a", 1);
     ///
-    /// for value in map.values() {
-    ///     println!("{}", value);
-    /// }
-    ///
-    /// let first_value = map.values().next().unwrap();
-    /// assert_eq!(first_value, &1);
+    /// let mut it = map.raw_iter();
+    /// assert_eq!(it.next_entry(), Some((&b"a"[..], &1)));
+    /// assert_eq!(it.next_entry(), Some((&b"b"[..], &2)));
+    /// assert_eq!(it.next_entry(), None);
     /// ```
-    pub fn values(&self) -> Values<K, V> {
-        Values {
-            iter: self.iter(),
+    pub fn raw_iter(&self) -> RawIter<'_, K, V> {
+        RawIter {
+            iter: self.tree.iter(),
         }
     }
 
-    /// Gets an iterator over a filtered subset of the map, sorted by key.
+    /// Feeds the entries of the map to `visitor`, sorted by key, until it returns an error.
     ///
-    /// The iterator resembles `iter()` since it yields key-value pairs from the map. Note that
-    /// the full key will be yielded each time, not just the filtered suffix.
+    /// Like `raw_iter()`, no key is allocated. The visitor is also told how many leading
+    /// components each key shares with the previous one, e.g. to only write the rest of it.
     ///
     /// # Examples
     ///
@@ -293,108 +824,3419 @@ impl<K: Key + ?Sized, V> RadixMap<K, V> {
     /// ```
     /// use panoradix::RadixMap;
     ///
-    /// let mut map = RadixMap::new();
-    /// map.insert("abc", 1);
-    /// map.insert("acd", 2);
-    /// map.insert("abd", 3);
-    /// map.insert("bbb", 1);
-    /// map.insert("ccc", 1);
+    /// let map: RadixMap<str, u8> = vec![("car", 1), ("cart", 2), ("cat", 3)].into_iter().collect();
     ///
-    /// for (key, value) in map.find("a") {
-    ///     println!("{}: {}", key, value);
-    /// }
+    /// // each key is written as the length shared with the previous key, then the rest of it
+    /// let mut out = Vec::new();
+    /// map.visit_entries(&mut |key: &[u8], shared: usize, value: &u8| -> Result<(), ()> {
+    ///     out.push(shared as u8);
+    ///     out.push((key.len() - shared) as u8);
+    ///     out.extend_from_slice(&key[shared..]);
+    ///     out.push(*value);
+    ///     Ok(())
+    /// }).unwrap();
     ///
-    /// let (first_key, first_value) = map.find("a").next().unwrap();
-    /// assert_eq!((first_key, first_value), ("abc".to_string(), &1));
+    /// assert_eq!(out, b"\x00\x03car\x01\x03\x01t\x02\x02\x01t\x03");
     /// ```
-    pub fn find<'a>(&'a self, key: &K) -> Matches<'a, K, V> {
-        Matches {
-            matches: self.tree.find(key.as_slice()),
-        }
+    pub fn visit_entries<Vi: EntryVisitor<K, V>>(&self, visitor: &mut Vi) -> Result<(), Vi::Error> {
+        visitor.start(self.len())?;
+        self.tree.visit_entries(|key, shared, value| visitor.visit_entry(key, shared, value))
     }
-}
+
+    /// Collects the entries of the map into a `Vec`, sorted by key.
+    ///
+    /// The `Vec` is allocated once with the exact number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// assert_eq!(map.to_sorted_vec(), vec![("a".to_string(), &1), ("b".to_string(), &2)]);
+    /// ```
+    pub fn to_sorted_vec(&self) -> Vec<(K::Owned, &V)> {
+        let mut entries = Vec::with_capacity(self.len());
+        entries.extend(self.iter());
+        entries
+    }
+
+    /// Moves the entries of the map into a `Vec`, sorted by key.
+    ///
+    /// The `Vec` is allocated once with the exact number of entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("b", vec![2]);
+    /// map.insert("a", vec![1]);
+    ///
+    /// assert_eq!(map.into_sorted_vec(), vec![("a".to_string(), vec![1]), ("b".to_string(), vec![2])]);
+    /// ```
+    pub fn into_sorted_vec(mut self) -> Vec<(K::Owned, V)> {
+        #[cfg(feature = "paranoid")]
+        self.shadow.clear();
+        mem::replace(&mut self.tree, Tree::new()).into_entries().into_iter()
+            .map(|(key, value)| (K::from_vec(key), value))
+            .collect()
+    }
+
+    /// Turns the map into a map with the same keys, whose values are mapped by `f`, called in key
+    /// order.
+    ///
+    /// If `f` panics, the values it was already called on are dropped along with the partially
+    /// built map, and the others along with the rest of this map: each value is dropped exactly
+    /// once.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("a", "1");
+    /// map.insert("b", "22");
+    ///
+    /// let lengths = map.map_values(|v| v.len());
+    /// assert_eq!(lengths.get("b"), Some(&2));
+    /// ```
+    pub fn map_values<W, F>(mut self, f: F) -> RadixMap<K, W>
+        where F: FnMut(V) -> W,
+    {
+        #[cfg(feature = "paranoid")]
+        let shadow = mem::take(&mut self.shadow);
+
+        let tree = mem::replace(&mut self.tree, Tree::new()).map_values(f);
+        RadixMap {
+            tree,
+            rendering: self.rendering,
+            recent: self.recent.take(),
+            #[cfg(feature = "paranoid")]
+            shadow,
+        }
+    }
+
+    /// Gets an iterator over the keys of the map (sorted).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("c", 3);
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// for key in map.keys() {
+    ///     println!("{}", key);
+    /// }
+    ///
+    /// let first_key = map.keys().next().unwrap();
+    /// assert_eq!(first_key, "a".to_string());
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys {
+            iter: self.iter(),
+        }
+    }
+
+    /// Creates a consuming iterator over the keys of the map (sorted).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// let keys: Vec<String> = map.into_keys().collect();
+    /// assert_eq!(keys, vec!["a", "b"]);
+    /// ```
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            iter: self.into_iter(),
+        }
+    }
+
+    /// Creates a consuming iterator over the values of the map, sorted by corresponding key.
+    ///
+    /// The keys aren't rebuilt, the values are only moved out of the map.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("b", vec![2]);
+    /// map.insert("a", vec![1]);
+    ///
+    /// let values: Vec<Vec<i32>> = map.into_values().collect();
+    /// assert_eq!(values, vec![vec![1], vec![2]]);
+    /// ```
+    pub fn into_values(mut self) -> IntoValues<V> {
+        #[cfg(feature = "paranoid")]
+        self.shadow.clear();
+        IntoValues {
+            iter: mem::replace(&mut self.tree, Tree::new()).into_values().into_iter(),
+        }
+    }
+
+    /// Gets an iterator over the values of the map, sorted by corresponding key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("c", 3);
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// for value in map.values() {
+    ///     println!("{}", value);
+    /// }
+    ///
+    /// let first_value = map.values().next().unwrap();
+    /// assert_eq!(first_value, &1);
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values {
+            iter: self.iter(),
+        }
+    }
+
+    /// Gets an iterator over mutable references to the values of the map, sorted by
+    /// corresponding key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut scores: RadixMap<str, f64> = vec![("a", 2.0), ("b", 6.0)].into_iter().collect();
+    /// let total: f64 = scores.values().sum();
+    /// for score in scores.values_mut() {
+    ///     *score /= total;
+    /// }
+    /// assert_eq!(scores.values().collect::<Vec<_>>(), vec![&0.25, &0.75]);
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            iter: self.tree.iter_mut(),
+        }
+    }
+
+    /// Gets an iterator over a filtered subset of the map, sorted by key.
+    ///
+    /// The iterator resembles `iter()` since it yields key-value pairs from the map. Note that
+    /// the full key will be yielded each time, not just the filtered suffix.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("abc", 1);
+    /// map.insert("acd", 2);
+    /// map.insert("abd", 3);
+    /// map.insert("bbb", 1);
+    /// map.insert("ccc", 1);
+    ///
+    /// for (key, value) in map.find("a") {
+    ///     println!("{}: {}", key, value);
+    /// }
+    ///
+    /// let (first_key, first_value) = map.find("a").next().unwrap();
+    /// assert_eq!((first_key, first_value), ("abc".to_string(), &1));
+    /// ```
+    pub fn find<'a, Q: AsKey<K> + ?Sized>(&'a self, key: &Q) -> Matches<'a, K, V> {
+        Matches {
+            matches: self.tree.find(key.as_key().as_slice()),
+        }
+    }
+
+    /// Finds the entries starting with each of `prefixes`, returning an iterator over them (sorted
+    /// by key) for each prefix, in the order of the prefixes, e.g. for the terms of a single query.
+    ///
+    /// The prefixes are looked up in sorted order: the tree is descended once to the subtree of a
+    /// prefix, and from there to the subtrees of all the prefixes starting with it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, u32> = vec![("car", 1), ("card", 2), ("care", 3), ("dog", 4)].into_iter().collect();
+    ///
+    /// let mut found = map.find_batch(&["card", "car", "cat"]);
+    /// assert_eq!(found[0].next(), Some(("card".to_string(), &2)));
+    /// assert_eq!(found[1].len(), 3);
+    /// assert_eq!(found[2].next(), None);
+    /// ```
+    pub fn find_batch<'a, Q: AsKey<K>>(&'a self, prefixes: &[Q]) -> Vec<Matches<'a, K, V>> {
+        let prefixes: Vec<_> = prefixes.iter().map(|p| p.as_key().as_slice()).collect();
+        self.tree.find_batch(&prefixes)
+            .into_iter()
+            .map(|matches| Matches { matches })
+            .collect()
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, sorted by key, with mutable
+    /// references to the values, e.g. to update all the entries of a namespace in place.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut cache = RadixMap::new();
+    /// cache.insert("user:42:name", (true, "alice"));
+    /// cache.insert("user:42:email", (true, "alice@example.com"));
+    /// cache.insert("user:43:name", (true, "bob"));
+    ///
+    /// for (_, entry) in cache.find_mut("user:42:") {
+    ///     entry.0 = false;
+    /// }
+    /// assert_eq!(cache.values().filter(|e| e.0).count(), 1);
+    /// ```
+    pub fn find_mut<'a, Q: AsKey<K> + ?Sized>(&'a mut self, key: &Q) -> MatchesMut<'a, K, V> {
+        MatchesMut {
+            iter: self.tree.find_mut(key.as_key().as_slice()),
+        }
+    }
+
+    /// Writes an indented rendering of the tree backing the map, for debugging.
+    ///
+    /// Each line is an edge of the tree, with its label (rendered following the
+    /// [`key_rendering`] of the map), a `*` if a key ends there and the number of keys below it.
+    ///
+    /// [`key_rendering`]: #method.key_rendering
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("abc", 1);
+    /// map.insert("abd", 2);
+    /// map.insert("b\n", 3);
+    ///
+    /// let mut dump = String::new();
+    /// map.dump_structure(&mut dump).unwrap();
+    /// assert_eq!(dump, "\
+    /// (root) (3)
+    ///   \"ab\" (2)
+    ///     \"c\" * (1)
+    ///     \"d\" * (1)
+    ///   \"b\\n\" * (1)
+    /// ");
+    /// ```
+    pub fn dump_structure<W: fmt::Write>(&self, w: &mut W) -> fmt::Result
+        where K::Component: fmt::Debug + 'static,
+    {
+        let rendering = self.rendering;
+        self.tree.dump(w, &|label: &[K::Component], w: &mut W| K::fmt_label(label, rendering, w))
+    }
+
+    /// Returns how the keys of the map are rendered in its debugging output.
+    pub fn key_rendering(&self) -> KeyRendering {
+        self.rendering
+    }
+
+    /// Sets how the keys of the map are rendered in its debugging output, e.g. in hexadecimal
+    /// when they hold binary data.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::{RadixMap, KeyRendering};
+    ///
+    /// let mut map = RadixMap::new();
+    /// // the keys only share the first byte of their first character
+    /// map.insert("é", 1);
+    /// map.insert("è", 2);
+    ///
+    /// let mut dump = String::new();
+    /// map.dump_structure(&mut dump).unwrap();
+    /// assert_eq!(dump, "\
+    /// (root) (2)
+    ///   \"\\xc3\" (2)
+    ///     \"\\xa8\" * (1)
+    ///     \"\\xa9\" * (1)
+    /// ");
+    ///
+    /// map.set_key_rendering(KeyRendering::Hex);
+    /// let mut dump = String::new();
+    /// map.dump_structure(&mut dump).unwrap();
+    /// assert!(dump.contains("[c3] (2)"));
+    ///
+    /// map.set_key_rendering(KeyRendering::Text);
+    /// let mut dump = String::new();
+    /// map.dump_structure(&mut dump).unwrap();
+    /// assert!(dump.contains("\"\u{fffd}\" (2)"));
+    /// ```
+    pub fn set_key_rendering(&mut self, rendering: KeyRendering) {
+        self.rendering = rendering;
+    }
+
+    /// Checks the invariants of the tree backing the map, for debugging.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an invariant doesn't hold, with a rendering of the tree (see
+    /// [`dump_structure`]).
+    ///
+    /// [`dump_structure`]: #method.dump_structure
+    pub fn check_invariants(&self)
+        where K::Component: fmt::Debug + 'static,
+    {
+        if let Err(e) = self.tree.check() {
+            let mut dump = String::new();
+            let _ = self.dump_structure(&mut dump);
+            panic!("broken radix tree invariant: {}\n{}", e, dump);
+        }
+    }
+
+    /// Starts building a query over the map's entries, see [`Query`].
+    ///
+    /// [`Query`]: struct.Query.html
+    pub fn query(&self) -> Query<'_, K, V> {
+        Query {
+            map: self,
+            prefix: Vec::new(),
+            min_len: 0,
+            max_len: usize::MAX,
+            filter: Box::new(|_| true),
+            limit: usize::MAX,
+        }
+    }
+
+    /// Gets the entries whose key starts with `prefix` and is exactly `len` components long (bytes
+    /// for `str` keys), sorted by key.
+    ///
+    /// The nodes keep track of the lengths of the keys below them, so that subtrees without keys
+    /// of the right length aren't visited. See [`query`] for ranges of lengths.
+    ///
+    /// [`query`]: #method.query
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let words: RadixMap<str, ()> = ["crane", "crate", "crater", "cry", "slate"].iter().map(|w| (w, ())).collect();
+    ///
+    /// let candidates: Vec<_> = words.find_len("cra", 5).into_iter().map(|(k, _)| k).collect();
+    /// assert_eq!(candidates, vec!["crane", "crate"]);
+    /// ```
+    pub fn find_len<Q: AsKey<K> + ?Sized>(&self, prefix: &Q, len: usize) -> Vec<(K::Owned, &V)> {
+        self.query().prefix(prefix).min_len(len).max_len(len).run()
+    }
+
+    /// Starts an autocompletion session, see [`Completer`].
+    ///
+    /// [`Completer`]: struct.Completer.html
+    pub fn completer(&self) -> Completer<'_, K, V> {
+        Completer {
+            cursor: self.tree.cursor(),
+        }
+    }
+
+    /// Starts validating a key as it is typed, see [`PrefixCursor`].
+    ///
+    /// [`PrefixCursor`]: struct.PrefixCursor.html
+    pub fn prefix_cursor(&self) -> PrefixCursor<'_, K, V> {
+        PrefixCursor {
+            cursor: self.tree.prefix_cursor(),
+        }
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, compared with `compare`,
+    /// sorted by key.
+    ///
+    /// The comparison happens while descending the tree, so the stored keys stay exact: a query
+    /// may match several branches, e.g. all the case variations of a prefix.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    /// use panoradix::compare::CompareMode;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("Café", 1);
+    /// map.insert("cafeteria", 2);
+    /// map.insert("cab", 3);
+    ///
+    /// let matches: Vec<_> = map.find_with("cafe", CompareMode::IGNORE_CASE | CompareMode::IGNORE_DIACRITICS)
+    ///     .map(|(k, _)| k)
+    ///     .collect();
+    /// assert_eq!(matches, vec!["Café", "cafeteria"]);
+    /// ```
+    pub fn find_with<'a, C: Compare<K>, Q>(&'a self, key: &Q, compare: C) -> MatchesWith<'a, K, V>
+        where Q: AsKey<K> + ?Sized,
+    {
+        let key = key.as_key();
+        let step = |state: &mut C::State, component: &K::Component| compare.step(key, state, component);
+        let complete = |state: &C::State| compare.is_complete(key, state);
+
+        let matches = self.tree.find_by(compare.start(key), &step, &complete);
+        MatchesWith {
+            matches: matches.into_iter().flatten(),
+        }
+    }
+
+    /// Returns a reference to the value of the key matching `key` once each of its components is
+    /// rewritten by `rewrite`.
+    ///
+    /// The components are rewritten one at a time while descending the tree, instead of
+    /// allocating a rewritten copy of the key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("content-type", 1);
+    ///
+    /// assert_eq!(map.get_rewritten("Content-Type", |c| c.to_ascii_lowercase()), Some(&1));
+    /// assert_eq!(map.get_rewritten("Content", |c| c.to_ascii_lowercase()), None);
+    /// ```
+    pub fn get_rewritten<F, Q: AsKey<K> + ?Sized>(&self, key: &Q, rewrite: F) -> Option<&V>
+        where F: FnMut(&K::Component) -> K::Component,
+    {
+        self.tree.get_by_components(key.as_key().as_slice().iter().map(rewrite))
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key` once each of its components
+    /// is rewritten by `rewrite`, sorted by key.
+    ///
+    /// Unlike [`find_with`], which compares the query with every branch of the tree, this only
+    /// descends along the rewritten key.
+    ///
+    /// [`find_with`]: #method.find_with
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("accept", 1);
+    /// map.insert("accept-encoding", 2);
+    /// map.insert("age", 3);
+    ///
+    /// let matches: Vec<_> = map.find_rewritten("Accept", |c| c.to_ascii_lowercase()).map(|(k, _)| k).collect();
+    /// assert_eq!(matches, vec!["accept", "accept-encoding"]);
+    /// ```
+    pub fn find_rewritten<'a, F, Q>(&'a self, key: &Q, rewrite: F) -> Matches<'a, K, V>
+        where Q: AsKey<K> + ?Sized,
+              F: FnMut(&K::Component) -> K::Component,
+    {
+        Matches {
+            matches: self.tree.find_by_components(key.as_key().as_slice().iter().map(rewrite)),
+        }
+    }
+
+    /// Gets an iterator over the entries of several maps, sorted by key, where each key is yielded
+    /// once along with its values in the maps holding it (in the order of `maps`).
+    ///
+    /// The maps are traversed side by side, holding a single entry of each at a time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let a: RadixMap<str, i32> = vec![("a", 1), ("c", 3)].into_iter().collect();
+    /// let b: RadixMap<str, i32> = vec![("b", 2), ("c", 4)].into_iter().collect();
+    ///
+    /// let merged: Vec<_> = RadixMap::merge_iter(&[&a, &b]).collect();
+    /// assert_eq!(merged, vec![
+    ///     ("a".to_string(), vec![&1]),
+    ///     ("b".to_string(), vec![&2]),
+    ///     ("c".to_string(), vec![&3, &4]),
+    /// ]);
+    /// ```
+    pub fn merge_iter<'a>(maps: &[&'a RadixMap<K, V>]) -> MergeIter<'a, K, V> {
+        let mut merge = MergeIter {
+            iters: maps.iter().map(|m| m.tree.iter()).collect(),
+            heads: Vec::with_capacity(maps.len()),
+            heap: BinaryHeap::with_capacity(maps.len()),
+        };
+
+        for i in 0..maps.len() {
+            merge.heads.push(None);
+            merge.advance(i);
+        }
+        merge
+    }
+
+    /// Gets the entries whose key has the same length as `pattern` and matches each of its
+    /// components, `None` being a wildcard, sorted by key.
+    ///
+    /// Only the branches starting with a fixed component of the pattern are descended, which
+    /// keeps searches cheap on small alphabets, e.g. nucleotides.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut kmers: RadixMap<[u8], usize> = RadixMap::new();
+    /// kmers.insert(b"ACGT", 1);
+    /// kmers.insert(b"AGGT", 2);
+    /// kmers.insert(b"TCGT", 3);
+    ///
+    /// let matches = kmers.find_pattern(&[Some(b'A'), None, Some(b'G'), Some(b'T')]);
+    /// assert_eq!(matches, vec![(b"ACGT".to_vec(), &1), (b"AGGT".to_vec(), &2)]);
+    /// ```
+    pub fn find_pattern(&self, pattern: &[Option<K::Component>]) -> Vec<(K::Owned, &V)> {
+        self.tree.find_pattern(pattern).into_iter()
+            .map(|(key, value)| (K::from_vec(key), value))
+            .collect()
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, from the highest to the
+    /// lowest weight (as computed by `weight`), ties being sorted by key.
+    ///
+    /// The weights of the matches are gathered in a heap, which is built in linear time, and the
+    /// keys are only built as the entries are yielded: taking the first few entries, e.g. for
+    /// ranked completions, doesn't sort nor allocate all of them.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut frequencies = RadixMap::new();
+    /// frequencies.insert("the", 120);
+    /// frequencies.insert("then", 15);
+    /// frequencies.insert("they", 40);
+    /// frequencies.insert("theme", 15);
+    ///
+    /// let ranked: Vec<_> = frequencies.find_by_weight("the", |&f| f).map(|(k, _)| k).collect();
+    /// assert_eq!(ranked, vec!["the", "they", "theme", "then"]);
+    /// ```
+    pub fn find_by_weight<'a, W, F, Q>(&'a self, key: &Q, weight: F) -> ByWeight<'a, K, V, W>
+        where Q: AsKey<K> + ?Sized,
+              W: Ord,
+              F: Fn(&V) -> W,
+    {
+        let mut matches = self.tree.find(key.as_key().as_slice());
+        let mut heap = Vec::with_capacity(matches.len());
+        let mut rank = 0;
+        while let Some((_, value)) = matches.next_raw() {
+            heap.push((weight(value), Reverse(rank)));
+            rank += 1;
+        }
+
+        ByWeight {
+            heap: BinaryHeap::from(heap),
+            matches,
+        }
+    }
+
+    /// Gets a handle to the subtree of the entries whose key starts with `prefix`, to iterate
+    /// over them later through [`resolve`] without searching for the prefix again.
+    ///
+    /// The handle holds the path to the subtree and is invalidated by any change to the structure
+    /// of the map, i.e. when a key is added or removed.
+    ///
+    /// [`resolve`]: #method.resolve
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("user:1:name", "alice");
+    /// map.insert("user:1:mail", "alice@example.com");
+    /// map.insert("user:2:name", "bob");
+    ///
+    /// let id = map.node_id("user:1:").unwrap();
+    /// assert_eq!(map.resolve(&id).unwrap().count(), 2);
+    ///
+    /// // replacing values doesn't change the map's structure
+    /// map.insert("user:1:name", "carol");
+    /// assert!(map.resolve(&id).is_some());
+    ///
+    /// map.remove("user:2:name");
+    /// assert!(map.resolve(&id).is_none());
+    /// ```
+    pub fn node_id<Q: AsKey<K> + ?Sized>(&self, prefix: &Q) -> Option<NodeId<K>> {
+        self.tree.node_path(prefix.as_key().as_slice()).map(|(path, prefix)| NodeId {
+            generation: self.tree.generation(),
+            path,
+            prefix,
+        })
+    }
+
+    /// Gets an iterator over the entries of the subtree of a handle returned by [`node_id`],
+    /// sorted by key, or `None` if the handle was invalidated.
+    ///
+    /// Handles returned by another map are invalid too, since no two maps share the generation
+    /// of their structure (but for new empty maps, which have no entries to resolve anyway).
+    ///
+    /// [`node_id`]: #method.node_id
+    pub fn resolve<'a>(&'a self, id: &NodeId<K>) -> Option<Matches<'a, K, V>> {
+        if id.generation != self.tree.generation() {
+            return None;
+        }
+
+        self.tree.resolve(&id.path, id.prefix.clone())
+            .map(|matches| Matches { matches })
+    }
+
+    /// Gets the value of the key of a handle returned by [`node_id`] (see [`NodeId::prefix`]),
+    /// following its path without comparing any key, or `None` if the handle was invalidated or
+    /// the key isn't in the map.
+    ///
+    /// [`node_id`]: #method.node_id
+    /// [`NodeId::prefix`]: struct.NodeId.html#method.prefix
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("user:1", "alice");
+    /// map.insert("user:2", "bob");
+    ///
+    /// let id = map.node_id("user:1").unwrap();
+    /// map.insert("user:1", "carol");
+    /// assert_eq!(map.resolve_value(&id), Some(&"carol"));
+    ///
+    /// // "user:" is the prefix of the keys, not one of them
+    /// assert_eq!(map.resolve_value(&map.node_id("user:").unwrap()), None);
+    /// ```
+    pub fn resolve_value(&self, id: &NodeId<K>) -> Option<&V> {
+        if id.generation != self.tree.generation() {
+            return None;
+        }
+
+        self.tree.value_at(&id.path)
+    }
+
+    /// Gets an iterator over the entries whose key is a prefix of `key` (including `key` itself),
+    /// from the shortest to the longest key.
+    ///
+    /// The map is only descended once along `key`, which doesn't have to be in the map.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut acl = RadixMap::new();
+    /// acl.insert("/", "read");
+    /// acl.insert("/home/", "write");
+    /// acl.insert("/home/alice/", "none");
+    ///
+    /// let inherited: Vec<_> = acl.ancestors("/home/bob/notes").collect();
+    /// assert_eq!(inherited, vec![("/".to_string(), &"read"), ("/home/".to_string(), &"write")]);
+    /// ```
+    pub fn ancestors<'a, 'k>(&'a self, key: &'k K) -> Ancestors<'a, 'k, K, V> {
+        Ancestors {
+            ancestors: self.tree.ancestors(key.as_slice()),
+            key: key.as_slice(),
+        }
+    }
+
+    /// Gets the entry with the smallest key starting with `key`.
+    ///
+    /// This is equivalent to `find(key).next()`, but only descends the map once, without any
+    /// iteration state.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("cargo", 1);
+    /// map.insert("cat", 2);
+    /// map.insert("car", 3);
+    ///
+    /// assert_eq!(map.find_one("ca"), Some(("car".to_string(), &3)));
+    /// assert_eq!(map.find_one("d"), None);
+    /// ```
+    pub fn find_one<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> Option<(K::Owned, &V)> {
+        self.tree.find_first(key.as_key().as_slice()).map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    /// Counts the keys sharing each prefix of `prefix_len` components, sorted by prefix.
+    ///
+    /// This is equivalent to calling `find(prefix).count()` for every such prefix, but the map is
+    /// only traversed down to `prefix_len` components. Keys shorter than `prefix_len` aren't
+    /// counted.
+    ///
+    /// Note that for `str` keys the length is in bytes: a prefix ending in the middle of a
+    /// character is extended to the end of the character, so that distinct characters are
+    /// counted apart.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("user:1", 1);
+    /// map.insert("user:2", 2);
+    /// map.insert("group:1", 3);
+    /// map.insert("g", 4);
+    ///
+    /// assert_eq!(map.group_count(2), vec![
+    ///     ("gr".to_string(), 1),
+    ///     ("us".to_string(), 2),
+    /// ]);
+    /// ```
+    pub fn group_count(&self, prefix_len: usize) -> Vec<(K::Owned, usize)> {
+        self.tree.group_count(prefix_len, &K::continues).into_iter()
+            .map(|(prefix, count)| (K::from_vec(prefix), count))
+            .collect()
+    }
+
+    /// Computes a digest of the map's contents with a hasher of type `H`.
+    ///
+    /// The digest only depends on the (key, value) pairs of the map, not on the order in which
+    /// they were inserted nor on the shape of the tree, so replicas holding the same entries
+    /// compute the same digest as long as they use the same deterministic hasher. The map is
+    /// traversed once and no key is allocated.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut a = RadixMap::new();
+    /// a.insert("a", 1);
+    /// a.insert("b", 2);
+    ///
+    /// let mut b = RadixMap::new();
+    /// b.insert("b", 2);
+    /// b.insert("a", 1);
+    ///
+    /// assert_eq!(a.content_hash::<DefaultHasher>(), b.content_hash::<DefaultHasher>());
+    ///
+    /// b.insert("b", 3);
+    /// assert_ne!(a.content_hash::<DefaultHasher>(), b.content_hash::<DefaultHasher>());
+    /// ```
+    pub fn content_hash<H>(&self) -> u64
+        where H: Hasher + Default,
+              K::Component: Hash,
+              V: Hash,
+    {
+        let mut hasher = H::default();
+        self.len().hash(&mut hasher);
+
+        let mut it = self.tree.iter();
+        while let Some((key, value)) = it.next_raw() {
+            key.hash(&mut hasher);
+            value.hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    /// Computes a digest of the entries whose key starts with `prefix`, with a hasher of type `H`.
+    ///
+    /// Each entry is hashed on its own and the digests are summed, so the digest of a prefix is
+    /// the sum of the digests of any partition of its entries: two processes holding large maps
+    /// can compare the digest of a prefix and only descend into longer prefixes where they
+    /// differ, instead of exchanging all the entries.
+    ///
+    /// Note that the digest is computed by traversing the entries under the prefix, a
+    /// [`MerkleRadixMap`] maintains the same digests in its nodes instead.
+    ///
+    /// [`MerkleRadixMap`]: ../merkle/struct.MerkleRadixMap.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut a = RadixMap::new();
+    /// a.insert("user:1", "alice");
+    /// a.insert("user:2", "bob");
+    /// a.insert("group:1", "admins");
+    ///
+    /// let mut b = RadixMap::new();
+    /// b.insert("user:1", "alice");
+    /// b.insert("user:2", "eve");
+    /// b.insert("group:1", "admins");
+    ///
+    /// assert_ne!(a.prefix_hash::<DefaultHasher>(""), b.prefix_hash::<DefaultHasher>(""));
+    /// assert_eq!(a.prefix_hash::<DefaultHasher>("group:"), b.prefix_hash::<DefaultHasher>("group:"));
+    /// assert_ne!(a.prefix_hash::<DefaultHasher>("user:"), b.prefix_hash::<DefaultHasher>("user:"));
+    /// assert_ne!(a.prefix_hash::<DefaultHasher>("user:2"), b.prefix_hash::<DefaultHasher>("user:2"));
+    /// ```
+    pub fn prefix_hash<H>(&self, prefix: &(impl AsKey<K> + ?Sized)) -> u64
+        where H: Hasher + Default,
+              K::Component: Hash,
+              V: Hash,
+    {
+        let mut matches = self.tree.find(prefix.as_key().as_slice());
+        let mut digest = 0u64;
+        while let Some((key, value)) = matches.next_raw() {
+            let mut hasher = H::default();
+            key.hash(&mut hasher);
+            value.hash(&mut hasher);
+            digest = digest.wrapping_add(hasher.finish());
+        }
+
+        digest
+    }
+
+    /// Computes the operations transforming this map into `other`.
+    ///
+    /// The result is minimal: keys only in this map are removed, keys only in `other` are
+    /// inserted, and keys in both maps are only re-inserted if their values differ. Operations
+    /// are sorted by key. Both maps are traversed once, side by side.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    /// use panoradix::map::Op;
+    ///
+    /// let mut a = RadixMap::new();
+    /// a.insert("a", 1);
+    /// a.insert("b", 2);
+    ///
+    /// let mut b = RadixMap::new();
+    /// b.insert("b", 3);
+    /// b.insert("c", 4);
+    ///
+    /// let delta = a.delta_to(&b);
+    /// assert_eq!(delta, vec![
+    ///     Op::Remove("a".to_string()),
+    ///     Op::Insert("b".to_string(), 3),
+    ///     Op::Insert("c".to_string(), 4),
+    /// ]);
+    ///
+    /// a.apply_delta(delta);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn delta_to(&self, other: &RadixMap<K, V>) -> Vec<Op<K, V>>
+        where V: Clone + PartialEq,
+    {
+        let mut ops = Vec::new();
+
+        let mut ours = self.tree.iter().peekable();
+        let mut theirs = other.tree.iter().peekable();
+        loop {
+            let ordering = match (ours.peek(), theirs.peek()) {
+                (Some((a, _)), Some((b, _))) => a.cmp(b),
+                (Some(_), None)              => Ordering::Less,
+                (None, Some(_))              => Ordering::Greater,
+                (None, None)                 => break,
+            };
+
+            match ordering {
+                Ordering::Less => {
+                    let (key, _) = ours.next().unwrap();
+                    ops.push(Op::Remove(K::from_vec(key)));
+                },
+                Ordering::Greater => {
+                    let (key, value) = theirs.next().unwrap();
+                    ops.push(Op::Insert(K::from_vec(key), value.clone()));
+                },
+                Ordering::Equal => {
+                    let (_, our_value) = ours.next().unwrap();
+                    let (key, value) = theirs.next().unwrap();
+                    if our_value != value {
+                        ops.push(Op::Insert(K::from_vec(key), value.clone()));
+                    }
+                },
+            }
+        }
+
+        ops
+    }
+
+    /// Applies operations, typically computed by [`delta_to`], to the map.
+    ///
+    /// [`delta_to`]: #method.delta_to
+    pub fn apply_delta<I>(&mut self, ops: I)
+        where I: IntoIterator<Item=Op<K, V>>,
+    {
+        for op in ops {
+            match op {
+                Op::Insert(key, value) => { self.insert(key.borrow(), value); },
+                Op::Remove(key)        => { self.remove(key.borrow()); },
+            }
+        }
+    }
+
+    /// Gets the entry of the given rank, i.e. the `n`-th smallest key (starting at 0).
+    ///
+    /// The number of entries under each node is tracked by the tree, so this only costs a
+    /// descent from the root.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, usize> = vec![("b", 1), ("a", 0), ("c", 2)].into_iter().collect();
+    /// assert_eq!(map.select(1), Some(("b".to_string(), &1)));
+    /// assert_eq!(map.select(3), None);
+    /// ```
+    pub fn select(&self, n: usize) -> Option<(K::Owned, &V)> {
+        self.tree.select(n).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    /// Gets the entry of the given rank among the keys starting with `prefix`, e.g. to get a page
+    /// of completions at an offset without iterating over the previous pages.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["apple", "apricot", "avocado", "banana"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.select_in("a", 2), Some(("avocado".to_string(), &())));
+    /// assert_eq!(map.select_in("b", 1), None);
+    /// ```
+    pub fn select_in<Q: AsKey<K> + ?Sized>(&self, prefix: &Q, n: usize) -> Option<(K::Owned, &V)> {
+        self.tree.select_in(prefix.as_key().as_slice(), n).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    /// Gets the entry of the shortest key, the smallest one if there are several.
+    ///
+    /// The lengths of the keys are tracked by the tree, so this only costs a descent from the
+    /// root.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["tea", "teapot", "ten", "tomato"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.shortest_key(), Some(("tea".to_string(), &())));
+    /// assert_eq!(map.longest_key(), Some(("teapot".to_string(), &())));
+    /// ```
+    pub fn shortest_key(&self) -> Option<(K::Owned, &V)> {
+        self.tree.shortest_key(&[]).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    /// Gets the entry of the shortest key starting with `prefix`, the smallest one if there are
+    /// several.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["tea", "teapot", "ten", "tomato"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.shortest_key_in("teap"), Some(("teapot".to_string(), &())));
+    /// assert_eq!(map.shortest_key_in("x"), None);
+    /// ```
+    pub fn shortest_key_in<Q: AsKey<K> + ?Sized>(&self, prefix: &Q) -> Option<(K::Owned, &V)> {
+        self.tree.shortest_key(prefix.as_key().as_slice()).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    /// Gets the entry of the longest key, the smallest one if there are several.
+    ///
+    /// See [`shortest_key`](#method.shortest_key).
+    pub fn longest_key(&self) -> Option<(K::Owned, &V)> {
+        self.tree.longest_key(&[]).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    /// Gets the entry of the longest key starting with `prefix`, the smallest one if there are
+    /// several.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["tea", "teapot", "ten", "tomato"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.longest_key_in("ten"), Some(("ten".to_string(), &())));
+    /// assert_eq!(map.longest_key_in("t"), Some(("teapot".to_string(), &())));
+    /// ```
+    pub fn longest_key_in<Q: AsKey<K> + ?Sized>(&self, prefix: &Q) -> Option<(K::Owned, &V)> {
+        self.tree.longest_key(prefix.as_key().as_slice()).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    /// Returns the number of keys sorting before `key`, whether it's in the map or not.
+    ///
+    /// Like [`select`], this only costs a descent from the root (and summing the counts of the
+    /// edges skipped at each node).
+    ///
+    /// [`select`]: #method.select
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["a", "ab", "b"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.rank("ab"), 1);
+    /// assert_eq!(map.rank("aa"), 1);
+    /// assert_eq!(map.rank("z"), 3);
+    /// ```
+    pub fn rank<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> usize {
+        self.tree.rank(key.as_key().as_slice())
+    }
+
+    /// Gets the key at the quantile `q` of the sorted keys, i.e. the key of rank `q * len()`
+    /// (rounded down, the last key for `q == 1.0`), consistently with [`partition_points`].
+    ///
+    /// [`partition_points`]: #method.partition_points
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` isn't between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["a", "b", "c", "d", "e"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.quantile_key(0.0), Some("a".to_string()));
+    /// assert_eq!(map.quantile_key(0.5), Some("c".to_string()));
+    /// assert_eq!(map.quantile_key(0.99), Some("e".to_string()));
+    /// assert_eq!(map.quantile_key(1.0), Some("e".to_string()));
+    /// ```
+    pub fn quantile_key(&self, q: f64) -> Option<K::Owned> {
+        assert!((0.0..=1.0).contains(&q), "quantile {} should be between 0 and 1", q);
+
+        let len = self.len();
+        let rank = ((q * len as f64) as usize).min(len.checked_sub(1)?);
+        self.tree.select(rank).map(|(key, _)| K::from_vec(key))
+    }
+
+    /// Analyzes the keys of the map, suggesting how to encode them into components.
+    ///
+    /// This walks the whole map, to be run once on a representative sample of keys rather than
+    /// on every change.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    /// use panoradix::map::Granularity;
+    ///
+    /// let map: RadixMap<str, ()> = ["ACGTAC", "ACGTTT", "ACCA", "GATTACA"].iter().map(|k| (k, ())).collect();
+    ///
+    /// let suggestion = map.suggest_encoding();
+    /// assert_eq!(suggestion.alphabet_size(), 4);
+    /// assert_eq!(suggestion.average_shared_prefix(), 2.0);
+    /// assert_eq!(suggestion.granularity(), Granularity::Nibble);
+    /// ```
+    pub fn suggest_encoding(&self) -> EncodingSuggestion {
+        let mut alphabet = BTreeSet::new();
+        let (mut total_len, mut total_shared) = (0, 0);
+        let mut last: Vec<K::Component> = Vec::new();
+
+        let mut iter = self.tree.iter();
+        while let Some((key, _)) = iter.next_raw() {
+            alphabet.extend(key.iter().cloned());
+            total_len += key.len();
+            total_shared += last.iter().zip(key).take_while(|&(a, b)| a == b).count();
+
+            last.clear();
+            last.extend_from_slice(key);
+        }
+
+        let (nodes, edges) = self.tree.visit_postorder(|_, children: Vec<(usize, usize)>| {
+            let (nodes, edges) = children.iter().fold((0, 0), |(n, e), &(cn, ce)| (n + cn, e + ce));
+            if children.is_empty() {
+                (nodes, edges)
+            } else {
+                (nodes + 1, edges + children.len())
+            }
+        });
+
+        let average = |total: usize, n: usize| if n == 0 { 0.0 } else { total as f64 / n as f64 };
+        EncodingSuggestion {
+            alphabet_size: alphabet.len(),
+            average_key_len: average(total_len, self.len()),
+            average_shared_prefix: average(total_shared, self.len().saturating_sub(1)),
+            average_fan_out: average(edges, nodes),
+        }
+    }
+
+    /// Chooses keys splitting the map into `n` ranges holding roughly the same number of entries.
+    ///
+    /// The returned keys are sorted, each one being the first key of a range: the first range
+    /// goes from the start of the map up to (excluding) the first returned key, and the last
+    /// range starts at the last returned key. At most `n - 1` keys are returned, fewer if the
+    /// map doesn't hold enough entries.
+    ///
+    /// The number of entries under each node is tracked by the tree, so finding each split key
+    /// only costs a descent from the root.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["a", "b", "c", "d", "e", "f"].iter().map(|k| (k, ())).collect();
+    /// assert_eq!(map.partition_points(3), vec!["c", "e"]);
+    /// assert_eq!(map.partition_points(1), Vec::<String>::new());
+    /// ```
+    pub fn partition_points(&self, n: usize) -> Vec<K::Owned> {
+        let len = self.len();
+
+        let mut points = Vec::with_capacity(n.saturating_sub(1));
+        let mut last_rank = 0;
+        for i in 1..n {
+            let rank = i * len / n;
+            if rank == last_rank {
+                continue;
+            }
+            last_rank = rank;
+
+            if let Some((key, _)) = self.tree.select(rank) {
+                points.push(K::from_vec(key));
+            }
+        }
+
+        points
+    }
+
+    /// Folds the map bottom-up, visiting each node of the tree after all of its children.
+    ///
+    /// The visitor is given the entry stored at the node (if any) and the results of visiting the
+    /// node's children (sorted by key), which makes it possible to compute aggregates over each
+    /// subtree in a single pass. The result of visiting the root is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut sizes = RadixMap::new();
+    /// sizes.insert("/src/lib.rs", 10);
+    /// sizes.insert("/src/map.rs", 20);
+    /// sizes.insert("/README.md", 5);
+    ///
+    /// // the largest file in the whole tree
+    /// let largest = sizes.visit_postorder(|entry, children: Vec<Option<(String, i32)>>| {
+    ///     let own = entry.map(|(path, &size)| (path, size));
+    ///     children.into_iter().chain(Some(own)).flatten().max_by_key(|&(_, size)| size)
+    /// });
+    ///
+    /// assert_eq!(largest, Some(("/src/map.rs".to_string(), 20)));
+    /// ```
+    pub fn visit_postorder<A, F>(&self, mut f: F) -> A
+        where F: FnMut(Option<(K::Owned, &V)>, Vec<A>) -> A,
+    {
+        self.tree.visit_postorder(|entry, children| {
+            f(entry.map(|(k, v)| (K::from_vec(k.to_owned()), v)), children)
+        })
+    }
+}
+
+impl<K: Key + ?Sized, T: Clone> RadixMap<K, Box<[T]>> {
+    /// Inserts a copy of a slice as the value of a key, allocating it once, directly as a boxed
+    /// slice, and returns the value previously stored at this key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut blobs: RadixMap<str, Box<[u8]>> = RadixMap::new();
+    /// blobs.insert_value_slice("a", b"payload");
+    ///
+    /// assert_eq!(blobs.get_slice("a"), Some(&b"payload"[..]));
+    /// assert_eq!(blobs.insert_value_slice("a", b"other").as_deref(), Some(&b"payload"[..]));
+    /// ```
+    pub fn insert_value_slice(&mut self, key: &K, value: &[T]) -> Option<Box<[T]>> {
+        self.insert(key, Box::from(value))
+    }
+
+    /// Returns the value of a key as a slice.
+    pub fn get_slice<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> Option<&[T]> {
+        self.get(key).map(|value| &value[..])
+    }
+}
+
+impl<K: Key + ?Sized> RadixMap<K, Box<str>> {
+    /// Inserts a copy of a string as the value of a key, allocating it once, directly as a boxed
+    /// `str`, and returns the value previously stored at this key.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut titles: RadixMap<str, Box<str>> = RadixMap::new();
+    /// titles.insert_value_str("/", "Home");
+    ///
+    /// assert_eq!(titles.get_str("/"), Some("Home"));
+    /// ```
+    pub fn insert_value_str(&mut self, key: &K, value: &str) -> Option<Box<str>> {
+        self.insert(key, Box::from(value))
+    }
+
+    /// Returns the value of a key as a `str`.
+    pub fn get_str<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> Option<&str> {
+        self.get(key).map(|value| &value[..])
+    }
+}
+
+impl<T: KeyComponent, V> RadixMap<[T], V> {
+    /// Removes the entries for which `keep` returns `false`, walking the map once. Returns the
+    /// number of entries removed.
+    ///
+    /// See the `retain` of maps of `str`.
+    pub fn retain<F>(&mut self, keep: F) -> usize
+        where F: FnMut(&[T], &mut V) -> bool,
+    {
+        self.retain_and_prune(&[], keep)
+    }
+
+    /// Gets an iterator removing the entries for which `pred` returns `true`, and moving them out
+    /// of the map in key order.
+    ///
+    /// See the `extract_if` of maps of `str`.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, [T], V, F>
+        where F: FnMut(&[T], &mut V) -> bool,
+    {
+        self.extract_components_if(pred)
+    }
+}
+
+impl<V> RadixMap<str, V> {
+    /// Removes the entries for which `keep` returns `false`, walking the map once. Returns the
+    /// number of entries removed.
+    ///
+    /// This is a shorthand for [`retain_and_prune`], so the map is compacted as well. Maps of
+    /// other keys than `str` and slices can call it directly, with the components of the keys.
+    ///
+    /// [`retain_and_prune`]: #method.retain_and_prune
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut map: RadixMap<str, i32> = vec![("a", 1), ("ab", -2), ("tmp", 3)].into_iter().collect();
+    /// assert_eq!(map.retain(|key, &mut value| value > 0 && !key.starts_with("tmp")), 2);
+    /// assert_eq!(map.keys().collect::<Vec<_>>(), vec!["a"]);
+    /// ```
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+        where F: FnMut(&str, &mut V) -> bool,
+    {
+        self.retain_and_prune(&[], |key, value| keep(borrow_str(key), value))
+    }
+
+    /// Gets an iterator removing the entries for which `pred` returns `true`, and moving them out
+    /// of the map in key order.
+    ///
+    /// Like `Vec::extract_if`, the entries are removed as the iterator is consumed: those it
+    /// didn't reach when dropped are kept, whether `pred` matches them or not. The nodes left
+    /// empty are compacted when the iterator is dropped, as [`retain`] does.
+    ///
+    /// [`retain`]: #method.retain
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let mut shard: RadixMap<str, u32> = vec![("eu/fr", 1), ("us/ca", 2), ("eu/de", 3)].into_iter().collect();
+    /// let mut other: RadixMap<str, u32> = RadixMap::new();
+    /// for (key, value) in shard.extract_if(|key, _| key.starts_with("eu/")) {
+    ///     other.insert(&key, value);
+    /// }
+    ///
+    /// assert_eq!(shard.keys().collect::<Vec<_>>(), vec!["us/ca"]);
+    /// assert_eq!(other.keys().collect::<Vec<_>>(), vec!["eu/de", "eu/fr"]);
+    ///
+    /// // the entries are removed lazily
+    /// let first = other.extract_if(|_, _| true).next();
+    /// assert_eq!(first, Some(("eu/de".to_string(), 3)));
+    /// assert_eq!(other.keys().collect::<Vec<_>>(), vec!["eu/fr"]);
+    /// ```
+    pub fn extract_if<F>(&mut self, mut pred: F) -> ExtractIf<'_, str, V, impl FnMut(&[u8], &mut V) -> bool>
+        where F: FnMut(&str, &mut V) -> bool,
+    {
+        self.extract_components_if(move |key, value| pred(borrow_str(key), value))
+    }
+
+    /// Like [`get_rewritten`], rewriting the characters of the key instead of its bytes.
+    ///
+    /// [`get_rewritten`]: #method.get_rewritten
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// // Arabic-Indic digits are looked up as ASCII digits
+    /// let ascii_digit = |c: char| match c {
+    ///     '٠'..='٩' => (b'0' + (c as u32 - '٠' as u32) as u8) as char,
+    ///     c         => c,
+    /// };
+    ///
+    /// let mut map = RadixMap::new();
+    /// map.insert("room 42", "2nd floor");
+    /// assert_eq!(map.get_rewritten_chars("room ٤٢", ascii_digit), Some(&"2nd floor"));
+    /// ```
+    pub fn get_rewritten_chars<F>(&self, key: &str, rewrite: F) -> Option<&V>
+        where F: FnMut(char) -> char,
+    {
+        self.tree.get_by_components(rewrite_chars(key, rewrite))
+    }
+
+    /// Like [`find_rewritten`], rewriting the characters of the key instead of its bytes.
+    ///
+    /// [`find_rewritten`]: #method.find_rewritten
+    pub fn find_rewritten_chars<'a, F>(&'a self, key: &str, rewrite: F) -> Matches<'a, str, V>
+        where F: FnMut(char) -> char,
+    {
+        Matches {
+            matches: self.tree.find_by_components(rewrite_chars(key, rewrite)),
+        }
+    }
+}
+
+/// The UTF-8 bytes of the characters of `key` rewritten by `rewrite`.
+fn rewrite_chars<'a, F>(key: &'a str, mut rewrite: F) -> impl Iterator<Item=u8> + 'a
+    where F: FnMut(char) -> char + 'a,
+{
+    key.chars().flat_map(move |c| {
+        let mut bytes = [0; 4];
+        let len = rewrite(c).encode_utf8(&mut bytes).len();
+        IntoIterator::into_iter(bytes).take(len)
+    })
+}
+
+impl<K: Key + ?Sized, V> RadixMap<K, V> {
+    pub(crate) fn from_tree(tree: Tree<K::Component, V>) -> RadixMap<K, V> {
+        #[cfg(feature = "paranoid")]
+        let shadow = tree.iter().map(|(key, _)| key).collect();
+
+        let map = RadixMap {
+            tree,
+            rendering: KeyRendering::default(),
+            recent: None,
+            #[cfg(feature = "paranoid")]
+            shadow,
+        };
+        map.verify();
+        map
+    }
+
+    pub(crate) fn insert_components(&mut self, key: &[K::Component], value: V) -> Option<V> {
+        let old = self.tree.insert(key, value);
+        if let Some(ref mut recent) = self.recent {
+            recent.push(key);
+        }
+        #[cfg(feature = "paranoid")]
+        self.shadow.insert(key.to_vec());
+        self.verify();
+        old
+    }
+
+    pub(crate) fn remove_components(&mut self, key: &[K::Component]) -> Option<V> {
+        let old = self.tree.remove(key);
+        #[cfg(feature = "paranoid")]
+        self.shadow.remove(key);
+        self.verify();
+        old
+    }
+
+    #[cfg(feature = "paranoid")]
+    fn verify(&self) {
+        if let Err(e) = self.tree.check() {
+            panic!("paranoid: broken radix tree invariant: {}", e);
+        }
+
+        assert_eq!(self.tree.len(), self.shadow.len(), "paranoid: wrong number of keys");
+
+        let mut it = self.tree.iter();
+        for (i, expected) in self.shadow.iter().enumerate() {
+            let (key, _) = it.next_raw().unwrap();
+            assert!(key == &expected[..], "paranoid: key #{} differs from the reference", i);
+        }
+    }
+
+    #[cfg(not(feature = "paranoid"))]
+    #[inline(always)]
+    fn verify(&self) {}
+
+    /// Run a pass removing entries from the tree, keeping the reference set of the `paranoid`
+    /// feature in sync even if the pass panics.
+    #[cfg(feature = "paranoid")]
+    fn removing<T, F: FnOnce(&mut Tree<K::Component, V>) -> T>(&mut self, pass: F) -> T {
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| pass(&mut self.tree)));
+        let tree = &self.tree;
+        self.shadow.retain(|key| tree.get(key).is_some());
+        result.unwrap_or_else(|e| ::std::panic::resume_unwind(e))
+    }
+
+    #[cfg(not(feature = "paranoid"))]
+    #[inline(always)]
+    fn removing<T, F: FnOnce(&mut Tree<K::Component, V>) -> T>(&mut self, pass: F) -> T {
+        pass(&mut self.tree)
+    }
+}
+
+#[cfg(feature = "paranoid")]
+impl<K: Key + ?Sized, V> Drop for RadixMap<K, V> {
+    fn drop(&mut self) {
+        if !::std::thread::panicking() {
+            self.verify();
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, V> RadixMap<K, V>
+    where K: Key + ?Sized,
+          K::Component: Send,
+          V: Send,
+{
+    /// Builds a map from an unsorted parallel iterator, using all the threads of rayon's pool.
+    ///
+    /// The entries are sorted in parallel, split in shards of consecutive entries built in
+    /// parallel, which are then grafted together with [`append`]. Since the shards cover disjoint
+    /// ranges of keys, grafting them only touches the nodes along their boundaries.
+    ///
+    /// Like `collect()`, the last value of a key is kept.
+    ///
+    /// [`append`]: #method.append
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// extern crate rayon;
+    /// # extern crate panoradix;
+    ///
+    /// use panoradix::RadixMap;
+    /// use rayon::prelude::*;
+    ///
+    /// # fn main() {
+    /// let words: Vec<String> = (0..1000).rev().map(|i| format!("word{}", i)).collect();
+    /// let map: RadixMap<str, usize> = RadixMap::par_from_iter(words.par_iter().map(|w| (w, w.len())));
+    ///
+    /// assert_eq!(map.len(), 1000);
+    /// assert_eq!(map.find("word99").count(), 11);
+    /// # }
+    /// ```
+    pub fn par_from_iter<I, T>(iter: I) -> RadixMap<K, V>
+        where I: IntoParallelIterator<Item=(T, V)>,
+              T: AsRef<K> + Send,
+    {
+        match RadixMap::try_par_from_iter(iter.into_par_iter().map(Ok::<_, Infallible>)) {
+            Ok(map) => map,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Builds a map like [`par_from_iter`], from a parallel iterator of entries that may fail,
+    /// e.g. while being parsed. Returns one of the errors if there are any, in which case the map
+    /// isn't built.
+    ///
+    /// [`par_from_iter`]: #method.par_from_iter
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// extern crate rayon;
+    /// # extern crate panoradix;
+    ///
+    /// use panoradix::RadixMap;
+    /// use rayon::prelude::*;
+    ///
+    /// # fn main() {
+    /// let parse = |line: &&str| {
+    ///     let mut parts = line.split('=');
+    ///     match (parts.next(), parts.next().map(str::parse::<u32>)) {
+    ///         (Some(key), Some(Ok(value))) => Ok((key.to_string(), value)),
+    ///         _ => Err(format!("invalid line: {:?}", line)),
+    ///     }
+    /// };
+    ///
+    /// let lines = vec!["a=1", "b=2", "ab=3"];
+    /// let map: RadixMap<str, u32> = RadixMap::try_par_from_iter(lines.par_iter().map(parse)).unwrap();
+    /// assert_eq!(map.get("ab"), Some(&3));
+    ///
+    /// let lines = vec!["a=1", "b"];
+    /// assert!(RadixMap::<str, u32>::try_par_from_iter(lines.par_iter().map(parse)).is_err());
+    /// # }
+    /// ```
+    pub fn try_par_from_iter<I, T, E>(iter: I) -> Result<RadixMap<K, V>, E>
+        where I: IntoParallelIterator<Item=Result<(T, V), E>>,
+              T: AsRef<K> + Send,
+              E: Send,
+    {
+        let mut entries: Vec<(Vec<K::Component>, V)> = iter.into_par_iter()
+            .map(|entry| entry.map(|(key, value)| (key.as_ref().as_slice().to_vec(), value)))
+            .collect::<Result<_, E>>()?;
+
+        // the sort is stable, so the last value of a key is still inserted last
+        entries.par_sort_by(|a, b| a.0.cmp(&b.0));
+
+        #[cfg(feature = "paranoid")]
+        let shadow = entries.iter().map(|(key, _)| key.clone()).collect();
+
+        // each thread builds a tree of consecutive entries, which are appended in order
+        let tree = entries.into_par_iter()
+            .fold(Tree::new, |mut tree, (key, value)| {
+                tree.insert(&key, value);
+                tree
+            })
+            .reduce(Tree::new, |mut left, right| {
+                left.append(right);
+                left
+            });
+
+        let map = RadixMap {
+            tree,
+            rendering: KeyRendering::default(),
+            recent: None,
+            #[cfg(feature = "paranoid")]
+            shadow,
+        };
+        map.verify();
+        Ok(map)
+    }
+}
+
+impl<K: Key + ?Sized, V: fmt::Debug> fmt::Debug for RadixMap<K, V>
+    where K::Component: fmt::Debug + 'static,
+{
+    /// Formats the entries of the map like std maps, sorted by key, the keys being rendered in
+    /// hexadecimal if the [`key_rendering`] of the map is `Hex`.
+    ///
+    /// [`key_rendering`]: #method.key_rendering
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, i32> = vec![("b", 2), ("a\n", 1), ("café", 3)].into_iter().collect();
+    /// assert_eq!(format!("{:?}", map), r#"{"a\n": 1, "b": 2, "café": 3}"#);
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut entries = f.debug_map();
+        let mut iter = self.tree.iter();
+        while let Some((key, value)) = iter.next_raw() {
+            entries.entry(&DebugKey::<K>::new(key, self.rendering), value);
+        }
+        entries.finish()
+    }
+}
+
+impl<K: Key + ?Sized, V: PartialEq> PartialEq for RadixMap<K, V> {
+    /// Compares the entries of the maps, regardless of how the maps were built or configured
+    /// (e.g. their key rendering).
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        let (mut a, mut b) = (self.tree.iter(), other.tree.iter());
+        while let Some((key, value)) = a.next_raw() {
+            match b.next_raw() {
+                Some((k, v)) if k == key && v == value => {},
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl<K: Key + ?Sized, V: Eq> Eq for RadixMap<K, V> {}
+
+impl<K: Key + ?Sized, V: Hash> Hash for RadixMap<K, V>
+    where K::Component: Hash,
+{
+    /// Hashes the entries of the map, sorted by key, so that equal maps have the same hash.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use panoradix::RadixMap;
+    ///
+    /// let a: RadixMap<str, u32> = vec![("x", 1), ("y", 2)].into_iter().collect();
+    /// let b: RadixMap<str, u32> = vec![("y", 2), ("x", 1)].into_iter().collect();
+    ///
+    /// let mut configs = HashMap::new();
+    /// configs.insert(a, "first");
+    /// assert_eq!(configs.get(&b), Some(&"first"));
+    /// ```
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        let mut iter = self.tree.iter();
+        while let Some((key, value)) = iter.next_raw() {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+
+/// Formats the components of a whole key with `Key::fmt_key`.
+pub(crate) struct DebugKey<'a, K: 'a + Key + ?Sized> {
+    components: &'a [K::Component],
+    rendering: KeyRendering,
+}
+
+impl<'a, K: 'a + Key + ?Sized> DebugKey<'a, K> {
+    pub(crate) fn new(components: &'a [K::Component], rendering: KeyRendering) -> DebugKey<'a, K> {
+        DebugKey { components, rendering }
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized> fmt::Debug for DebugKey<'a, K>
+    where K::Component: fmt::Debug + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        K::fmt_key(self.components, self.rendering, f)
+    }
+}
 
 impl<K: Key + ?Sized, V> Default for RadixMap<K, V> {
     fn default() -> Self {
         Self::new()
     }
-}
+}
+
+impl<K, V, T> FromIterator<(T, V)> for RadixMap<K, V>
+    where K: Key + ?Sized,
+          T: AsRef<K>,
+{
+    fn from_iter<It>(iter: It) -> Self
+        where It: IntoIterator<Item=(T, V)>,
+    {
+        let mut map = RadixMap::new();
+        for (t, v) in iter {
+            map.insert(t.as_ref(), v);
+        }
+
+        map
+    }
+}
+
+impl<K: Key + ?Sized, V> IntoIterator for RadixMap<K, V> {
+    type Item = (K::Owned, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> IntoIter<K, V> {
+        #[cfg(feature = "paranoid")]
+        self.shadow.clear();
+        IntoIter {
+            iter: mem::replace(&mut self.tree, Tree::new()).into_entries().into_iter(),
+        }
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> IntoIterator for &'a RadixMap<K, V> {
+    type Item = (K::Owned, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> IntoIterator for &'a mut RadixMap<K, V> {
+    type Item = (K::Owned, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V> From<BTreeMap<K::Owned, V>> for RadixMap<K, V>
+    where K: Key + ?Sized,
+          K::Owned: AsRef<K>,
+{
+    fn from(map: BTreeMap<K::Owned, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K, V, S> From<HashMap<K::Owned, V, S>> for RadixMap<K, V>
+    where K: Key + ?Sized,
+          K::Owned: AsRef<K>,
+{
+    fn from(map: HashMap<K::Owned, V, S>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K, V> From<RadixMap<K, V>> for BTreeMap<K::Owned, V>
+    where K: Key + ?Sized,
+          K::Owned: Ord,
+{
+    fn from(map: RadixMap<K, V>) -> Self {
+        // entries come out sorted, which lets the BTreeMap be built in bulk
+        map.into_sorted_vec().into_iter().collect()
+    }
+}
+
+impl<K, V, S> From<RadixMap<K, V>> for HashMap<K::Owned, V, S>
+    where K: Key + ?Sized,
+          K::Owned: Hash + Eq,
+          S: BuildHasher + Default,
+{
+    fn from(map: RadixMap<K, V>) -> Self {
+        map.into_sorted_vec().into_iter().collect()
+    }
+}
+
+/// An operation on a `RadixMap`, as produced by [`delta_to`].
+///
+/// [`delta_to`]: struct.RadixMap.html#method.delta_to
+pub enum Op<K: Key + ?Sized, V> {
+    /// Insert a value at a key, replacing any previous value.
+    Insert(K::Owned, V),
+    /// Remove a key.
+    Remove(K::Owned),
+}
+
+impl<K: Key + ?Sized, V: Clone> Clone for Op<K, V>
+    where K::Owned: Clone,
+{
+    fn clone(&self) -> Self {
+        match *self {
+            Op::Insert(ref key, ref value) => Op::Insert(key.clone(), value.clone()),
+            Op::Remove(ref key)            => Op::Remove(key.clone()),
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V: PartialEq> PartialEq for Op<K, V>
+    where K::Owned: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Op::Insert(k1, v1), Op::Insert(k2, v2)) => k1 == k2 && v1 == v2,
+            (Op::Remove(k1), Op::Remove(k2))         => k1 == k2,
+            _                                        => false,
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V: fmt::Debug> fmt::Debug for Op<K, V>
+    where K::Owned: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Op::Insert(ref key, ref value) => f.debug_tuple("Insert").field(key).field(value).finish(),
+            Op::Remove(ref key)            => f.debug_tuple("Remove").field(key).finish(),
+        }
+    }
+}
+
+/// What to do with the entry of a key, as decided by the closure passed to [`apply`].
+///
+/// [`apply`]: struct.RadixMap.html#method.apply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome<V> {
+    /// Leave the entry as is, keeping any change made through the reference to its value.
+    Keep,
+    /// Set the value of the key, inserting it if it isn't in the map.
+    Set(V),
+    /// Remove the key from the map.
+    Remove,
+}
+
+/// An iterator over a `RadixMap`'s (key, value) pairs.
+pub struct Iter<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: TreeIter<'a, K::Component, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] Iter<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Iter<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, V: 'a> Iter<'a, str, V> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, i32> = vec![("a", 1), ("b", 2)].into_iter().collect();
+    ///
+    /// let mut it = map.iter();
+    /// while let Some((key, value)) = it.next_ref() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// ```
+    pub fn next_ref(&mut self) -> Option<(&str, &'a V)> {
+        self.iter.next_raw().map(|(k, v)| (borrow_str(k), v))
+    }
+}
+
+impl<'a, T: 'a + KeyComponent, V: 'a> Iter<'a, [T], V> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<(&[T], &'a V)> {
+        self.iter.next_raw()
+    }
+}
+
+/// A lending iterator over a `RadixMap`'s entries, see [`raw_iter`].
+///
+/// [`raw_iter`]: struct.RadixMap.html#method.raw_iter
+pub struct RawIter<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: TreeIter<'a, K::Component, V>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] RawIter<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> RawIter<'a, K, V> {
+    /// Advances the iterator and returns the next entry, the key being borrowed from the iterator
+    /// until the next call.
+    pub fn next_entry(&mut self) -> Option<(&[K::Component], &'a V)> {
+        self.iter.next_raw()
+    }
+}
+
+/// An iterator over the entries of a `RadixMap` in a range of keys, see [`range`].
+///
+/// [`range`]: struct.RadixMap.html#method.range
+pub struct Range<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: iter::Take<TreeIter<'a, K::Component, V>>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] Range<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Range<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for Range<'a, K, V> {}
+
+/// A mutable iterator over a `RadixMap`'s (key, value) pairs.
+pub struct IterMut<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: TreeIterMut<'a, K::Component, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] IterMut<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for IterMut<'a, K, V> {
+    type Item = (K::Owned, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for IterMut<'a, K, V> {}
+
+/// An iterator over a `RadixMap`'s keys.
+pub struct Keys<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: Iter<'a, K, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] Keys<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Keys<'a, K, V> {
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, V: 'a> Keys<'a, str, V> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<&str> {
+        self.iter.next_ref().map(|(k, _)| k)
+    }
+}
+
+impl<'a, T: 'a + KeyComponent, V: 'a> Keys<'a, [T], V> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<&[T]> {
+        self.iter.next_ref().map(|(k, _)| k)
+    }
+}
+
+/// An iterator over a `RadixMap`'s values.
+pub struct Values<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: Iter<'a, K, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] Values<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for Values<'a, K, V> {}
+
+/// A mutable iterator over the elements matching a call to [`find_mut`].
+///
+/// [`find_mut`]: struct.RadixMap.html#method.find_mut
+pub struct MatchesMut<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: TreeIterMut<'a, K::Component, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] MatchesMut<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for MatchesMut<'a, K, V> {
+    type Item = (K::Owned, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for MatchesMut<'a, K, V> {}
+
+/// An owning iterator over a `RadixMap`'s entries, sorted by key.
+pub struct IntoIter<K: Key + ?Sized, V> {
+    iter: vec::IntoIter<(Vec<K::Component>, V)>,
+}
+
+debug_iterator!(exact [K: Key + ?Sized, V] IntoIter<K, V>);
+
+impl<K: Key + ?Sized, V> Iterator for IntoIter<K, V> {
+    type Item = (K::Owned, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K: Key + ?Sized, V> ExactSizeIterator for IntoIter<K, V> {}
+
+/// An owning iterator over a `RadixMap`'s keys, sorted.
+pub struct IntoKeys<K: Key + ?Sized, V> {
+    iter: IntoIter<K, V>,
+}
+
+debug_iterator!(exact [K: Key + ?Sized, V] IntoKeys<K, V>);
+
+impl<K: Key + ?Sized, V> Iterator for IntoKeys<K, V> {
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K: Key + ?Sized, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+/// An owning iterator over a `RadixMap`'s values, sorted by corresponding key.
+pub struct IntoValues<V> {
+    iter: vec::IntoIter<V>,
+}
+
+debug_iterator!(exact [V] IntoValues<V>);
+
+impl<V> Iterator for IntoValues<V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<V> ExactSizeIterator for IntoValues<V> {}
+
+/// An iterator removing entries from a map, created by [`extract_if`].
+///
+/// [`extract_if`]: struct.RadixMap.html#method.extract_if
+pub struct ExtractIf<'a, K: 'a + Key + ?Sized, V: 'a, F> {
+    iter: TreeExtractIf<'a, K::Component, V, F>,
+    #[cfg(feature = "paranoid")]
+    shadow: &'a mut BTreeSet<Vec<K::Component>>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a, F] ExtractIf<'a, K, V, F>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a, F> Iterator for ExtractIf<'a, K, V, F>
+    where F: FnMut(&[K::Component], &mut V) -> bool,
+{
+    type Item = (K::Owned, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.iter.next_raw()?;
+        #[cfg(feature = "paranoid")]
+        self.shadow.remove(key);
+        Some((K::from_vec(key.to_vec()), value))
+    }
+}
+
+/// A mutable iterator over a `RadixMap`'s values.
+pub struct ValuesMut<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: TreeIterMut<'a, K::Component, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] ValuesMut<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the keys aren't cloned out of the iterator's buffer
+        self.iter.next_raw().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for ValuesMut<'a, K, V> {}
+
+/// An iterator over the elements matching a call to [`find`].
+///
+/// [`find`]: struct.RadixMap.html#method.find
+pub struct Matches<'a, K: 'a + Key + ?Sized, V: 'a> {
+    matches: TreeMatches<'a, K::Component, V>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized, V: 'a] Matches<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Matches<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.matches.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> ExactSizeIterator for Matches<'a, K, V> {}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Matches<'a, K, V> {
+    /// Returns the longest prefix shared by the matches, which may be longer than the prefix
+    /// searched.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    ///
+    /// let map: RadixMap<str, ()> = ["apple", "applet", "apply", "banana"].iter().map(|k| (k, ())).collect();
+    ///
+    /// let mut matches = map.find("ap");
+    /// assert_eq!(matches.matched_prefix(), "appl");
+    /// matches.next();
+    /// assert_eq!((matches.len(), matches.total()), (2, 3));
+    /// ```
+    pub fn matched_prefix(&self) -> K::Owned {
+        K::from_partial_vec(self.matches.matched_prefix().to_vec())
+    }
+
+    /// Returns the number of matches, including those already iterated over (unlike `len()`).
+    ///
+    /// The number of entries under each node is tracked by the tree, so this costs nothing.
+    pub fn total(&self) -> usize {
+        self.matches.total()
+    }
+}
+
+impl<'a, V: 'a> Matches<'a, str, V> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<(&str, &'a V)> {
+        self.matches.next_raw().map(|(k, v)| (borrow_str(k), v))
+    }
+}
+
+impl<'a, T: 'a + KeyComponent, V: 'a> Matches<'a, [T], V> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<(&[T], &'a V)> {
+        self.matches.next_raw()
+    }
+}
+
+/// An iterator over the entries matching a call to [`find_by_weight`].
+///
+/// [`find_by_weight`]: struct.RadixMap.html#method.find_by_weight
+pub struct ByWeight<'a, K: 'a + Key + ?Sized, V: 'a, W> {
+    // the weight of each entry, along with its rank in key order
+    heap: BinaryHeap<(W, Reverse<usize>)>,
+    // the matches, only kept to select the entries by rank
+    matches: TreeMatches<'a, K::Component, V>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a, W] ByWeight<'a, K, V, W>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a, W: Ord> Iterator for ByWeight<'a, K, V, W> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, Reverse(rank)) = self.heap.pop()?;
+        self.matches.select(rank).map(|(key, value)| (K::from_vec(key), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+/// A consumer of the entries of a `RadixMap`, see [`visit_entries`].
+///
+/// This is implemented by closures taking the arguments of `visit_entry`.
+///
+/// [`visit_entries`]: struct.RadixMap.html#method.visit_entries
+pub trait EntryVisitor<K: Key + ?Sized, V> {
+    /// The error stopping the visit, e.g. an I/O error.
+    type Error;
+
+    /// Starts visiting `len` entries.
+    fn start(&mut self, len: usize) -> Result<(), Self::Error> {
+        let _ = len;
+        Ok(())
+    }
+
+    /// Visits an entry, whose key shares its first `shared` components with the key of the
+    /// previous entry (none for the first entry).
+    ///
+    /// The components of `str` keys are their UTF-8 bytes.
+    fn visit_entry(&mut self, key: &[K::Component], shared: usize, value: &V) -> Result<(), Self::Error>;
+}
+
+impl<K, V, E, F> EntryVisitor<K, V> for F
+    where K: Key + ?Sized,
+          F: FnMut(&[K::Component], usize, &V) -> Result<(), E>,
+{
+    type Error = E;
+
+    fn visit_entry(&mut self, key: &[K::Component], shared: usize, value: &V) -> Result<(), E> {
+        self(key, shared, value)
+    }
+}
+
+/// How finely keys should be split into components, see [`EncodingSuggestion`].
+///
+/// [`EncodingSuggestion`]: struct.EncodingSuggestion.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    /// One bit per component, for binary alphabets, e.g. the bits of IP addresses.
+    Bit,
+    /// Several components packed per byte, for alphabets of up to 16 symbols, e.g. like the `dna`
+    /// feature does for nucleotides.
+    Nibble,
+    /// Keys are best kept as they are.
+    Component,
+}
+
+/// Statistics on the keys of a `RadixMap`, see [`suggest_encoding`].
+///
+/// [`suggest_encoding`]: struct.RadixMap.html#method.suggest_encoding
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodingSuggestion {
+    alphabet_size: usize,
+    average_key_len: f64,
+    average_shared_prefix: f64,
+    average_fan_out: f64,
+}
+
+impl EncodingSuggestion {
+    /// Returns the number of distinct components of the keys.
+    pub fn alphabet_size(&self) -> usize {
+        self.alphabet_size
+    }
+
+    /// Returns the average number of components of the keys.
+    pub fn average_key_len(&self) -> f64 {
+        self.average_key_len
+    }
+
+    /// Returns the average length of the prefix shared by each key and the previous one, in
+    /// sorted order, i.e. how many components of a key are stored once for several keys.
+    pub fn average_shared_prefix(&self) -> f64 {
+        self.average_shared_prefix
+    }
+
+    /// Returns the average number of children of the nodes having some.
+    ///
+    /// Nodes close to the alphabet size are dense, while nodes with a couple of children mostly
+    /// chain long keys, which wider components (or a [`KeyCodec`]) make shorter.
+    ///
+    /// [`KeyCodec`]: ../codec/trait.KeyCodec.html
+    pub fn average_fan_out(&self) -> f64 {
+        self.average_fan_out
+    }
+
+    /// Returns the number of bits telling the components of the keys apart.
+    pub fn bits_per_component(&self) -> u32 {
+        match self.alphabet_size {
+            0 | 1 => 0,
+            n     => usize::BITS - (n - 1).leading_zeros(),
+        }
+    }
+
+    /// Suggests how finely keys should be split into components, from the size of their alphabet.
+    pub fn granularity(&self) -> Granularity {
+        match self.bits_per_component() {
+            0 | 1 => Granularity::Bit,
+            2..=4 => Granularity::Nibble,
+            _     => Granularity::Component,
+        }
+    }
+}
+
+/// A handle to a subtree of a `RadixMap`, see [`node_id`].
+///
+/// [`node_id`]: struct.RadixMap.html#method.node_id
+pub struct NodeId<K: Key + ?Sized> {
+    generation: u64,
+    path: Vec<usize>,
+    prefix: Vec<K::Component>,
+}
+
+impl<K: Key + ?Sized> NodeId<K> {
+    /// Returns the longest prefix shared by the keys of the subtree, which may be longer than
+    /// the prefix it was created from.
+    pub fn prefix(&self) -> K::Owned {
+        K::from_partial_vec(self.prefix.clone())
+    }
+
+    pub(crate) fn components(&self) -> &[K::Component] {
+        &self.prefix
+    }
+}
+
+impl<K: Key + ?Sized> Clone for NodeId<K> {
+    fn clone(&self) -> Self {
+        NodeId {
+            generation: self.generation,
+            path: self.path.clone(),
+            prefix: self.prefix.clone(),
+        }
+    }
+}
+
+/// An iterator over the entries on the path to a key, see [`ancestors`].
+///
+/// [`ancestors`]: struct.RadixMap.html#method.ancestors
+pub struct Ancestors<'a, 'k, K: 'a + 'k + Key + ?Sized, V: 'a> {
+    ancestors: TreeAncestors<'a, 'k, K::Component, V>,
+    key: &'k [K::Component],
+}
+
+debug_iterator!(['a, 'k, K: 'a + 'k + Key + ?Sized, V: 'a] Ancestors<'a, 'k, K, V>);
+
+impl<'a, 'k, K: 'a + 'k + Key + ?Sized, V: 'a> Iterator for Ancestors<'a, 'k, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ancestors.next().map(|(len, v)| (K::from_vec(self.key[..len].to_vec()), v))
+    }
+}
+
+/// An iterator merging the entries of several maps, see [`merge_iter`].
+///
+/// [`merge_iter`]: struct.RadixMap.html#method.merge_iter
+pub struct MergeIter<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iters: Vec<TreeIter<'a, K::Component, V>>,
+    // the value of the next entry of each map, whose key is in the heap along with the map's index
+    heads: Vec<Option<&'a V>>,
+    heap: BinaryHeap<Reverse<(Vec<K::Component>, usize)>>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] MergeIter<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> MergeIter<'a, K, V> {
+    fn advance(&mut self, i: usize) {
+        self.heads[i] = match self.iters[i].next() {
+            Some((key, value)) => {
+                self.heap.push(Reverse((key, i)));
+                Some(value)
+            }
+            None => None,
+        };
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for MergeIter<'a, K, V> {
+    type Item = (K::Owned, Vec<&'a V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((key, i)) = self.heap.pop()?;
+        let mut values = vec![self.heads[i].unwrap()];
+        self.advance(i);
+
+        // ties are popped by increasing index, i.e. in the order of the maps
+        while self.heap.peek().is_some_and(|Reverse((k, _))| *k == key) {
+            let Reverse((_, j)) = self.heap.pop().unwrap();
+            values.push(self.heads[j].unwrap());
+            self.advance(j);
+        }
+
+        Some((K::from_vec(key), values))
+    }
+}
+
+/// A query combining constraints on the entries of a map, built through [`query`].
+///
+/// All the constraints are checked during a single traversal of the map: only the subtree under
+/// the prefix is visited, subtrees whose keys are all too short or too long are skipped,
+/// keys are only allocated for the entries that are returned, and the traversal stops as soon as
+/// the limit is reached. Lengths are counted in key components (bytes for `str` keys).
+///
+/// [`query`]: struct.RadixMap.html#method.query
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::RadixMap;
+///
+/// let mut map = RadixMap::new();
+/// map.insert("ap", 1);
+/// map.insert("apple", 2);
+/// map.insert("apply", 3);
+/// map.insert("apricot", 4);
+/// map.insert("banana", 5);
+///
+/// let entries = map.query()
+///     .prefix("ap")
+///     .min_len(4)
+///     .max_len(5)
+///     .filter(|&v| v > 2)
+///     .limit(20)
+///     .run();
+/// assert_eq!(entries, vec![("apply".to_string(), &3)]);
+/// ```
+pub struct Query<'a, K: 'a + Key + ?Sized, V: 'a> {
+    map: &'a RadixMap<K, V>,
+    prefix: Vec<K::Component>,
+    min_len: usize,
+    max_len: usize,
+    filter: Box<dyn Fn(&V) -> bool + 'a>,
+    limit: usize,
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Query<'a, K, V> {
+    /// Only keeps the entries whose key starts with `prefix`.
+    pub fn prefix<Q: AsKey<K> + ?Sized>(mut self, prefix: &Q) -> Self {
+        self.prefix = prefix.as_key().as_slice().to_vec();
+        self
+    }
+
+    /// Only keeps the entries whose key is at least `len` components long.
+    pub fn min_len(mut self, len: usize) -> Self {
+        self.min_len = len;
+        self
+    }
+
+    /// Only keeps the entries whose key is at most `len` components long.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// Only keeps the entries whose value matches `predicate`.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+        where F: Fn(&V) -> bool + 'a,
+    {
+        self.filter = Box::new(predicate);
+        self
+    }
+
+    /// Stops after `limit` entries.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Runs the query, returning the matching entries sorted by key.
+    pub fn run(self) -> Vec<(K::Owned, &'a V)> {
+        let mut entries = Vec::new();
+        if self.limit == 0 {
+            return entries;
+        }
+
+        self.map.tree.walk(&self.prefix, self.min_len, self.max_len, |key, value| {
+            if (self.filter)(value) {
+                entries.push((K::from_vec(key.to_vec()), value));
+            }
+            entries.len() < self.limit
+        });
+
+        entries
+    }
+}
+
+/// An autocompletion session over a map, obtained through [`completer`].
+///
+/// The completer keeps track of its position in the map, so that feeding it one more component
+/// (or removing the last one) doesn't descend the map from its root again. `str` keys are fed
+/// one character at a time.
+///
+/// [`completer`]: struct.RadixMap.html#method.completer
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::RadixMap;
+///
+/// let mut map = RadixMap::new();
+/// map.insert("println", 1);
+/// map.insert("print", 2);
+/// map.insert("panic", 3);
+///
+/// let mut completer = map.completer();
+/// completer.push_char('p');
+/// completer.push_char('r');
+/// assert_eq!(completer.completions().map(|(k, _)| k).collect::<Vec<_>>(), vec!["print", "println"]);
+///
+/// completer.pop_char();
+/// completer.push_char('a');
+/// assert_eq!(completer.prefix(), "pa");
+/// assert_eq!(completer.completions().count(), 1);
+/// ```
+pub struct Completer<'a, K: 'a + Key + ?Sized, V: 'a> {
+    cursor: TreeCursor<'a, K::Component, V>,
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Completer<'a, K, V> {
+    /// Appends a component to the prefix being completed, returning `false` if no key starts with
+    /// the new prefix.
+    pub fn push(&mut self, component: K::Component) -> bool {
+        self.cursor.push(component)
+    }
+
+    /// Removes the last component of the prefix being completed, returning it.
+    pub fn pop(&mut self) -> Option<K::Component> {
+        self.cursor.pop()
+    }
+
+    /// Gets an iterator over the entries whose key starts with the prefix being completed,
+    /// sorted by key.
+    pub fn completions(&self) -> Matches<'a, K, V> {
+        Matches {
+            matches: self.cursor.matches(),
+        }
+    }
+}
+
+impl<'a, V: 'a> Completer<'a, str, V> {
+    /// Gets the prefix being completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if bytes not forming whole characters were fed through `push`.
+    pub fn prefix(&self) -> &str {
+        std::str::from_utf8(self.cursor.key()).unwrap()
+    }
+
+    /// Appends a character to the prefix being completed, returning `false` if no key starts
+    /// with the new prefix.
+    pub fn push_char(&mut self, c: char) -> bool {
+        let mut buf = [0; 4];
+        let mut found = true;
+        for &b in c.encode_utf8(&mut buf).as_bytes() {
+            found = self.cursor.push(b);
+        }
+        found
+    }
+
+    /// Removes the last character of the prefix being completed, returning it.
+    pub fn pop_char(&mut self) -> Option<char> {
+        let c = self.prefix().chars().next_back()?;
+        for _ in 0..c.len_utf8() {
+            self.cursor.pop();
+        }
+        Some(c)
+    }
+}
+
+/// What a [`PrefixCursor`] tells about the components fed to it.
+///
+/// [`PrefixCursor`]: struct.PrefixCursor.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrefixState {
+    /// The components form a key of the map (which may also be the prefix of longer keys).
+    Key,
+    /// The components aren't a key, but some keys start with them.
+    Prefix,
+    /// No key starts with the components, whatever follows them.
+    Dead,
+}
+
+/// A validator of keys typed one component at a time, created by [`prefix_cursor`].
+///
+/// Unlike a [`Completer`], the cursor can't move back, but each component only costs a step down
+/// the tree and nothing is allocated. Cloning the cursor is cheap, e.g. to try several
+/// continuations of the same prefix.
+///
+/// [`prefix_cursor`]: struct.RadixMap.html#method.prefix_cursor
+/// [`Completer`]: struct.Completer.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::RadixMap;
+/// use panoradix::map::PrefixState;
+///
+/// let mut words = RadixMap::new();
+/// words.insert("car", 1);
+/// words.insert("cart", 2);
+///
+/// let mut cursor = words.prefix_cursor();
+/// assert_eq!(cursor.push_char('c'), PrefixState::Prefix);
+/// assert_eq!(cursor.push_char('a'), PrefixState::Prefix);
+/// assert_eq!(cursor.push_char('r'), PrefixState::Key);
+/// assert_eq!(cursor.value(), Some(&1));
+///
+/// let mut cart = cursor.clone();
+/// assert_eq!(cart.push_char('t'), PrefixState::Key);
+/// assert_eq!(cursor.push_char('p'), PrefixState::Dead);
+/// assert_eq!(cursor.push_char('s'), PrefixState::Dead);
+/// ```
+pub struct PrefixCursor<'a, K: 'a + Key + ?Sized, V: 'a> {
+    cursor: TreePrefixCursor<'a, K::Component, V>,
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Clone for PrefixCursor<'a, K, V> {
+    fn clone(&self) -> Self {
+        PrefixCursor { cursor: self.cursor.clone() }
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> PrefixCursor<'a, K, V> {
+    /// Appends a component to the typed key, returning the new state.
+    pub fn push(&mut self, component: K::Component) -> PrefixState {
+        self.cursor.push(&component);
+        self.state()
+    }
+
+    /// Returns the state of the typed key.
+    pub fn state(&self) -> PrefixState {
+        if self.cursor.value().is_some() {
+            PrefixState::Key
+        } else if self.cursor.is_live() {
+            PrefixState::Prefix
+        } else {
+            PrefixState::Dead
+        }
+    }
+
+    /// Returns the value of the typed key, if it is a key of the map.
+    pub fn value(&self) -> Option<&'a V> {
+        self.cursor.value()
+    }
+}
+
+impl<'a, V: 'a> PrefixCursor<'a, str, V> {
+    /// Appends a character to the typed key, returning the new state.
+    pub fn push_char(&mut self, c: char) -> PrefixState {
+        let mut buf = [0; 4];
+        for &b in c.encode_utf8(&mut buf).as_bytes() {
+            self.cursor.push(&b);
+        }
+        self.state()
+    }
+}
+
+/// An iterator over the elements matching a call to [`find_with`].
+///
+/// [`find_with`]: struct.RadixMap.html#method.find_with
+pub struct MatchesWith<'a, K: 'a + Key + ?Sized, V: 'a> {
+    matches: Flatten<vec::IntoIter<TreeMatches<'a, K::Component, V>>>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] MatchesWith<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for MatchesWith<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next().map(|(k, v)| (K::from_vec(k), v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::{RadixMap, EntryVisitor, Granularity, Outcome, PrefixState};
+    use key::{KeyRendering, OrderedFloat};
+
+    struct DropCounter<'a>(&'a Cell<usize>);
+
+    impl<'a> Drop for DropCounter<'a> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn it_mutates_values_in_place() {
+        let mut map: RadixMap<str, Vec<u32>> = RadixMap::new();
+        for key in &["", "a", "ab", "abc", "b"] {
+            map.insert(key, vec![]);
+        }
+
+        for (i, key) in ["abc", "", "ab", "a", "abc"].iter().enumerate() {
+            map.get_mut(key).unwrap().push(i as u32);
+        }
+        assert!(map.get_mut("ac").is_none());
+        assert!(map.get_mut("abcd").is_none());
+
+        let values: Vec<_> = map.values().cloned().collect();
+        assert_eq!(values, vec![vec![1], vec![3], vec![2], vec![0, 4], vec![]]);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn it_builds_in_parallel() {
+        use rayon::prelude::*;
+
+        // every key appears 3 times, far apart in the input
+        let entries: Vec<(Vec<u8>, usize)> = (0..3000).map(|i| (vec![(i % 1000 / 100) as u8, (i % 100) as u8, (i % 1000 % 7) as u8], i)).collect();
+
+        let map: RadixMap<[u8], usize> = RadixMap::par_from_iter(entries.par_iter().cloned());
+        let expected: RadixMap<[u8], usize> = entries.iter().cloned().collect();
+        assert_eq!(map.tree.check(), Ok(()));
+        assert_eq!(map.len(), 1000);
+        assert!(map.iter().eq(expected.iter()));
+
+        let failed = RadixMap::<[u8], usize>::try_par_from_iter(entries.par_iter().cloned().map(|(k, v)| {
+            if v == 1234 { Err(v) } else { Ok((k, v)) }
+        }));
+        assert_eq!(failed.err(), Some(1234));
+    }
 
-impl<K, V, T> FromIterator<(T, V)> for RadixMap<K, V>
-    where K: Key + ?Sized,
-          T: AsRef<K>,
-{
-    fn from_iter<It>(iter: It) -> Self
-        where It: IntoIterator<Item=(T, V)>,
-    {
-        let mut tree = Tree::new();
-        for (t, v) in iter {
-            tree.insert(t.as_ref().as_slice(), v);
+    #[test]
+    fn it_renders_keys_following_the_policy_of_the_map() {
+        let mut map: RadixMap<[u8], ()> = RadixMap::new();
+        map.insert(&[0xde, 0xad], ());
+        map.insert(&[0xde, 0x10], ());
+        assert_eq!(map.key_rendering(), KeyRendering::Escaped);
+
+        let dump = |map: &RadixMap<[u8], ()>| {
+            let mut dump = String::new();
+            map.dump_structure(&mut dump).unwrap();
+            dump
+        };
+        assert_eq!(dump(&map), "(root) (2)\n  \"\\xde\" (2)\n    \"\\x10\" * (1)\n    \"\\xad\" * (1)\n");
+
+        map.set_key_rendering(KeyRendering::Hex);
+        assert_eq!(dump(&map), "(root) (2)\n  [de] (2)\n    [10] * (1)\n    [ad] * (1)\n");
+
+        map.set_key_rendering(KeyRendering::Text);
+        map.insert(b"ok", ());
+        assert_eq!(dump(&map), "(root) (3)\n  \"ok\" * (1)\n  \"\u{fffd}\" (2)\n    \"\u{10}\" * (1)\n    \"\u{fffd}\" * (1)\n");
+        map.remove(b"ok");
+        map.set_key_rendering(KeyRendering::Hex);
+
+        // whole keys are formatted like std collections format them
+        assert_eq!(format!("{:?}", map), "{[de, 10]: (), [de, ad]: ()}");
+
+        // the policy is kept by the maps derived from this one
+        assert_eq!(map.remove_prefix(&[0xde]).key_rendering(), KeyRendering::Hex);
+    }
+
+    #[test]
+    fn it_keeps_the_last_keys_inserted() {
+        let mut map: RadixMap<[u8], u32> = RadixMap::with_recent_inserts(4);
+        for i in 0..10u8 {
+            map.insert(&[i % 3], i as u32);
         }
+        map.remove(&[0]);
+        assert_eq!(map.sample_recent_inserts(10), vec![vec![0], vec![2], vec![1], vec![0]]);
+        assert_eq!(map.recent.as_ref().map(|r| r.keys.capacity() >= 4), Some(true));
 
-        RadixMap { tree }
+        // the keys are kept along with the map, whatever its values
+        let map = map.map_values(|v| v * 2);
+        assert_eq!(map.sample_recent_inserts(2), vec![vec![0], vec![2]]);
+        assert!(RadixMap::<str, ()>::with_recent_inserts(0).sample_recent_inserts(1).is_empty());
     }
-}
 
-/// An iterator over a `RadixMap`'s (key, value) pairs.
-pub struct Iter<'a, K: 'a + Key + ?Sized, V: 'a> {
-    iter: TreeIter<'a, K::Component, V>,
-}
+    #[test]
+    fn it_formats_entries_like_std_maps() {
+        #[derive(Debug)]
+        struct Config {
+            routes: RadixMap<str, u16>,
+        }
 
-impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Iter<'a, K, V> {
-    type Item = (K::Owned, &'a V);
+        let mut config = Config { routes: RadixMap::new() };
+        assert_eq!(format!("{:?}", config), "Config { routes: {} }");
+        config.routes.insert("/api", 8080);
+        config.routes.insert("/", 80);
+        assert_eq!(format!("{:?}", config), r#"Config { routes: {"/": 80, "/api": 8080} }"#);
+        assert_eq!(format!("{:#?}", config.routes), "{\n    \"/\": 80,\n    \"/api\": 8080,\n}");
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, v)| (K::from_vec(k), v))
+        // keys are formatted whole, unlike the labels of dump_structure
+        config.routes.insert("/café", 443);
+        assert_eq!(format!("{:?}", config.routes), r#"{"/": 80, "/api": 8080, "/café": 443}"#);
+        config.routes.remove("/café");
+
+        config.routes.set_key_rendering(KeyRendering::Hex);
+        assert_eq!(format!("{:?}", config.routes), "{[2f]: 80, [2f, 61, 70, 69]: 8080}");
+
+        let mut iter = config.routes.iter();
+        iter.next();
+        assert_eq!(format!("{:?}", iter), "Iter { remaining: 1, .. }");
+        assert_eq!(format!("{:?}", config.routes.ancestors("/api")), "Ancestors { .. }");
     }
-}
 
-/// An iterator over a `RadixMap`'s keys.
-pub struct Keys<'a, K: 'a + Key + ?Sized, V: 'a> {
-    iter: Iter<'a, K, V>,
-}
+    #[test]
+    fn it_iterates_over_references() {
+        let mut map: RadixMap<str, u32> = vec![("b", 2), ("a", 1)].into_iter().collect();
 
-impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Keys<'a, K, V> {
-    type Item = K::Owned;
+        for (_, value) in &mut map {
+            *value *= 10;
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, _)| k)
+        let mut entries = Vec::new();
+        for (key, &value) in &map {
+            entries.push((key, value));
+        }
+        assert_eq!(entries, vec![("a".to_string(), 10), ("b".to_string(), 20)]);
     }
-}
 
-/// An iterator over a `RadixMap`'s values.
-pub struct Values<'a, K: 'a + Key + ?Sized, V: 'a> {
-    iter: Iter<'a, K, V>,
-}
+    #[test]
+    fn it_moves_entries_out() {
+        struct NotClone(u32);
 
-impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Values<'a, K, V> {
-    type Item = &'a V;
+        let mut map: RadixMap<str, NotClone> = RadixMap::new();
+        for (i, key) in ["b", "ab", "a", ""].iter().enumerate() {
+            map.insert(key, NotClone(i as u32));
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(_, v)| v)
+        let mut iter = map.into_iter();
+        assert_eq!(iter.len(), 4);
+        let (key, value) = iter.next().unwrap();
+        assert_eq!((key.as_str(), value.0), ("", 3));
+        let rest: Vec<_> = iter.map(|(k, v)| (k, v.0)).collect();
+        assert_eq!(rest, vec![("a".to_string(), 2), ("ab".to_string(), 1), ("b".to_string(), 0)]);
+
+        let map: RadixMap<str, NotClone> = vec![("b", NotClone(1)), ("a", NotClone(2))].into_iter().collect();
+        assert_eq!(map.into_values().map(|v| v.0).collect::<Vec<_>>(), vec![2, 1]);
+        let map: RadixMap<str, NotClone> = vec![("b", NotClone(1)), ("a", NotClone(2))].into_iter().collect();
+        assert_eq!(map.into_keys().collect::<Vec<_>>(), vec!["a", "b"]);
     }
-}
 
-/// An iterator over the elements matching a call to [`find`].
-///
-/// [`find`]: struct.RadixMap.html#method.find
-pub struct Matches<'a, K: 'a + Key + ?Sized, V: 'a> {
-    matches: TreeMatches<'a, K::Component, V>,
-}
+    #[test]
+    fn it_extracts_matching_entries() {
+        let mut map: RadixMap<[u8], u32> = (0..50u32).map(|i| (vec![i as u8 / 10, i as u8 % 10], i)).collect();
 
-impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Matches<'a, K, V> {
-    type Item = (K::Owned, &'a V);
+        let extracted: Vec<_> = map.extract_if(|key, value| {
+            *value += 100;
+            key[1] % 2 == 0
+        }).collect();
+        assert_eq!(extracted.len(), 25);
+        assert_eq!(extracted[0], (vec![0, 0], 100));
+        assert!(extracted.windows(2).all(|w| w[0].0 < w[1].0));
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.matches.next().map(|(k, v)| (K::from_vec(k), v))
+        assert_eq!(map.len(), 25);
+        assert!(map.iter().all(|(key, &value)| key[1] % 2 == 1 && value >= 100));
+
+        assert_eq!(map.extract_if(|key, _| key[0] < 2).count(), 10);
+        assert_eq!(map.len(), 15);
+        assert_eq!(map.extract_if(|_, _| false).count(), 0);
+        assert_eq!(map.len(), 15);
+
+        // the entries the iterator didn't reach are kept
+        let mut iter = map.extract_if(|_, _| true);
+        assert_eq!(iter.next(), Some((vec![2, 1], 121)));
+        assert_eq!(iter.next(), Some((vec![2, 3], 123)));
+        drop(iter);
+        assert_eq!(map.len(), 13);
+        assert_eq!(map.keys().next(), Some(vec![2, 5]));
+        assert!(map.tree.check().is_ok());
+        assert_eq!(map.tree.removals(), 37);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::RadixMap;
+    #[test]
+    fn it_looks_keys_up_by_their_owners() {
+        let mut map: RadixMap<str, i32> = vec![("ab", 1), ("abc", 2)].into_iter().collect();
+        let (owned, boxed): (String, Box<str>) = ("ab".into(), "abc".into());
+        assert_eq!((map.get(&owned), map.get(&boxed), map.get(&&"ab")), (Some(&1), Some(&2), Some(&1)));
+        assert_eq!(map.find(&owned).count(), 2);
+        *map.get_mut(&owned).unwrap() += 10;
+        assert_eq!(map.remove(&owned), Some(11));
+        assert!(!map.contains_key(&owned));
+
+        let mut bytes: RadixMap<[u8], i32> = vec![(&b"ab"[..], 1)].into_iter().collect();
+        assert_eq!(bytes.get(b"ab"), Some(&1));
+        assert_eq!(bytes.get(&vec![b'a', b'b']), Some(&1));
+        assert_eq!(bytes.get(&Box::<[u8]>::from(&b"ab"[..])), Some(&1));
+        assert_eq!(bytes.find(b"a").count(), 1);
+        assert_eq!(bytes.remove(&&b"ab"[..]), Some(1));
+    }
+
+    #[test]
+    fn it_returns_the_stored_key_with_its_value() {
+        let map: RadixMap<[u8], i32> = vec![(&b"ab"[..], 1), (b"abc", 2)].into_iter().collect();
+        assert_eq!(map.get_key_value(&vec![b'a', b'b']), Some((b"ab".to_vec(), &1)));
+        assert_eq!(map.get_key_value(b"abc"), Some((b"abc".to_vec(), &2)));
+        assert_eq!(map.get_key_value(b"a"), None);
+        assert_eq!(map.get_key_value(b"abcd"), None);
+    }
+
+    #[test]
+    fn it_finds_batches_of_overlapping_prefixes() {
+        let map: RadixMap<str, i32> = vec![("a", 1), ("ab", 2), ("abc", 3), ("b", 4), ("bc", 5)].into_iter().collect();
+        let found = map.find_batch(&["ab", "b", "abc", "", "ab", "c", "abcd"]);
+
+        let keys: Vec<Vec<_>> = found.into_iter().map(|f| f.map(|(k, _)| k).collect()).collect();
+        assert_eq!(keys, vec![
+            vec!["ab", "abc"],
+            vec!["b", "bc"],
+            vec!["abc"],
+            vec!["a", "ab", "abc", "b", "bc"],
+            vec!["ab", "abc"],
+            vec![],
+            vec![],
+        ]);
+
+        // the subtree of "c" is below the edge "car", which the other prefixes go along or leave
+        let map: RadixMap<str, i32> = vec![("card", 1), ("care", 2), ("dog", 3)].into_iter().collect();
+        let found = map.find_batch(&["c", "ca", "card", "cb", "carx"]);
+        let keys: Vec<Vec<_>> = found.into_iter().map(|f| f.map(|(k, _)| k).collect()).collect();
+        assert_eq!(keys, vec![vec!["card", "care"], vec!["card", "care"], vec!["card"], vec![], vec![]]);
+        assert!(map.find_batch::<&str>(&[]).is_empty());
+    }
+
+    #[test]
+    fn it_removes_entries_with_their_key() {
+        let mut map: RadixMap<str, i32> = vec![("a", 1), ("ab", 2)].into_iter().collect();
+        assert_eq!(map.remove_entry(&String::from("ab")), Some(("ab".to_string(), 2)));
+        assert_eq!(map.remove_entry("ab"), None);
+        assert_eq!(map.remove_entry(""), None);
+        assert_eq!(map.into_sorted_vec(), vec![("a".to_string(), 1)]);
+    }
+
+    #[test]
+    fn it_hashes_equal_maps_alike() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of<T: Hash>(t: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let a: RadixMap<str, u32> = vec![("ab", 1), ("b", 2)].into_iter().collect();
+
+        // removing keys may leave the tree with more nodes than needed
+        let mut b: RadixMap<str, u32> = vec![("abc", 3), ("abd", 4), ("b", 2), ("ab", 1)].into_iter().collect();
+        b.remove("abc");
+        b.remove("abd");
+        b.set_key_rendering(KeyRendering::Hex);
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        b.insert("b", 3);
+        assert_ne!(a, b);
+        assert_ne!(hash_of(&a), hash_of(&b));
+
+        // the key boundaries are hashed, not only the concatenated keys
+        let c: RadixMap<str, ()> = vec![("ab", ()), ("c", ())].into_iter().collect();
+        let d: RadixMap<str, ()> = vec![("a", ()), ("bc", ())].into_iter().collect();
+        assert_ne!(c, d);
+        assert_ne!(hash_of(&c), hash_of(&d));
+        assert_ne!(hash_of(&RadixMap::<str, ()>::new()), hash_of(&c));
+    }
+
+    #[test]
+    fn it_makes_empty_maps_at_compile_time() {
+        static EMPTY: RadixMap<[OrderedFloat<f64>], ()> = RadixMap::EMPTY;
+        const NEW: RadixMap<str, u32> = RadixMap::new();
+
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.tree.node_count(), 1);
+
+        // each use of a constant is a new map
+        let mut map = NEW;
+        map.insert("a", 1);
+        assert_eq!(NEW.len(), 0);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn it_applies_updates_in_a_single_descent() {
+        let mut map: RadixMap<str, u32> = vec![("abc", 1), ("abd", 2)].into_iter().collect();
+
+        // inside an edge, at a new edge and at the root
+        assert_eq!(map.apply("ab", |v| { assert_eq!(v, None); Outcome::Set(3) }), None);
+        assert_eq!(map.apply("b", |_| Outcome::Set(4)), None);
+        assert_eq!(map.apply("", |_| Outcome::Set(5)), None);
+        assert_eq!(map.apply("x", |_| Outcome::Keep), None);
+        assert_eq!(map.apply("a", |_| Outcome::Remove), None);
+        assert_eq!(map.len(), 5);
+
+        assert_eq!(map.apply("abc", |v| { *v.unwrap() += 10; Outcome::Keep }), None);
+        assert_eq!(map.apply("abd", |v| { assert_eq!(v, Some(&mut 2)); Outcome::Set(20) }), Some(2));
+        assert_eq!(map.apply("b", |_| Outcome::Remove), Some(4));
+        assert_eq!(map.apply("ab", |_| Outcome::Remove), Some(3));
+
+        let entries: Vec<_> = map.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(entries, vec![("".to_string(), 5), ("abc".to_string(), 11), ("abd".to_string(), 20)]);
+        assert_eq!((map.tree.insertions(), map.tree.removals()), (5, 2));
+
+        for key in &["", "abc", "abd"] {
+            map.apply(key, |_| Outcome::Remove);
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.tree.node_count(), 1);
+    }
+
+    #[test]
+    fn it_rewrites_characters_of_queries() {
+        let map: RadixMap<str, i32> = vec![("straße", 1), ("strasse", 2), ("stra", 3)].into_iter().collect();
+
+        // characters may be rewritten to characters of another length
+        let sharp_s = |c| if c == 'ß' { 's' } else { c };
+        assert_eq!(map.get_rewritten_chars("straße", sharp_s), None);
+        assert_eq!(map.get_rewritten_chars("strasse", |c| if c == 'ẞ' { 'ß' } else { c }), Some(&2));
+        assert_eq!(map.get_rewritten_chars("STRASSE", |c| c.to_ascii_lowercase()), Some(&2));
+        assert_eq!(map.get_rewritten_chars("straẞe", |c| if c == 'ẞ' { 'ß' } else { c }), Some(&1));
+
+        let matches: Vec<_> = map.find_rewritten_chars("STRA", |c| c.to_ascii_lowercase()).map(|(k, _)| k).collect();
+        assert_eq!(matches, vec!["stra", "strasse", "straße"]);
+        assert_eq!(map.find_rewritten_chars("STRAẞ", |c| if c == 'ẞ' { 'ß' } else { c.to_ascii_lowercase() }).count(), 1);
+    }
+
+    #[test]
+    fn it_validates_keys_as_they_are_typed() {
+        let mut map: RadixMap<str, ()> = RadixMap::new();
+        let mut cursor = map.prefix_cursor();
+        assert_eq!(cursor.state(), PrefixState::Dead);
+        assert_eq!(cursor.push_char('a'), PrefixState::Dead);
+
+        for key in &["", "né", "nez", "ni"] {
+            map.insert(key, ());
+        }
+
+        let mut cursor = map.prefix_cursor();
+        assert_eq!(cursor.state(), PrefixState::Key);
+        assert_eq!(cursor.push(b'n'), PrefixState::Prefix);
+        assert_eq!(cursor.clone().push_char('é'), PrefixState::Key);
+        assert_eq!(cursor.clone().push_char('è'), PrefixState::Dead);
+        assert_eq!(cursor.push(0xc3), PrefixState::Prefix);
+        assert_eq!(cursor.push(0xa9), PrefixState::Key);
+        assert_eq!(cursor.push(b'z'), PrefixState::Dead);
+        assert_eq!(cursor.value(), None);
+    }
+
+    #[test]
+    fn it_suggests_encodings() {
+        let empty: RadixMap<str, ()> = RadixMap::new();
+        let suggestion = empty.suggest_encoding();
+        assert_eq!(suggestion.alphabet_size(), 0);
+        assert_eq!(suggestion.average_shared_prefix(), 0.0);
+        assert_eq!(suggestion.average_fan_out(), 0.0);
+
+        let bits: RadixMap<[bool], ()> = vec![(vec![true, false], ()), (vec![true, true], ()), (vec![false], ())]
+            .into_iter().collect();
+        let suggestion = bits.suggest_encoding();
+        assert_eq!((suggestion.bits_per_component(), suggestion.granularity()), (1, Granularity::Bit));
+        assert_eq!(suggestion.average_key_len(), 5.0 / 3.0);
+        assert_eq!(suggestion.average_shared_prefix(), 0.5);
+        // the root and the `true` node have two children each
+        assert_eq!(suggestion.average_fan_out(), 2.0);
+
+        let words: RadixMap<str, ()> = ["apple", "banana", "cherry", "date", "elder", "fig", "grape", "hazel", "iris"]
+            .iter().map(|k| (k, ())).collect();
+        let suggestion = words.suggest_encoding();
+        assert_eq!(suggestion.alphabet_size(), 17);
+        assert_eq!(suggestion.granularity(), Granularity::Component);
+        assert_eq!(suggestion.average_fan_out(), 9.0);
+    }
+
+    #[test]
+    fn it_visits_entries_with_their_shared_prefix() {
+        struct FrontCoder {
+            entries: Vec<(usize, Vec<u8>, i32)>,
+            expected: usize,
+        }
+
+        impl EntryVisitor<str, i32> for FrontCoder {
+            type Error = String;
+
+            fn start(&mut self, len: usize) -> Result<(), String> {
+                self.expected = len;
+                Ok(())
+            }
+
+            fn visit_entry(&mut self, key: &[u8], shared: usize, value: &i32) -> Result<(), String> {
+                if *value < 0 {
+                    return Err(format!("negative value at {:?}", key));
+                }
+                self.entries.push((shared, key[shared..].to_vec(), *value));
+                Ok(())
+            }
+        }
+
+        let mut map: RadixMap<str, i32> = vec![("", 0), ("a", 1), ("abc", 2), ("abd", 3), ("b", 4)].into_iter().collect();
+
+        let mut coder = FrontCoder { entries: Vec::new(), expected: 0 };
+        assert_eq!(map.visit_entries(&mut coder), Ok(()));
+        assert_eq!(coder.expected, 5);
+        assert_eq!(coder.entries, vec![
+            (0, b"".to_vec(), 0),
+            (0, b"a".to_vec(), 1),
+            (1, b"bc".to_vec(), 2),
+            (2, b"d".to_vec(), 3),
+            (0, b"b".to_vec(), 4),
+        ]);
+
+        map.insert("abcd", -1);
+        let mut coder = FrontCoder { entries: Vec::new(), expected: 0 };
+        assert_eq!(map.visit_entries(&mut coder), Err("negative value at [97, 98, 99, 100]".to_string()));
+        assert_eq!(coder.entries.len(), 3);
+    }
+
+    #[test]
+    fn it_stores_boxed_slices() {
+        let mut map: RadixMap<[u8], Box<[u16]>> = RadixMap::new();
+        assert_eq!(map.insert_value_slice(&[1], &[1, 2, 3]), None);
+        assert_eq!(map.insert_value_slice(&[1, 2], &[]), None);
+
+        assert_eq!(map.get_slice(&[1]), Some(&[1, 2, 3][..]));
+        assert_eq!(map.get_slice(&[1, 2]), Some(&[][..]));
+        assert_eq!(map.get_slice(&[2]), None);
+
+        let mut map: RadixMap<str, Box<str>> = RadixMap::new();
+        map.insert_value_str("a", "x");
+        assert_eq!(map.insert_value_str("a", "y").as_deref(), Some("x"));
+        assert_eq!(map.get_str("a"), Some("y"));
+    }
+
+    #[test]
+    fn it_finds_quantile_keys() {
+        let map: RadixMap<[u8], ()> = (0..100u8).map(|i| (vec![i / 10, i % 10], ())).collect();
+
+        assert_eq!(map.quantile_key(0.0), Some(vec![0, 0]));
+        assert_eq!(map.quantile_key(0.25), Some(vec![2, 5]));
+        assert_eq!(map.quantile_key(0.999), Some(vec![9, 9]));
+        assert_eq!(map.quantile_key(1.0), Some(vec![9, 9]));
+        assert_eq!(RadixMap::<[u8], ()>::new().quantile_key(0.5), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_rejects_invalid_quantiles() {
+        let map: RadixMap<str, ()> = RadixMap::new();
+        map.quantile_key(f64::NAN);
+    }
+
+    #[test]
+    fn it_maps_values() {
+        let map: RadixMap<str, usize> = vec![("c", 3), ("a", 1), ("ab", 2)].into_iter().collect();
+        let map = map.map_values(|v| v * 10);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![
+            ("a".to_string(), &10), ("ab".to_string(), &20), ("c".to_string(), &30),
+        ]);
+    }
+
+    #[test]
+    fn it_drops_values_once_when_mapping_panics() {
+        let drops = Cell::new(0);
+        let mut map = RadixMap::<[u8], DropCounter>::new();
+        for i in 0..10u8 {
+            map.insert(&[i % 3, i], DropCounter(&drops));
+        }
+
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.map_values(|v| {
+                calls += 1;
+                if calls == 5 {
+                    panic!("mapping failed");
+                }
+                v
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 10);
+    }
+
+    #[test]
+    fn it_keeps_its_invariants_when_retain_and_prune_panics() {
+        let mut map: RadixMap<str, i32> = vec![("a", 0), ("ab", 1), ("abc", 2), ("abd", 3), ("b", 4)].into_iter().collect();
+
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.retain_and_prune(&[], |_, _| {
+                calls += 1;
+                if calls == 4 {
+                    panic!("keep failed");
+                }
+                false
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(map.len(), map.iter().count());
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["abd", "b"]);
+        assert_eq!(map.select(1).map(|(k, _)| k), Some("b".to_string()));
+        assert!(map.tree.check().is_ok());
+        assert_eq!(map.tree.removals(), 3);
+    }
+
+    #[test]
+    fn it_keeps_its_invariants_when_retain_or_extract_if_panics() {
+        let mut map: RadixMap<str, i32> = (0..20).map(|i| (format!("k/{}", i), i)).collect();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.retain(|key, _| {
+                if key == "k/5" {
+                    panic!("keep failed");
+                }
+                !key.ends_with('1')
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(map.len(), map.iter().count());
+        assert_eq!(map.len(), 18);
+        assert!(map.tree.check().is_ok());
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.extract_if(|key, _| {
+                if key == "k/5" {
+                    panic!("pred failed");
+                }
+                true
+            }).count()
+        }));
+        assert!(result.is_err());
+        assert_eq!(map.len(), map.iter().count());
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["k/5", "k/6", "k/7", "k/8", "k/9"]);
+        assert!(map.tree.check().is_ok());
+    }
+
+    #[test]
+    fn it_finds_entries_by_decreasing_weight() {
+        let mut map: RadixMap<[u8], i32> = RadixMap::new();
+        for (i, &w) in [3, -1, 7, 3, 0].iter().enumerate() {
+            map.insert(&[1, i as u8], w);
+        }
+        map.insert(&[2], 10);
+
+        let ranked: Vec<_> = map.find_by_weight(&[1], |&w| w).map(|(k, &w)| (k[1], w)).collect();
+        assert_eq!(ranked, vec![(2, 7), (0, 3), (3, 3), (4, 0), (1, -1)]);
+
+        let mut lowest = map.find_by_weight(&[], |&w| -w);
+        assert_eq!(lowest.size_hint(), (6, Some(6)));
+        assert_eq!(lowest.next(), Some((vec![1, 1], &-1)));
+        assert_eq!(map.find_by_weight(&[3], |&w| w).count(), 0);
+    }
+
+    #[test]
+    fn it_iterates_in_lexicographic_order() {
+        let keys: Vec<Vec<u8>> = vec![vec![2], vec![1, 2, 3], vec![], vec![1], vec![1, 3], vec![1, 2], vec![0, 9]];
+        let map: RadixMap<[u8], ()> = keys.iter().map(|k| (k, ())).collect();
+
+        let mut expected = keys.clone();
+        expected.sort();
+        assert_eq!(map.keys().collect::<Vec<_>>(), expected);
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![
+            vec![], vec![0, 9], vec![1], vec![1, 2], vec![1, 2, 3], vec![1, 3], vec![2],
+        ]);
+    }
+
+    #[test]
+    fn it_iterates_float_keys_in_total_order() {
+        let key = |fs: &[f64]| -> Vec<OrderedFloat<f64>> { fs.iter().cloned().map(OrderedFloat).collect() };
+
+        let mut map: RadixMap<[OrderedFloat<f64>], usize> = RadixMap::new();
+        for (i, fs) in [&[0.5, 1.0][..], &[-1.5, 2.0], &[0.5, -0.0], &[0.5], &[-1.5, -2.0]].iter().enumerate() {
+            map.insert(&key(fs), i);
+        }
+
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec![4, 1, 3, 2, 0]);
+    }
 
     #[test]
+    #[allow(clippy::map_clone)]
     fn it_can_lookup_elements() {
         let mut map: RadixMap<str, i32> = RadixMap::new();
         map.insert("a", 0);
@@ -418,6 +4260,200 @@ mod tests {
         assert_eq!(keys, vec!["bar", "baz", "foo"]);
     }
 
+    #[test]
+    fn it_has_a_raw_iterator() {
+        let mut map: RadixMap<[u8], i32> = RadixMap::new();
+        map.insert(b"ab", 0);
+        map.insert(b"a", 1);
+        map.insert(b"b", 2);
+
+        let mut entries = Vec::new();
+        let mut it = map.raw_iter();
+        while let Some((k, v)) = it.next_entry() {
+            entries.push((k.to_vec(), *v));
+        }
+
+        let expected: Vec<_> = map.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn it_hashes_contents_regardless_of_structure() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut a: RadixMap<str, i32> = RadixMap::new();
+        a.insert("abc", 0);
+        a.insert("ab", 1);
+
+        // removals leave a differently shaped tree behind
+        let mut b: RadixMap<str, i32> = RadixMap::new();
+        b.insert("ab", 1);
+        b.insert("abd", 2);
+        b.insert("abc", 0);
+        b.remove("abd");
+
+        assert_eq!(a.content_hash::<DefaultHasher>(), b.content_hash::<DefaultHasher>());
+
+        b.insert("", 0);
+        assert_ne!(a.content_hash::<DefaultHasher>(), b.content_hash::<DefaultHasher>());
+    }
+
+    #[test]
+    fn it_sums_prefix_hashes() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let map: RadixMap<str, i32> = vec![("aa", 0), ("ab", 1), ("b", 2), ("", 3)].into_iter().collect();
+        let root: RadixMap<str, i32> = vec![("", 3)].into_iter().collect();
+
+        // the root's digest is the sum of its parts
+        let parts = [
+            root.prefix_hash::<DefaultHasher>(""),
+            map.prefix_hash::<DefaultHasher>("a"),
+            map.prefix_hash::<DefaultHasher>("b"),
+        ];
+        let sum = parts.iter().fold(0u64, |acc, h| acc.wrapping_add(*h));
+        assert_eq!(map.prefix_hash::<DefaultHasher>(""), sum);
+
+        assert_eq!(map.prefix_hash::<DefaultHasher>("c"), 0);
+    }
+
+    #[test]
+    fn it_computes_deltas_between_maps() {
+        let a: RadixMap<str, i32> = vec![("", 0), ("ab", 1), ("abc", 2), ("b", 3)].into_iter().collect();
+        let b: RadixMap<str, i32> = vec![("a", 0), ("abc", 2), ("b", 4), ("bc", 5)].into_iter().collect();
+
+        assert!(a.delta_to(&a).is_empty());
+
+        let mut c: RadixMap<str, i32> = vec![("", 0), ("ab", 1), ("abc", 2), ("b", 3)].into_iter().collect();
+        c.apply_delta(a.delta_to(&b));
+        assert_eq!(c.iter().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+
+        c.apply_delta(b.delta_to(&a));
+        assert_eq!(c.iter().collect::<Vec<_>>(), a.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_chooses_balanced_partition_points() {
+        let map: RadixMap<[u8], ()> = (0..100u8).map(|i| (vec![i / 10, i % 10], ())).collect();
+
+        let points = map.partition_points(4);
+        assert_eq!(points, vec![vec![2, 5], vec![5, 0], vec![7, 5]]);
+
+        let tiny: RadixMap<str, ()> = vec![("a", ()), ("b", ())].into_iter().collect();
+        assert_eq!(tiny.partition_points(10), vec!["b"]);
+        assert!(RadixMap::<str, ()>::new().partition_points(10).is_empty());
+    }
+
+    #[test]
+    fn it_groups_str_keys_by_bytes() {
+        let map: RadixMap<str, ()> = vec![("éa", ()), ("éb", ()), ("èa", ()), ("ea", ())].into_iter().collect();
+
+        // 'é' and 'è' are two bytes long and share the first one, cutting them in half would
+        // produce invalid strings, and mix them up
+        assert_eq!(map.group_count(1), vec![("e".to_string(), 1), ("è".to_string(), 1), ("é".to_string(), 2)]);
+        assert_eq!(map.group_count(2), vec![("ea".to_string(), 1), ("è".to_string(), 1), ("é".to_string(), 2)]);
+        assert_eq!(map.group_count(3), vec![("èa".to_string(), 1), ("éa".to_string(), 1), ("éb".to_string(), 1)]);
+    }
+
+    #[test]
+    fn it_converts_from_and_into_std_maps() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let mut btree = BTreeMap::new();
+        btree.insert("b".to_string(), 2);
+        btree.insert("a".to_string(), 1);
+
+        let map: RadixMap<str, i32> = btree.clone().into();
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![("a".to_string(), &1), ("b".to_string(), &2)]);
+        assert_eq!(BTreeMap::from(map), btree);
+
+        let hash: HashMap<Vec<u8>, i32> = btree.into_iter().map(|(k, v)| (k.into_bytes(), v)).collect();
+        let map = RadixMap::<[u8], i32>::from(hash.clone());
+        assert_eq!(map.get(b"a"), Some(&1));
+        assert_eq!(HashMap::from(map), hash);
+    }
+
+    #[test]
+    fn it_completes_characters_incrementally() {
+        let map: RadixMap<str, ()> = vec![("été", ()), ("étang", ()), ("ete", ())].into_iter().collect();
+
+        let mut completer = map.completer();
+        assert!(completer.push_char('é'));
+        assert!(completer.push_char('t'));
+        assert_eq!(completer.completions().count(), 2);
+        assert!(!completer.push_char('x'));
+        assert_eq!(completer.completions().count(), 0);
+
+        assert_eq!(completer.pop_char(), Some('x'));
+        assert_eq!(completer.pop_char(), Some('t'));
+        assert_eq!(completer.pop_char(), Some('é'));
+        assert_eq!(completer.pop_char(), None);
+        assert_eq!(completer.completions().count(), 3);
+    }
+
+    #[test]
+    fn it_runs_queries_in_a_single_traversal() {
+        let map: RadixMap<[u8], usize> = (0..100u8).map(|i| (vec![i / 10; (i % 10) as usize], i as usize)).collect();
+
+        assert_eq!(map.query().run().len(), 91);
+        assert_eq!(map.query().limit(0).run(), vec![]);
+
+        let entries = map.query().prefix(&[3]).min_len(2).max_len(4).filter(|v| v % 2 == 0).limit(2).run();
+        assert_eq!(entries, vec![(vec![3, 3], &32), (vec![3, 3, 3, 3], &34)]);
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[test]
+    #[should_panic(expected = "paranoid")]
+    fn it_catches_diverging_trees() {
+        let mut map: RadixMap<str, i32> = RadixMap::new();
+        map.insert("a", 0);
+
+        // bypass the reference set
+        map.tree.insert(b"b", 1);
+        map.insert("c", 2);
+    }
+
+    #[test]
+    fn it_lends_keys_from_its_iterators() {
+        let map: RadixMap<str, i32> = vec![("ab", 1), ("abc", 2), ("b", 3)].into_iter().collect();
+
+        let mut it = map.iter();
+        assert_eq!(it.next_ref(), Some(("ab", &1)));
+        assert_eq!(it.next(), Some(("abc".to_string(), &2)));
+        assert_eq!(it.next_ref(), Some(("b", &3)));
+        assert_eq!(it.next_ref(), None);
+
+        let mut keys = map.keys();
+        keys.next();
+        assert_eq!(keys.next_ref(), Some("abc"));
+
+        let mut matches = map.find("ab");
+        assert_eq!(matches.next_ref(), Some(("ab", &1)));
+        assert_eq!(matches.next_ref(), Some(("abc", &2)));
+        assert_eq!(matches.next_ref(), None);
+
+        let bytes: RadixMap<[u8], ()> = vec![(&b"xy"[..], ())].into_iter().collect();
+        assert_eq!(bytes.iter().next_ref(), Some((&b"xy"[..], &())));
+    }
+
+    #[test]
+    fn it_merges_several_maps() {
+        let a: RadixMap<[u8], i32> = vec![(&b"a"[..], 0), (b"ab", 1), (b"b", 2)].into_iter().collect();
+        let b: RadixMap<[u8], i32> = RadixMap::new();
+        let c: RadixMap<[u8], i32> = vec![(&b""[..], 3), (b"ab", 4), (b"c", 5)].into_iter().collect();
+
+        let merged: Vec<_> = RadixMap::merge_iter(&[&c, &b, &a]).collect();
+        assert_eq!(merged, vec![
+            (b"".to_vec(), vec![&3]),
+            (b"a".to_vec(), vec![&0]),
+            (b"ab".to_vec(), vec![&4, &1]),
+            (b"b".to_vec(), vec![&2]),
+            (b"c".to_vec(), vec![&5]),
+        ]);
+        assert_eq!(RadixMap::<[u8], i32>::merge_iter(&[]).count(), 0);
+    }
+
     #[test]
     fn it_has_a_value_iterator() {
         let mut map: RadixMap<str, i32> = RadixMap::new();
@@ -428,4 +4464,19 @@ mod tests {
         let values: Vec<_> = map.values().collect();
         assert_eq!(values, vec![&1, &2, &0]);
     }
+
+    #[test]
+    fn it_accepts_any_key_in_every_lookup() {
+        let mut map: RadixMap<str, i32> = vec![("a", 0), ("ab", 1), ("b", 2), ("c", 3)].into_iter().collect();
+        let (a, ab, b) = (String::from("a"), String::from("ab"), String::from("b"));
+
+        assert!(map.swap(&a, "b"));
+        assert!(map.rename_key(&ab, &String::from("ac")));
+        assert_eq!(map.rank(&b), 2);
+        assert_eq!(map.range(&a, &b).count(), 2);
+        assert_eq!(map.find_mut(&a).count(), 2);
+        assert_eq!(map.find_one(&b), Some(("b".to_string(), &0)));
+        assert_eq!(map.remove_prefix(&a).len(), 2);
+        assert_eq!(map.len(), 2);
+    }
 }
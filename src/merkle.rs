@@ -0,0 +1,193 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use map::{RadixMap, Matches as MapMatches};
+use key::Key;
+
+/// A map maintaining the digest of every prefix of its keys in the nodes of its tree, so that
+/// [`prefix_hash`] costs a single descent instead of a traversal of the entries.
+///
+/// The digests are the same as those of [`RadixMap::prefix_hash`] with the same hasher: each
+/// entry is hashed on its own, and the digest of a prefix is the (wrapping) sum of the digests of
+/// the entries under it. Inserting or removing a key only updates the nodes along its path.
+///
+/// Values can't be borrowed mutably, as their digests would go stale.
+///
+/// [`prefix_hash`]: #method.prefix_hash
+/// [`RadixMap::prefix_hash`]: ../map/struct.RadixMap.html#method.prefix_hash
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::merkle::MerkleRadixMap;
+///
+/// let mut a = MerkleRadixMap::new();
+/// a.insert("user:1", "alice");
+/// a.insert("user:2", "bob");
+/// a.insert("group:1", "admins");
+///
+/// let mut b = MerkleRadixMap::new();
+/// b.insert("user:1", "alice");
+/// b.insert("user:2", "eve");
+/// b.insert("group:1", "admins");
+///
+/// assert_ne!(a.prefix_hash(""), b.prefix_hash(""));
+/// assert_eq!(a.prefix_hash("group:"), b.prefix_hash("group:"));
+/// assert_ne!(a.prefix_hash("user:"), b.prefix_hash("user:"));
+///
+/// b.insert("user:2", "bob");
+/// assert_eq!(a.prefix_hash(""), b.prefix_hash(""));
+/// ```
+pub struct MerkleRadixMap<K: Key + ?Sized, V, H = DefaultHasher> {
+    map: RadixMap<K, Entry<V>>,
+    hasher: PhantomData<fn() -> H>,
+}
+
+struct Entry<V> {
+    value: V,
+    digest: u64,
+}
+
+impl<K: Key + ?Sized, V> MerkleRadixMap<K, V> {
+    /// Makes a new empty map, hashing its entries with `DefaultHasher`. A map using another
+    /// hasher is made through `Default`.
+    pub fn new() -> MerkleRadixMap<K, V> {
+        MerkleRadixMap::default()
+    }
+}
+
+impl<K: Key + ?Sized, V, H> MerkleRadixMap<K, V, H> {
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|e| &e.value)
+    }
+
+    /// Returns `true` if the map contains a value for the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Removes a key from the map, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let digest = self.map.get(key)?.digest;
+        self.map.tree.add_to_sums(key.as_slice(), u128::from(digest).wrapping_neg());
+        self.map.remove(key).map(|e| e.value)
+    }
+
+    /// Clears the map, removing all entries.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns the digest of the entries whose key starts with `prefix`, 0 if there are none.
+    ///
+    /// The digest of the empty prefix is the digest of the whole map.
+    pub fn prefix_hash(&self, prefix: &K) -> u64 {
+        // the sums wrap at `u128::MAX`, truncating them wraps the digests at `u64::MAX`
+        self.map.tree.sum(prefix.as_slice()) as u64
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, sorted by key.
+    pub fn find<'a>(&'a self, key: &K) -> Matches<'a, K, V> {
+        Matches {
+            matches: self.map.find(key),
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V: Hash, H: Hasher + Default> MerkleRadixMap<K, V, H>
+    where K::Component: Hash,
+{
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        let mut hasher = H::default();
+        key.as_slice().hash(&mut hasher);
+        value.hash(&mut hasher);
+        let digest = hasher.finish();
+
+        let old = self.map.insert(key, Entry { value, digest });
+        let delta = u128::from(digest).wrapping_sub(old.as_ref().map_or(0, |e| e.digest.into()));
+        self.map.tree.add_to_sums(key.as_slice(), delta);
+        old.map(|e| e.value)
+    }
+}
+
+impl<K: Key + ?Sized, V, H> Default for MerkleRadixMap<K, V, H> {
+    fn default() -> MerkleRadixMap<K, V, H> {
+        MerkleRadixMap {
+            map: RadixMap::new(),
+            hasher: PhantomData,
+        }
+    }
+}
+
+/// An iterator over the entries matching a call to [`find`].
+///
+/// [`find`]: struct.MerkleRadixMap.html#method.find
+pub struct Matches<'a, K: 'a + Key + ?Sized, V: 'a> {
+    matches: MapMatches<'a, K, Entry<V>>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] Matches<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Matches<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next().map(|(k, e)| (k, &e.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use map::RadixMap;
+    use super::MerkleRadixMap;
+
+    fn check(merkle: &MerkleRadixMap<str, u32>, map: &RadixMap<str, u32>, prefixes: &[&str]) {
+        for prefix in prefixes {
+            assert_eq!(merkle.prefix_hash(prefix), map.prefix_hash::<DefaultHasher>(prefix), "{:?}", prefix);
+        }
+    }
+
+    #[test]
+    fn it_maintains_the_digests_of_the_prefixes() {
+        let prefixes = ["", "a", "ab", "abc", "abcd", "abd", "b", "ba", "bar", "c"];
+        let mut merkle: MerkleRadixMap<str, u32> = MerkleRadixMap::new();
+        let mut map: RadixMap<str, u32> = RadixMap::new();
+
+        // "abc" then "ab" splits an edge, "abd" branches inside it
+        for (i, &key) in ["abcd", "abc", "ab", "abd", "bar", "a"].iter().enumerate() {
+            assert_eq!(merkle.insert(key, i as u32), None);
+            map.insert(key, i as u32);
+            check(&merkle, &map, &prefixes);
+        }
+
+        assert_eq!(merkle.insert("abc", 10), Some(1));
+        map.insert("abc", 10);
+        check(&merkle, &map, &prefixes);
+
+        // removing "ab" then "abd" merges the edges back
+        for &key in &["ab", "abd", "a", "abcd", "bar", "abc"] {
+            assert_eq!(merkle.remove(key), map.remove(key));
+            check(&merkle, &map, &prefixes);
+        }
+        assert_eq!(merkle.remove("abc"), None);
+        assert!(merkle.is_empty());
+        assert_eq!(merkle.prefix_hash(""), 0);
+    }
+}
@@ -0,0 +1,151 @@
+use map::{RadixMap, Matches as MapMatches};
+use key::Key;
+
+/// A map storing a `u64` of metadata next to each value, e.g. flags or a timestamp, to mark
+/// entries without changing the type of the values.
+///
+/// The metadata of a new entry is 0, and it is kept when the value of an entry is replaced.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::meta::MetaRadixMap;
+///
+/// const PINNED: u64 = 1;
+/// const STALE: u64 = 2;
+///
+/// let mut pages = MetaRadixMap::new();
+/// pages.insert("/", "home");
+/// pages.insert("/about", "about us");
+/// pages.insert("/blog", "posts");
+///
+/// pages.set_meta("/", PINNED);
+/// pages.set_meta("/blog", STALE);
+/// pages.insert("/", "new home");
+///
+/// assert_eq!(pages.get_meta("/"), Some(PINNED));
+/// let stale: Vec<_> = pages.find("/").filter(|&(_, _, m)| m & STALE != 0).map(|(k, _, _)| k).collect();
+/// assert_eq!(stale, vec!["/blog"]);
+/// ```
+pub struct MetaRadixMap<K: Key + ?Sized, V> {
+    map: RadixMap<K, Entry<V>>,
+}
+
+struct Entry<V> {
+    value: V,
+    meta: u64,
+}
+
+impl<K: Key + ?Sized, V> MetaRadixMap<K, V> {
+    /// Makes a new empty map.
+    pub fn new() -> MetaRadixMap<K, V> {
+        MetaRadixMap { map: RadixMap::new() }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|e| &e.value)
+    }
+
+    /// Returns a mutable reference to the value of a key.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.map.get_mut(key).map(|e| &mut e.value)
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    ///
+    /// The metadata of the key is left as is, or set to 0 if the key is new.
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        match self.map.get_mut(key) {
+            Some(entry) => Some(::std::mem::replace(&mut entry.value, value)),
+            None        => { self.map.insert(key, Entry { value, meta: 0 }); None },
+        }
+    }
+
+    /// Removes a key from the map, returning its value and metadata.
+    pub fn remove(&mut self, key: &K) -> Option<(V, u64)> {
+        self.map.remove(key).map(|e| (e.value, e.meta))
+    }
+
+    /// Returns the metadata of a key.
+    pub fn get_meta(&self, key: &K) -> Option<u64> {
+        self.map.get(key).map(|e| e.meta)
+    }
+
+    /// Sets the metadata of a key, returning the previous one, or `None` (setting nothing) if the
+    /// key isn't in the map.
+    pub fn set_meta(&mut self, key: &K, meta: u64) -> Option<u64> {
+        self.map.get_mut(key).map(|e| ::std::mem::replace(&mut e.meta, meta))
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key`, along with their metadata,
+    /// sorted by key.
+    pub fn find<'a>(&'a self, key: &K) -> Matches<'a, K, V> {
+        Matches {
+            matches: self.map.find(key),
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V> Default for MetaRadixMap<K, V> {
+    fn default() -> MetaRadixMap<K, V> {
+        MetaRadixMap::new()
+    }
+}
+
+/// An iterator over the entries matching a call to [`find`].
+///
+/// [`find`]: struct.MetaRadixMap.html#method.find
+pub struct Matches<'a, K: 'a + Key + ?Sized, V: 'a> {
+    matches: MapMatches<'a, K, Entry<V>>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] Matches<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Matches<'a, K, V> {
+    type Item = (K::Owned, &'a V, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next().map(|(k, e)| (k, &e.value, e.meta))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.matches.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetaRadixMap;
+
+    #[test]
+    fn it_keeps_metadata_across_updates() {
+        let mut map: MetaRadixMap<[u8], &str> = MetaRadixMap::new();
+        assert_eq!(map.set_meta(b"a", 1), None);
+        assert_eq!(map.insert(b"a", "x"), None);
+        assert_eq!(map.get_meta(b"a"), Some(0));
+
+        assert_eq!(map.set_meta(b"a", 7), Some(0));
+        assert_eq!(map.insert(b"a", "y"), Some("x"));
+        *map.get_mut(b"a").unwrap() = "z";
+        assert_eq!(map.get_meta(b"a"), Some(7));
+        assert_eq!(map.get(b"a"), Some(&"z"));
+
+        assert_eq!(map.remove(b"a"), Some(("z", 7)));
+        assert_eq!(map.insert(b"a", "x"), None);
+        assert_eq!(map.get_meta(b"a"), Some(0));
+        assert_eq!(map.find(b"").collect::<Vec<_>>(), vec![(b"a".to_vec(), &"x", 0)]);
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The number of buckets of a `Histogram`, the last one counting all the waits of 2^31 ns (about 2
+/// seconds) or more.
+const BUCKETS: usize = 32;
+
+/// The counters of a lock, updated as it's acquired.
+pub(crate) struct LockCounters {
+    acquisitions: AtomicU64,
+    contended: AtomicU64,
+    waits: [AtomicU64; BUCKETS],
+}
+
+impl LockCounters {
+    pub(crate) fn new() -> LockCounters {
+        LockCounters {
+            acquisitions: AtomicU64::new(0),
+            contended: AtomicU64::new(0),
+            waits: [(); BUCKETS].map(|_| AtomicU64::new(0)),
+        }
+    }
+
+    /// Record the lock being acquired, having tried since `start`.
+    pub(crate) fn acquired(&self, start: Instant) {
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        self.waits[bucket(start.elapsed())].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an attempt to acquire the lock finding it held, before waiting for it or giving up,
+    /// so that a waiting thread is counted as soon as it starts waiting.
+    pub(crate) fn contended(&self) {
+        self.contended.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LockMetrics {
+        LockMetrics {
+            acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            contended: self.contended.load(Ordering::Relaxed),
+            wait: Histogram {
+                buckets: self.waits.each_ref().map(|b| b.load(Ordering::Relaxed)),
+            },
+        }
+    }
+}
+
+fn bucket(elapsed: Duration) -> usize {
+    let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+    (63 - (nanos | 1).leading_zeros() as usize).min(BUCKETS - 1)
+}
+
+/// Metrics on a lock of a concurrent map, to tune how the map is shared between threads.
+///
+/// The counters are updated with relaxed atomic operations, so a snapshot taken while the map is
+/// used may be slightly inconsistent, e.g. an acquisition may be counted before its wait.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::sync::PrefixLockMap;
+///
+/// let map = PrefixLockMap::<str, u32>::new();
+/// {
+///     let _a = map.lock_prefix("a/");
+///     assert!(map.try_lock_prefix("a/b").is_none());
+/// }
+///
+/// let metrics = map.metrics();
+/// assert_eq!((metrics.acquisitions(), metrics.contended()), (1, 1));
+/// assert_eq!(metrics.wait().count(), 1);
+/// println!("p99 wait: {:?}", metrics.wait().quantile(0.99));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LockMetrics {
+    acquisitions: u64,
+    contended: u64,
+    wait: Histogram,
+}
+
+impl LockMetrics {
+    /// Returns the number of times the lock was acquired.
+    pub fn acquisitions(&self) -> u64 {
+        self.acquisitions
+    }
+
+    /// Returns the number of attempts to acquire the lock that found it held, whether they then
+    /// waited for it or gave up.
+    pub fn contended(&self) -> u64 {
+        self.contended
+    }
+
+    /// Returns the histogram of the time taken to acquire the lock.
+    pub fn wait(&self) -> &Histogram {
+        &self.wait
+    }
+}
+
+/// A histogram of durations, in buckets of powers of two nanoseconds.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    buckets: [u64; BUCKETS],
+}
+
+impl Histogram {
+    /// Returns the number of durations recorded.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Returns the number of durations recorded in each bucket: the bucket `i` counts durations of
+    /// at least 2^i ns and less than 2^(i + 1) ns, except the first one which starts at 0 and the
+    /// last one which has no upper bound.
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Returns an upper bound of the `q` quantile of the durations (e.g. 0.99 for the 99th
+    /// percentile), being the upper bound of its bucket, or `None` if no duration was recorded.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Some(Duration::from_nanos(1 << (i + 1)));
+            }
+        }
+        unreachable!("the rank is at most the number of durations")
+    }
+}
+
+/// Metrics on the locks of a map created by [`sync::new`].
+///
+/// [`sync::new`]: ../sync/fn.new.html
+#[derive(Clone, Debug)]
+pub struct SharedMetrics {
+    pub(crate) readers: LockMetrics,
+    pub(crate) writer: LockMetrics,
+}
+
+impl SharedMetrics {
+    /// Returns the metrics of the readers getting a view of the published copy, which only
+    /// contend when the writer publishes at the same time.
+    pub fn readers(&self) -> &LockMetrics {
+        &self.readers
+    }
+
+    /// Returns the metrics of the writer, which contends with the readers still looking at the
+    /// copy it writes to.
+    pub fn writer(&self) -> &LockMetrics {
+        &self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{bucket, LockCounters};
+
+    #[test]
+    fn it_buckets_durations_by_powers_of_two() {
+        assert_eq!(bucket(Duration::from_nanos(0)), 0);
+        assert_eq!(bucket(Duration::from_nanos(1)), 0);
+        assert_eq!(bucket(Duration::from_nanos(2)), 1);
+        assert_eq!(bucket(Duration::from_nanos(1023)), 9);
+        assert_eq!(bucket(Duration::from_nanos(1024)), 10);
+        assert_eq!(bucket(Duration::from_secs(3600)), 31);
+
+        let counters = LockCounters::new();
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.wait().quantile(0.5), None);
+
+        for _ in 0..99 {
+            counters.acquired(Instant::now());
+        }
+        counters.contended();
+        counters.acquired(Instant::now() - Duration::from_secs(1));
+        counters.contended();
+
+        let metrics = counters.snapshot();
+        assert_eq!((metrics.acquisitions(), metrics.contended()), (100, 2));
+        assert_eq!(metrics.wait().count(), 100);
+        assert!(metrics.wait().quantile(0.5).unwrap() < Duration::from_millis(100));
+        assert!(metrics.wait().quantile(1.0).unwrap() > Duration::from_secs(1));
+        assert_eq!(metrics.wait().buckets()[29], 1);
+    }
+}
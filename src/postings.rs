@@ -0,0 +1,224 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::Deref;
+
+use map::RadixMap;
+use key::Key;
+use tree::Matches as TreeMatches;
+
+/// A sorted list of document IDs, without duplicates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PostingList {
+    ids: Vec<u64>,
+}
+
+impl PostingList {
+    /// Makes a new empty list.
+    pub fn new() -> PostingList {
+        PostingList { ids: Vec::new() }
+    }
+
+    /// Inserts a document ID at its place in the list, returning `false` if it was already there.
+    pub fn insert(&mut self, id: u64) -> bool {
+        match self.ids.binary_search(&id) {
+            Ok(_)  => false,
+            Err(i) => { self.ids.insert(i, id); true },
+        }
+    }
+
+    /// Removes a document ID from the list, returning `false` if it wasn't there.
+    pub fn remove(&mut self, id: u64) -> bool {
+        match self.ids.binary_search(&id) {
+            Ok(i)  => { self.ids.remove(i); true },
+            Err(_) => false,
+        }
+    }
+
+    /// Returns if the document ID is in the list.
+    pub fn contains(&self, id: u64) -> bool {
+        self.ids.binary_search(&id).is_ok()
+    }
+}
+
+impl Deref for PostingList {
+    type Target = [u64];
+
+    fn deref(&self) -> &[u64] {
+        &self.ids
+    }
+}
+
+/// An inverted index, mapping terms to the documents they appear in.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::postings::InvertedIndex;
+///
+/// let mut index = InvertedIndex::new();
+/// index.insert_doc("rust", 1);
+/// index.insert_doc("rusty", 3);
+/// index.insert_doc("rust", 2);
+/// index.insert_doc("ruby", 2);
+///
+/// assert_eq!(&index.get("rust").unwrap()[..], &[1, 2]);
+/// assert_eq!(index.find("rus").collect::<Vec<_>>(), vec![1, 2, 3]);
+/// assert_eq!(index.find("ru").collect::<Vec<_>>(), vec![1, 2, 3]);
+/// ```
+pub struct InvertedIndex<K: Key + ?Sized> {
+    map: RadixMap<K, PostingList>,
+}
+
+impl<K: Key + ?Sized> InvertedIndex<K> {
+    /// Makes a new empty index.
+    pub fn new() -> InvertedIndex<K> {
+        InvertedIndex { map: RadixMap::new() }
+    }
+
+    /// Returns the number of terms in the index.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the index contains no terms.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Adds a document to the posting list of a term, returning `false` if it was already there.
+    pub fn insert_doc(&mut self, term: &K, id: u64) -> bool {
+        if let Some(postings) = self.map.get_mut(term) {
+            return postings.insert(id);
+        }
+
+        let mut postings = PostingList::new();
+        postings.insert(id);
+        self.map.insert(term, postings);
+        true
+    }
+
+    /// Removes a document from the posting list of a term, returning `false` if it wasn't there.
+    ///
+    /// Terms are removed along with their last document.
+    pub fn remove_doc(&mut self, term: &K, id: u64) -> bool {
+        let (removed, is_empty) = match self.map.get_mut(term) {
+            Some(postings) => (postings.remove(id), postings.is_empty()),
+            None           => return false,
+        };
+
+        if is_empty {
+            self.map.remove(term);
+        }
+        removed
+    }
+
+    /// Gets the posting list of a term.
+    pub fn get(&self, term: &K) -> Option<&PostingList> {
+        self.map.get(term)
+    }
+
+    /// Gets an iterator over the documents of all the terms starting with `prefix`, sorted and
+    /// without duplicates.
+    ///
+    /// Nothing is done until the iterator is first advanced, which gathers the posting lists of
+    /// the terms (without building their keys), the lists are then merged as it advances.
+    pub fn find<'a>(&'a self, prefix: &K) -> Postings<'a, K> {
+        Postings {
+            lists: Some(self.map.tree.find(prefix.as_slice())),
+            heap: BinaryHeap::new(),
+            last: None,
+        }
+    }
+}
+
+impl<K: Key + ?Sized> Default for InvertedIndex<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An iterator over the documents matching a call to [`find`].
+///
+/// [`find`]: struct.InvertedIndex.html#method.find
+pub struct Postings<'a, K: 'a + Key + ?Sized> {
+    // the posting lists of the matching terms, until they are pushed into the heap
+    lists: Option<TreeMatches<'a, K::Component, PostingList>>,
+    // the next document of each list, along with the rest of the list
+    heap: BinaryHeap<Reverse<(u64, &'a [u64])>>,
+    last: Option<u64>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized] Postings<'a, K>);
+
+impl<'a, K: 'a + Key + ?Sized> Iterator for Postings<'a, K> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if let Some(mut lists) = self.lists.take() {
+            while let Some((_, list)) = lists.next_raw() {
+                if let Some((&id, rest)) = list.split_first() {
+                    self.heap.push(Reverse((id, rest)));
+                }
+            }
+        }
+
+        while let Some(Reverse((id, rest))) = self.heap.pop() {
+            if let Some((&next, rest)) = rest.split_first() {
+                self.heap.push(Reverse((next, rest)));
+            }
+
+            if self.last != Some(id) {
+                self.last = Some(id);
+                return Some(id);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvertedIndex;
+
+    #[test]
+    fn it_keeps_postings_sorted() {
+        let mut index = InvertedIndex::<str>::new();
+        for &id in [5, 1, 3, 1, 4].iter() {
+            index.insert_doc("a", id);
+        }
+
+        assert_eq!(&index.get("a").unwrap()[..], &[1, 3, 4, 5]);
+        assert!(index.get("a").unwrap().contains(4));
+        assert!(!index.insert_doc("a", 3));
+    }
+
+    #[test]
+    fn it_removes_terms_without_documents() {
+        let mut index = InvertedIndex::<str>::new();
+        index.insert_doc("a", 1);
+        index.insert_doc("a", 2);
+
+        assert!(index.remove_doc("a", 1));
+        assert!(!index.remove_doc("a", 1));
+        assert_eq!(index.len(), 1);
+
+        assert!(index.remove_doc("a", 2));
+        assert!(index.is_empty());
+        assert!(!index.remove_doc("b", 2));
+    }
+
+    #[test]
+    fn it_merges_postings_of_a_prefix() {
+        let mut index = InvertedIndex::<str>::new();
+        for &(term, id) in [("ab", 4), ("ab", 1), ("abc", 2), ("abd", 4), ("abd", 7), ("b", 3)].iter() {
+            index.insert_doc(term, id);
+        }
+
+        assert_eq!(index.find("ab").collect::<Vec<_>>(), vec![1, 2, 4, 7]);
+        assert_eq!(index.find("").collect::<Vec<_>>(), vec![1, 2, 3, 4, 7]);
+        assert_eq!(index.find("c").count(), 0);
+    }
+}
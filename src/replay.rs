@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use map::RadixMap;
+
+/// An operation of a trace, see [`parse`].
+///
+/// [`parse`]: fn.parse.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Inserts a key-value pair.
+    Insert(String, i64),
+    /// Removes a key.
+    Remove(String),
+    /// Looks a key up.
+    Get(String),
+    /// Lists the entries whose key starts with a prefix.
+    Find(String),
+    /// Removes all the entries.
+    Clear,
+}
+
+/// An error found while parsing or replaying a trace, along with its line (starting at 1).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayError {
+    /// The line of the step that failed.
+    pub line: usize,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parses a trace, i.e. one operation per line among:
+///
+/// - `insert <key> <value>`, values being integers,
+/// - `remove <key>`,
+/// - `get <key>`,
+/// - `find <prefix>`,
+/// - `clear`.
+///
+/// Keys can't contain whitespace, `""` denotes the empty key. Blank lines and lines starting
+/// with `#` are ignored.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::replay::{parse, Step};
+///
+/// let steps = parse("# split an edge\ninsert abc 1\ninsert ab 2\nfind \"\"").unwrap();
+/// assert_eq!(steps, vec![
+///     (2, Step::Insert("abc".to_string(), 1)),
+///     (3, Step::Insert("ab".to_string(), 2)),
+///     (4, Step::Find("".to_string())),
+/// ]);
+/// ```
+pub fn parse(trace: &str) -> Result<Vec<(usize, Step)>, ReplayError> {
+    let mut steps = Vec::new();
+
+    for (i, line) in trace.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: String| ReplayError { line: i + 1, message };
+
+        let words: Vec<_> = line.split_whitespace().collect();
+        let key = |n: usize| match words.get(n) {
+            Some(&"\"\"") => Ok(String::new()),
+            Some(key)     => Ok(key.to_string()),
+            None          => Err(error(format!("missing argument to `{}`", words[0]))),
+        };
+
+        let (step, arity) = match words[0] {
+            "insert" => {
+                let value = words.get(2)
+                    .ok_or_else(|| error("missing value to `insert`".to_string()))?
+                    .parse()
+                    .map_err(|e| error(format!("invalid value: {}", e)))?;
+                (Step::Insert(key(1)?, value), 3)
+            }
+            "remove" => (Step::Remove(key(1)?), 2),
+            "get"    => (Step::Get(key(1)?), 2),
+            "find"   => (Step::Find(key(1)?), 2),
+            "clear"  => (Step::Clear, 1),
+            op       => return Err(error(format!("unknown operation `{}`", op))),
+        };
+
+        if words.len() > arity {
+            return Err(error(format!("too many arguments to `{}`", words[0])));
+        }
+        steps.push((i + 1, step));
+    }
+
+    Ok(steps)
+}
+
+/// Replays a trace (see [`parse`]) against a `RadixMap<str, i64>` and a `BTreeMap` used as a
+/// reference, failing on the first step where they disagree or where the tree's invariants don't
+/// hold.
+///
+/// [`parse`]: fn.parse.html
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::replay;
+///
+/// let trace = "insert romane 1\ninsert romanus 2\nremove romane\nfind rom";
+/// assert_eq!(replay::replay(trace), Ok(()));
+/// ```
+pub fn replay(trace: &str) -> Result<(), ReplayError> {
+    let mut map: RadixMap<str, i64> = RadixMap::new();
+    let mut reference = BTreeMap::new();
+
+    for (line, step) in parse(trace)? {
+        let diverged = |found: String, expected: String| ReplayError {
+            line,
+            message: format!("`{:?}` returned {} instead of {}", step, found, expected),
+        };
+
+        match step {
+            Step::Insert(ref key, value) => {
+                let found = map.insert(key, value);
+                let expected = reference.insert(key.clone(), value);
+                if found != expected {
+                    return Err(diverged(format!("{:?}", found), format!("{:?}", expected)));
+                }
+            }
+            Step::Remove(ref key) => {
+                let found = map.remove(key);
+                let expected = reference.remove(key);
+                if found != expected {
+                    return Err(diverged(format!("{:?}", found), format!("{:?}", expected)));
+                }
+            }
+            Step::Get(ref key) => {
+                let found = map.get(key);
+                let expected = reference.get(key);
+                if found != expected {
+                    return Err(diverged(format!("{:?}", found), format!("{:?}", expected)));
+                }
+            }
+            Step::Find(ref prefix) => {
+                let found: Vec<_> = map.find(prefix).collect();
+                let expected: Vec<_> = reference.range(prefix.clone()..)
+                    .take_while(|(k, _)| k.starts_with(prefix.as_str()))
+                    .map(|(k, v)| (k.clone(), v))
+                    .collect();
+                if found != expected {
+                    return Err(diverged(format!("{:?}", found), format!("{:?}", expected)));
+                }
+            }
+            Step::Clear => {
+                map.clear();
+                reference.clear();
+            }
+        }
+
+        if let Err(e) = map.tree.check() {
+            let mut dump = String::new();
+            let _ = map.dump_structure(&mut dump);
+            return Err(ReplayError { line, message: format!("{}\n{}", e, dump) });
+        }
+
+        if map.len() != reference.len() {
+            return Err(ReplayError {
+                line,
+                message: format!("map has {} entries instead of {}", map.len(), reference.len()),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, replay, ReplayError, Step};
+
+    #[test]
+    fn it_parses_traces() {
+        let steps = parse("  insert a -1\n\n# comment\nremove \"\"\nget b\nclear").unwrap();
+        assert_eq!(steps, vec![
+            (1, Step::Insert("a".to_string(), -1)),
+            (4, Step::Remove("".to_string())),
+            (5, Step::Get("b".to_string())),
+            (6, Step::Clear),
+        ]);
+    }
+
+    #[test]
+    fn it_reports_invalid_lines() {
+        let error = |line, message: &str| Err(ReplayError { line, message: message.to_string() });
+
+        assert_eq!(parse("get a\nremove"), error(2, "missing argument to `remove`"));
+        assert_eq!(parse("insert a"), error(1, "missing value to `insert`"));
+        assert_eq!(parse("clear a"), error(1, "too many arguments to `clear`"));
+        assert_eq!(parse("pop a"), error(1, "unknown operation `pop`"));
+        assert!(parse("insert a b").is_err());
+    }
+
+    #[test]
+    fn it_replays_traces() {
+        assert_eq!(replay("insert a 1\ninsert ab 2\ninsert a 3\nget a\nremove a\nfind a\nclear\nget ab"), Ok(()));
+    }
+}
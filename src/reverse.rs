@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use map::RadixMap;
+use set::{RadixSet, Iter as SetIter};
+use key::Key;
+
+/// A map along with a reverse index from its values to their keys.
+///
+/// The map can only be changed through this struct, which keeps the index in sync, but it can be
+/// read as a plain `RadixMap` with [`as_map`].
+///
+/// [`as_map`]: #method.as_map
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::reverse::ReverseIndexedMap;
+///
+/// let mut owners = ReverseIndexedMap::new();
+/// owners.insert("src/map.rs", "alice");
+/// owners.insert("src/set.rs", "bob");
+/// owners.insert("src/tree.rs", "alice");
+///
+/// assert_eq!(owners.keys_with_value(&"alice").collect::<Vec<_>>(), vec!["src/map.rs", "src/tree.rs"]);
+///
+/// owners.insert("src/tree.rs", "bob");
+/// assert_eq!(owners.keys_with_value(&"bob").collect::<Vec<_>>(), vec!["src/set.rs", "src/tree.rs"]);
+/// assert_eq!(owners.as_map().find("src/m").count(), 1);
+/// ```
+pub struct ReverseIndexedMap<K: Key + ?Sized, V: Eq + Hash + Clone> {
+    map: RadixMap<K, V>,
+    index: HashMap<V, RadixSet<K>>,
+}
+
+impl<K: Key + ?Sized, V: Eq + Hash + Clone> ReverseIndexedMap<K, V> {
+    /// Makes a new empty map.
+    pub fn new() -> ReverseIndexedMap<K, V> {
+        ReverseIndexedMap {
+            map: RadixMap::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Returns the map, to run any of its read-only queries.
+    pub fn as_map(&self) -> &RadixMap<K, V> {
+        &self.map
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        self.index.entry(value.clone()).or_default().insert(key);
+
+        let old = self.map.insert(key, value);
+        if let Some(ref old) = old {
+            if *old != *self.map.get(key).unwrap() {
+                self.unindex(key, old);
+            }
+        }
+        old
+    }
+
+    /// Removes a key from the map, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let old = self.map.remove(key);
+        if let Some(ref old) = old {
+            self.unindex(key, old);
+        }
+        old
+    }
+
+    /// Gets an iterator over the keys holding `value`, sorted.
+    pub fn keys_with_value(&self, value: &V) -> KeysWithValue<'_, K> {
+        KeysWithValue {
+            iter: self.index.get(value).map(|keys| keys.iter()),
+        }
+    }
+
+    /// Returns the number of keys holding `value`.
+    pub fn count_with_value(&self, value: &V) -> usize {
+        self.index.get(value).map_or(0, |keys| keys.len())
+    }
+
+    fn unindex(&mut self, key: &K, value: &V) {
+        let keys = self.index.get_mut(value).expect("values should be indexed");
+        keys.remove(key);
+        if keys.is_empty() {
+            self.index.remove(value);
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V: Eq + Hash + Clone> Default for ReverseIndexedMap<K, V> {
+    fn default() -> ReverseIndexedMap<K, V> {
+        ReverseIndexedMap::new()
+    }
+}
+
+/// An iterator over the keys holding a value, see [`keys_with_value`].
+///
+/// [`keys_with_value`]: struct.ReverseIndexedMap.html#method.keys_with_value
+pub struct KeysWithValue<'a, K: 'a + Key + ?Sized> {
+    iter: Option<SetIter<'a, K>>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized] KeysWithValue<'a, K>);
+
+impl<'a, K: 'a + Key + ?Sized> Iterator for KeysWithValue<'a, K> {
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<K::Owned> {
+        self.iter.as_mut()?.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.as_ref().map_or((0, Some(0)), |iter| iter.size_hint())
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized> ExactSizeIterator for KeysWithValue<'a, K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::ReverseIndexedMap;
+
+    #[test]
+    fn it_keeps_the_reverse_index_in_sync() {
+        let mut map: ReverseIndexedMap<str, u8> = ReverseIndexedMap::new();
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 1), None);
+        assert_eq!(map.insert("c", 2), None);
+        assert_eq!(map.insert("a", 1), Some(1));
+        assert_eq!(map.count_with_value(&1), 2);
+
+        assert_eq!(map.insert("b", 2), Some(1));
+        assert_eq!(map.keys_with_value(&1).collect::<Vec<_>>(), vec!["a"]);
+        assert_eq!(map.keys_with_value(&2).collect::<Vec<_>>(), vec!["b", "c"]);
+        assert_eq!(map.keys_with_value(&2).len(), 2);
+
+        assert_eq!(map.remove("a"), Some(1));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.keys_with_value(&1).count(), 0);
+        assert!(!map.index.contains_key(&1));
+
+        assert_eq!(map.remove("b"), Some(2));
+        assert_eq!(map.remove("c"), Some(2));
+        assert!(map.is_empty() && map.index.is_empty());
+    }
+}
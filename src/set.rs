@@ -1,22 +1,67 @@
+use std::fmt;
 use std::iter::FromIterator;
+use std::hash::{Hash, Hasher, BuildHasher};
+use std::collections::{BTreeSet, HashSet};
 
 use map::{
     RadixMap,
     Matches as MapMatches,
     Keys as MapKeys,
+    IntoIter as MapIntoIter,
+    MatchesWith as MapMatchesWith,
+    PrefixCursor,
+    DebugKey,
 };
 
-use key::Key;
+use key::{AsKey, Key, KeyComponent, KeyRendering};
+use compare::Compare;
+use stats::Stats;
+
+#[cfg(feature = "filter")]
+use filter::BloomFilter;
 
 /// A set based on a [Radix tree](https://en.wikipedia.org/wiki/Radix_tree).
 ///
 /// See [`RadixMap`](../map/struct.RadixMap.html) for an in-depth explanation of the workings of this
 /// struct, as it's simply a wrapper around `RadixMap<K, ()>`.
+///
+/// Since `()` is zero-sized, the only per-node cost of the values is the flag telling if a key
+/// ends at that node, which the tree needs regardless of the value type.
 pub struct RadixSet<K: Key + ?Sized> {
     map: RadixMap<K, ()>,
 }
 
+/// A set of strings, the most common kind of `RadixSet`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::StringRadixSet;
+///
+/// let words: StringRadixSet = vec!["radix", "radish", "tree"].into_iter().collect();
+/// assert_eq!(words.find("radi").collect::<Vec<_>>(), vec!["radish", "radix"]);
+/// ```
+pub type StringRadixSet = RadixSet<str>;
+
 impl<K: Key + ?Sized> RadixSet<K> {
+    /// An empty set, e.g. to initialize a `static` or a `const` without wrapping it in an
+    /// `Option`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// static NOTHING_BANNED: RadixSet<[u8]> = RadixSet::EMPTY;
+    ///
+    /// assert!(!NOTHING_BANNED.contains(&[127, 0, 0, 1]));
+    /// ```
+    pub const EMPTY: RadixSet<K> = RadixSet::new();
+
     /// Makes a new empty RadixSet.
     ///
     /// # Examples
@@ -31,10 +76,25 @@ impl<K: Key + ?Sized> RadixSet<K> {
     /// // entries can now be inserted into the empty set
     /// set.insert("a");
     /// ```
-    pub fn new() -> RadixSet<K> {
+    pub const fn new() -> RadixSet<K> {
         RadixSet { map: RadixMap::new() }
     }
 
+    /// Makes a new empty set, which keeps the last `capacity` keys inserted in it.
+    ///
+    /// See [`RadixMap::with_recent_inserts`](../map/struct.RadixMap.html#method.with_recent_inserts).
+    pub fn with_recent_inserts(capacity: usize) -> RadixSet<K> {
+        RadixSet { map: RadixMap::with_recent_inserts(capacity) }
+    }
+
+    /// Returns up to `k` of the last keys inserted in the set, the most recent first, if the set
+    /// keeps them.
+    ///
+    /// See [`RadixMap::sample_recent_inserts`](../map/struct.RadixMap.html#method.sample_recent_inserts).
+    pub fn sample_recent_inserts(&self, k: usize) -> Vec<K::Owned> {
+        self.map.sample_recent_inserts(k)
+    }
+
     /// Clears the set, removing all values.
     ///
     /// # Examples
@@ -108,10 +168,168 @@ impl<K: Key + ?Sized> RadixSet<K> {
     /// assert_eq!(set.contains("a"), true);
     /// assert_eq!(set.contains("b"), false);
     /// ```
-    pub fn contains(&self, key: &K) -> bool {
+    pub fn contains<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> bool {
         self.map.contains_key(key)
     }
 
+    /// Gets the key of the given rank, i.e. the `n`-th smallest key (starting at 0).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["b", "a", "c"].into_iter().collect();
+    /// assert_eq!(set.select(2), Some("c".to_string()));
+    /// ```
+    pub fn select(&self, n: usize) -> Option<K::Owned> {
+        self.map.select(n).map(|(key, _)| key)
+    }
+
+    /// Gets the key of the given rank among the keys starting with `prefix`.
+    pub fn select_in<Q: AsKey<K> + ?Sized>(&self, prefix: &Q, n: usize) -> Option<K::Owned> {
+        self.map.select_in(prefix, n).map(|(key, _)| key)
+    }
+
+    /// Gets the shortest key, the smallest one if there are several.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["user", "username", "users"].into_iter().collect();
+    /// assert_eq!(set.shortest_key(), Some("user".to_string()));
+    /// assert_eq!(set.longest_key(), Some("username".to_string()));
+    /// assert_eq!(set.shortest_key_in("users"), Some("users".to_string()));
+    /// ```
+    pub fn shortest_key(&self) -> Option<K::Owned> {
+        self.map.shortest_key().map(|(key, _)| key)
+    }
+
+    /// Gets the shortest key starting with `prefix`, the smallest one if there are several.
+    pub fn shortest_key_in<Q: AsKey<K> + ?Sized>(&self, prefix: &Q) -> Option<K::Owned> {
+        self.map.shortest_key_in(prefix).map(|(key, _)| key)
+    }
+
+    /// Gets the longest key, the smallest one if there are several.
+    pub fn longest_key(&self) -> Option<K::Owned> {
+        self.map.longest_key().map(|(key, _)| key)
+    }
+
+    /// Gets the longest key starting with `prefix`, the smallest one if there are several.
+    pub fn longest_key_in<Q: AsKey<K> + ?Sized>(&self, prefix: &Q) -> Option<K::Owned> {
+        self.map.longest_key_in(prefix).map(|(key, _)| key)
+    }
+
+    /// Gets the keys starting with `prefix` and exactly `len` components long, see
+    /// `RadixMap::find_len`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["crane", "crate", "crater", "cry"].into_iter().collect();
+    /// assert_eq!(set.find_len("cr", 5), vec!["crane", "crate"]);
+    /// ```
+    pub fn find_len<Q: AsKey<K> + ?Sized>(&self, prefix: &Q, len: usize) -> Vec<K::Owned> {
+        self.map.find_len(prefix, len).into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// Gets the key at the quantile `q` of the sorted keys, see `RadixMap::quantile_key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` isn't between 0 and 1.
+    pub fn quantile_key(&self, q: f64) -> Option<K::Owned> {
+        self.map.quantile_key(q)
+    }
+
+    /// Returns the number of keys sorting before `key`, whether it's in the set or not.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["b", "a", "c"].into_iter().collect();
+    /// assert_eq!(set.rank("bb"), 2);
+    /// ```
+    pub fn rank<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> usize {
+        self.map.rank(key)
+    }
+
+    /// Returns if each of the keys is present in the set, in the order of `keys`.
+    ///
+    /// The keys are looked up in sorted order, so that consecutive keys sharing a prefix only
+    /// descend the set below it once. Keys that are already sorted aren't sorted again, otherwise
+    /// sorting them may cost more than it saves, e.g. on keys sharing few prefixes.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["apple", "apricot", "banana"].into_iter().collect();
+    /// assert_eq!(set.contains_each(&["banana", "apple", "ap", "apricots"]), vec![true, true, false, false]);
+    /// ```
+    pub fn contains_each<Q: AsKey<K>>(&self, keys: &[Q]) -> Vec<bool> {
+        let keys: Vec<_> = keys.iter().map(|k| k.as_key().as_slice()).collect();
+        self.map.tree.contains_each(&keys)
+    }
+
+    /// Returns if all of the keys are present in the set, see [`contains_each`].
+    ///
+    /// [`contains_each`]: #method.contains_each
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["apple", "apricot", "banana"].into_iter().collect();
+    /// assert!(set.contains_all(&["apricot", "apple"]));
+    /// assert!(!set.contains_all(&["apple", "cherry"]));
+    /// ```
+    pub fn contains_all<Q: AsKey<K>>(&self, keys: &[Q]) -> bool {
+        self.contains_each(keys).into_iter().all(|found| found)
+    }
+
+    /// Returns if any key of the set is a prefix of `key` (including `key` itself), e.g. to
+    /// block paths under a denylist.
+    ///
+    /// The set is only descended once along `key`, stopping at the first key found.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let denied: RadixSet<str> = vec!["/admin/", "/internal"].into_iter().collect();
+    /// assert!(denied.is_prefix_of("/admin/users"));
+    /// assert!(denied.is_prefix_of("/internal"));
+    /// assert!(!denied.is_prefix_of("/adm"));
+    /// assert!(!denied.is_prefix_of("/public/admin/"));
+    /// ```
+    pub fn is_prefix_of<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> bool {
+        self.map.tree.ancestors(key.as_key().as_slice()).next().is_some()
+    }
+
     /// Returns `true` if the set contains no elements.
     ///
     /// # Examples
@@ -144,10 +362,60 @@ impl<K: Key + ?Sized> RadixSet<K> {
     /// assert_eq!(set.remove("a"), true);
     /// assert_eq!(set.remove("a"), false);
     /// ```
-    pub fn remove(&mut self, key: &K) -> bool {
+    pub fn remove<Q: AsKey<K> + ?Sized>(&mut self, key: &Q) -> bool {
         self.map.remove(key).is_some()
     }
 
+    /// Returns the minimal set of prefixes covering the keys of the set, i.e. its keys without
+    /// those starting with another key of the set, e.g. to compact a list of CIDR blocks or an
+    /// allowlist of paths.
+    ///
+    /// The cover is built in a single traversal, which doesn't descend below the keys kept.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let allowed: RadixSet<str> = vec!["/home/", "/home/alice/", "/tmp/", "/tmp/x", "/var/log/"].into_iter().collect();
+    /// let cover = allowed.minimal_cover();
+    /// assert_eq!(cover.iter().collect::<Vec<_>>(), vec!["/home/", "/tmp/", "/var/log/"]);
+    /// ```
+    pub fn minimal_cover(&self) -> RadixSet<K> {
+        RadixSet { map: RadixMap::from_tree(self.map.tree.minimal_cover()) }
+    }
+
+    /// Returns the minimal set of prefixes covering the keys starting with a key of the set, except
+    /// those starting with a key of `other`, e.g. to exclude CIDR blocks from others.
+    ///
+    /// Where a key of the set is only partially covered by `other`, it is split into its
+    /// extensions by each component of `alphabet`, as many times as needed to exclude the keys of
+    /// `other`: keys are assumed to be made of these components only, the keys of `other` using
+    /// other components are ignored.
+    ///
+    /// Both sets are traversed together, only descending below the keys of the set where the keys
+    /// of `other` do.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// // blocks of IPv4 addresses, as prefixes of bits
+    /// let blocks: RadixSet<str> = vec!["00001010"].into_iter().collect();
+    /// let excluded: RadixSet<str> = vec!["0000101011", "000010100000"].into_iter().collect();
+    ///
+    /// let cover = blocks.subtract_cover(&excluded, b"01");
+    /// assert_eq!(cover.iter().collect::<Vec<_>>(), vec!["000010100001", "00001010001", "0000101001", "0000101010"]);
+    /// ```
+    pub fn subtract_cover(&self, other: &RadixSet<K>, alphabet: &[K::Component]) -> RadixSet<K> {
+        RadixSet { map: RadixMap::from_tree(self.map.tree.subtract_cover(&other.map.tree, alphabet)) }
+    }
+
     /// Gets an iterator over the keys inserted (sorted).
     ///
     /// # Examples
@@ -169,8 +437,46 @@ impl<K: Key + ?Sized> RadixSet<K> {
     /// let first_key = set.iter().next().unwrap();
     /// assert_eq!(first_key, "a".to_string());
     /// ```
-    pub fn iter(&self) -> Iter<K> {
-        self.map.keys()
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            iter: self.map.keys(),
+        }
+    }
+
+    /// Collects the keys of the set into a `Vec`, sorted.
+    ///
+    /// The `Vec` is allocated once with the exact number of keys.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["b", "a"].into_iter().collect();
+    /// assert_eq!(set.to_sorted_vec(), vec!["a", "b"]);
+    /// ```
+    pub fn to_sorted_vec(&self) -> Vec<K::Owned> {
+        let mut keys = Vec::with_capacity(self.len());
+        keys.extend(self.iter());
+        keys
+    }
+
+    /// Moves the keys of the set into a `Vec`, sorted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["b", "a"].into_iter().collect();
+    /// assert_eq!(set.into_sorted_vec(), vec!["a", "b"]);
+    /// ```
+    pub fn into_sorted_vec(self) -> Vec<K::Owned> {
+        self.into_iter().collect()
     }
 
     /// Gets an iterator over a filtered subset of the set (sorted).
@@ -198,10 +504,260 @@ impl<K: Key + ?Sized> RadixSet<K> {
     /// let first_key = set.find("a").next().unwrap();
     /// assert_eq!(first_key, "abc".to_string());
     /// ```
-    pub fn find<'a>(&'a self, key: &K) -> Matches<'a, K> {
+    pub fn find<'a, Q: AsKey<K> + ?Sized>(&'a self, key: &Q) -> Matches<'a, K> {
+        let key = key.as_key();
         Matches {
             iter: self.map.find(key),
+            prefix: key.as_slice().to_vec(),
+        }
+    }
+
+    /// Gets the smallest key starting with `key`.
+    ///
+    /// This is equivalent to `find(key).next()`, but only descends the set once, without any
+    /// iteration state.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["status", "stash", "stage"].into_iter().collect();
+    /// assert_eq!(set.find_one("sta"), Some("stage".to_string()));
+    /// assert_eq!(set.find_one("x"), None);
+    /// ```
+    pub fn find_one<Q: AsKey<K> + ?Sized>(&self, key: &Q) -> Option<K::Owned> {
+        self.map.find_one(key).map(|(k, _)| k)
+    }
+
+    /// Starts validating a key as it is typed.
+    ///
+    /// See [`RadixMap::prefix_cursor`](../map/struct.RadixMap.html#method.prefix_cursor).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    /// use panoradix::map::PrefixState;
+    ///
+    /// let set: RadixSet<str> = vec!["go", "gone"].into_iter().collect();
+    /// let states: Vec<_> = {
+    ///     let mut cursor = set.prefix_cursor();
+    ///     "gon!".chars().map(|c| cursor.push_char(c)).collect()
+    /// };
+    /// assert_eq!(states, vec![PrefixState::Prefix, PrefixState::Key, PrefixState::Prefix, PrefixState::Dead]);
+    /// ```
+    pub fn prefix_cursor(&self) -> PrefixCursor<'_, K, ()> {
+        self.map.prefix_cursor()
+    }
+
+    /// Gets an iterator over the keys starting with `key`, compared with `compare` (sorted).
+    ///
+    /// See [`RadixMap::find_with`](../map/struct.RadixMap.html#method.find_with).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    /// use panoradix::compare::CompareMode;
+    ///
+    /// let set: RadixSet<str> = vec!["Foo", "foobar", "bar"].into_iter().collect();
+    /// assert_eq!(set.find_with("foo", CompareMode::IGNORE_CASE).collect::<Vec<_>>(), vec!["Foo", "foobar"]);
+    /// ```
+    pub fn find_with<'a, C: Compare<K>, Q>(&'a self, key: &Q, compare: C) -> MatchesWith<'a, K>
+        where Q: AsKey<K> + ?Sized,
+    {
+        MatchesWith {
+            iter: self.map.find_with(key, compare),
+        }
+    }
+
+    /// Counts the keys sharing each prefix of `prefix_len` components, sorted by prefix.
+    ///
+    /// See [`RadixMap::group_count`](../map/struct.RadixMap.html#method.group_count).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["abc", "abd", "bcd"].into_iter().collect();
+    /// assert_eq!(set.group_count(1), vec![("a".to_string(), 2), ("b".to_string(), 1)]);
+    /// ```
+    pub fn group_count(&self, prefix_len: usize) -> Vec<(K::Owned, usize)> {
+        self.map.group_count(prefix_len)
+    }
+
+    /// Writes an indented rendering of the tree backing the set, for debugging.
+    ///
+    /// See [`RadixMap::dump_structure`](../map/struct.RadixMap.html#method.dump_structure).
+    pub fn dump_structure<W: fmt::Write>(&self, w: &mut W) -> fmt::Result
+        where K::Component: fmt::Debug + 'static,
+    {
+        self.map.dump_structure(w)
+    }
+
+    /// Returns how the keys of the set are rendered in its debugging output.
+    pub fn key_rendering(&self) -> KeyRendering {
+        self.map.key_rendering()
+    }
+
+    /// Sets how the keys of the set are rendered in its debugging output, e.g. in hexadecimal
+    /// when they hold binary data.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::{RadixSet, KeyRendering};
+    ///
+    /// let mut set: RadixSet<[u8]> = vec![&[0xde, 0xad][..], &[0xde, 0x10]].into_iter().collect();
+    ///
+    /// let mut dump = String::new();
+    /// set.dump_structure(&mut dump).unwrap();
+    /// assert!(dump.contains("\"\\xde\" (2)"));
+    ///
+    /// set.set_key_rendering(KeyRendering::Hex);
+    /// assert_eq!(format!("{:?}", set), "{[de, 10], [de, ad]}");
+    /// ```
+    pub fn set_key_rendering(&mut self, rendering: KeyRendering) {
+        self.map.set_key_rendering(rendering);
+    }
+}
+
+impl RadixSet<str> {
+    /// Removes the keys for which `keep` returns `false`, walking the set once. Returns the
+    /// number of keys removed.
+    ///
+    /// See [`RadixMap::retain`](../map/struct.RadixMap.html#method.retain).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let mut set: RadixSet<str> = vec!["a", "abc", "b", "tmp/a"].into_iter().collect();
+    /// assert_eq!(set.retain(|key| key.len() == 1 || key.starts_with("tmp/")), 1);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), vec!["a", "b", "tmp/a"]);
+    /// ```
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+        where F: FnMut(&str) -> bool,
+    {
+        self.map.retain(|key, _| keep(key))
+    }
+}
+
+impl<T: KeyComponent> RadixSet<[T]> {
+    /// Removes the keys for which `keep` returns `false`, walking the set once. Returns the
+    /// number of keys removed.
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+        where F: FnMut(&[T]) -> bool,
+    {
+        self.map.retain(|key, _| keep(key))
+    }
+}
+
+#[cfg(feature = "filter")]
+impl<K: Key + ?Sized> RadixSet<K>
+    where K::Component: Hash,
+{
+    /// Builds a [Bloom filter](../filter/struct.BloomFilter.html) of the keys in the set, using
+    /// about `bits_per_key` bits for each of them (10 bits give roughly 1% false positives).
+    ///
+    /// Only available with the `filter` feature.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["foo", "bar"].into_iter().collect();
+    /// let filter = set.to_filter(10);
+    /// assert!(filter.contains("foo"));
+    /// assert!(filter.contains("bar"));
+    /// ```
+    pub fn to_filter(&self, bits_per_key: usize) -> BloomFilter<K> {
+        let mut filter = BloomFilter::with_capacity(self.len(), bits_per_key);
+        let mut it = self.map.raw_iter();
+        while let Some((key, _)) = it.next_entry() {
+            filter.insert_components(key);
+        }
+        filter
+    }
+}
+
+impl<K: Key + ?Sized> fmt::Debug for RadixSet<K>
+    where K::Component: fmt::Debug + 'static,
+{
+    /// Formats the keys of the set like std sets, sorted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<[u8]> = vec![&[2u8][..], &[1, 0]].into_iter().collect();
+    /// assert_eq!(format!("{:?}", set), "{[1, 0], [2]}");
+    ///
+    /// let set: RadixSet<str> = vec!["thé", "café"].into_iter().collect();
+    /// assert_eq!(format!("{:?}", set), r#"{"café", "thé"}"#);
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rendering = self.map.key_rendering();
+        let mut keys = f.debug_set();
+        let mut iter = self.map.tree.iter();
+        while let Some((key, _)) = iter.next_raw() {
+            keys.entry(&DebugKey::<K>::new(key, rendering));
         }
+        keys.finish()
+    }
+}
+
+impl<K: Key + ?Sized> PartialEq for RadixSet<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<K: Key + ?Sized> Eq for RadixSet<K> {}
+
+impl<K: Key + ?Sized> Hash for RadixSet<K>
+    where K::Component: Hash,
+{
+    /// Hashes the keys of the set, sorted, so that equal sets have the same hash.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use panoradix::RadixSet;
+    ///
+    /// let mut seen = HashSet::new();
+    /// for tags in &[&["b", "a"], &["a", "b"], &["a", "c"]] {
+    ///     let tags: RadixSet<str> = tags.iter().collect();
+    ///     seen.insert(tags);
+    /// }
+    /// assert_eq!(seen.len(), 2);
+    /// ```
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.map.hash(state);
     }
 }
 
@@ -220,14 +776,193 @@ impl<K: Key + ?Sized, T: AsRef<K>> FromIterator<T> for RadixSet<K> {
     }
 }
 
+impl<K: Key + ?Sized> IntoIterator for RadixSet<K> {
+    type Item = K::Owned;
+    type IntoIter = IntoIter<K>;
+
+    fn into_iter(self) -> IntoIter<K> {
+        IntoIter {
+            iter: self.map.into_iter(),
+        }
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized> IntoIterator for &'a RadixSet<K> {
+    type Item = K::Owned;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Iter<'a, K> {
+        self.iter()
+    }
+}
+
+impl<K: Key + ?Sized> Stats for RadixSet<K> {
+    fn insertions(&self) -> u64 {
+        self.map.insertions()
+    }
+
+    fn removals(&self) -> u64 {
+        self.map.removals()
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.map.node_count()
+    }
+}
+
+impl<K> From<BTreeSet<K::Owned>> for RadixSet<K>
+    where K: Key + ?Sized,
+          K::Owned: AsRef<K>,
+{
+    fn from(set: BTreeSet<K::Owned>) -> Self {
+        set.into_iter().collect()
+    }
+}
+
+impl<K, S> From<HashSet<K::Owned, S>> for RadixSet<K>
+    where K: Key + ?Sized,
+          K::Owned: AsRef<K>,
+{
+    fn from(set: HashSet<K::Owned, S>) -> Self {
+        set.into_iter().collect()
+    }
+}
+
+impl<K> From<RadixSet<K>> for BTreeSet<K::Owned>
+    where K: Key + ?Sized,
+          K::Owned: Ord,
+{
+    fn from(set: RadixSet<K>) -> Self {
+        set.into_sorted_vec().into_iter().collect()
+    }
+}
+
+impl<K, S> From<RadixSet<K>> for HashSet<K::Owned, S>
+    where K: Key + ?Sized,
+          K::Owned: Hash + Eq,
+          S: BuildHasher + Default,
+{
+    fn from(set: RadixSet<K>) -> Self {
+        set.into_sorted_vec().into_iter().collect()
+    }
+}
+
 /// An iterator over a `RadixSet`'s entries.
-pub type Iter<'a, K> = MapKeys<'a, K, ()>;
+pub struct Iter<'a, K: 'a + Key + ?Sized> {
+    iter: MapKeys<'a, K, ()>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized] Iter<'a, K>);
+
+impl<'a, K: 'a + Key + ?Sized> Iterator for Iter<'a, K> {
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized> ExactSizeIterator for Iter<'a, K> {}
+
+impl<'a> Iter<'a, str> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<&str> {
+        self.iter.next_ref()
+    }
+}
+
+impl<'a, T: 'a + KeyComponent> Iter<'a, [T]> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<&[T]> {
+        self.iter.next_ref()
+    }
+}
+
+/// An owning iterator over a `RadixSet`'s entries, sorted.
+pub struct IntoIter<K: Key + ?Sized> {
+    iter: MapIntoIter<K, ()>,
+}
+
+debug_iterator!(exact [K: Key + ?Sized] IntoIter<K>);
+
+impl<K: Key + ?Sized> Iterator for IntoIter<K> {
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K: Key + ?Sized> ExactSizeIterator for IntoIter<K> {}
 
 /// An iterator over the elements matching a call to [`find`].
 ///
 /// [`find`]: struct.RadixSet.html#method.find
 pub struct Matches<'a, K: 'a + Key + ?Sized> {
     iter: MapMatches<'a, K, ()>,
+    prefix: Vec<K::Component>,
+}
+
+debug_iterator!(exact ['a, K: 'a + Key + ?Sized] Matches<'a, K>);
+
+impl<'a, K: 'a + Key + ?Sized> Matches<'a, K> {
+    /// Returns the prefix the elements were searched for.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["apple", "apply", "banana"].into_iter().collect();
+    ///
+    /// let matches = set.find("app");
+    /// assert_eq!(matches.prefix(), "app");
+    /// assert_eq!(matches.len(), 2);
+    /// ```
+    pub fn prefix(&self) -> K::Owned {
+        K::from_vec(self.prefix.clone())
+    }
+
+    /// Returns the longest prefix shared by the elements, which may be longer than the prefix
+    /// they were searched for.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["apple", "apply", "banana"].into_iter().collect();
+    ///
+    /// let matches = set.find("a");
+    /// assert_eq!(matches.matched_prefix(), "appl");
+    /// assert_eq!(matches.total(), 2);
+    /// ```
+    pub fn matched_prefix(&self) -> K::Owned {
+        self.iter.matched_prefix()
+    }
+
+    /// Returns the number of elements, including those already iterated over (unlike `len()`).
+    pub fn total(&self) -> usize {
+        self.iter.total()
+    }
 }
 
 impl<'a, K: 'a + Key + ?Sized> Iterator for Matches<'a, K> {
@@ -236,6 +971,59 @@ impl<'a, K: 'a + Key + ?Sized> Iterator for Matches<'a, K> {
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(k, _)| k)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized> ExactSizeIterator for Matches<'a, K> {}
+
+impl<'a> Matches<'a, str> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixSet;
+    ///
+    /// let set: RadixSet<str> = vec!["ab", "abc"].into_iter().collect();
+    ///
+    /// let mut matches = set.find("a");
+    /// assert_eq!(matches.next_ref(), Some("ab"));
+    /// assert_eq!(matches.next_ref(), Some("abc"));
+    /// ```
+    pub fn next_ref(&mut self) -> Option<&str> {
+        self.iter.next_ref().map(|(k, _)| k)
+    }
+}
+
+impl<'a, T: 'a + KeyComponent> Matches<'a, [T]> {
+    /// Advances the iterator like `next()`, but the key is borrowed from a buffer of the iterator
+    /// (until the next call) instead of being allocated.
+    pub fn next_ref(&mut self) -> Option<&[T]> {
+        self.iter.next_ref().map(|(k, _)| k)
+    }
+}
+
+/// An iterator over the elements matching a call to [`find_with`].
+///
+/// [`find_with`]: struct.RadixSet.html#method.find_with
+pub struct MatchesWith<'a, K: 'a + Key + ?Sized> {
+    iter: MapMatchesWith<'a, K, ()>,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized] MatchesWith<'a, K>);
+
+impl<'a, K: 'a + Key + ?Sized> Iterator for MatchesWith<'a, K> {
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, _)| k)
+    }
 }
 
 #[cfg(test)]
@@ -275,7 +1063,7 @@ mod tests {
 
     #[test]
     fn it_can_be_built_from_multiple_elements() {
-        let items = vec!["a", "ac", "acb", "b", "c", "d"];
+        let items = ["a", "ac", "acb", "b", "c", "d"];
 
         let set: RadixSet<str> = items.iter().collect();
 
@@ -293,6 +1081,22 @@ mod tests {
         assert_eq!(keys, vec!["bar", "baz", "foo"]);
     }
 
+    #[test]
+    fn it_knows_how_many_keys_are_left() {
+        let set: RadixSet<str> = RadixSet::from_iter(vec!["a", "ab", "abc", "b"]);
+
+        let mut it = set.iter();
+        assert_eq!(it.len(), 4);
+        it.next();
+        assert_eq!(it.size_hint(), (3, Some(3)));
+
+        let mut matches = set.find("ab");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches.next_ref(), Some("ab"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(set.find("c").len(), 0);
+    }
+
     #[test]
     fn it_can_complete_keys() {
         let v = vec!["foo", "bar", "baz"];
@@ -301,6 +1105,36 @@ mod tests {
         assert_eq!(set.find("ba").collect::<Vec<_>>(), vec!["bar", "baz"]);
     }
 
+    #[test]
+    fn it_checks_for_keys_prefixing_a_key() {
+        let mut set = RadixSet::<[u8]>::new();
+        assert!(!set.is_prefix_of(&[]));
+
+        set.insert(&[1, 2]);
+        set.insert(&[1, 2, 3, 4]);
+        assert!(set.is_prefix_of(&[1, 2]));
+        assert!(set.is_prefix_of(&[1, 2, 3]));
+        assert!(!set.is_prefix_of(&[1]));
+        assert!(!set.is_prefix_of(&[1, 3, 2]));
+
+        set.insert(&[]);
+        assert!(set.is_prefix_of(&[5]));
+    }
+
+    #[test]
+    fn it_converts_from_and_into_std_sets() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let btree: BTreeSet<String> = vec!["b".to_string(), "a".to_string()].into_iter().collect();
+        let set: RadixSet<str> = btree.clone().into();
+        assert!(set.contains("a") && set.contains("b"));
+        assert_eq!(BTreeSet::from(set), btree);
+
+        let hash: HashSet<Vec<u8>> = vec![vec![1, 2], vec![3]].into_iter().collect();
+        let set = RadixSet::<[u8]>::from(hash.clone());
+        assert_eq!(HashSet::from(set), hash);
+    }
+
     #[test]
     fn it_can_remove_keys() {
         let v = vec!["foo", "bar", "baz"];
@@ -310,4 +1144,70 @@ mod tests {
         assert!(!set.contains("bar"));
         assert!(set.contains("baz"));
     }
+
+    #[test]
+    fn it_retains_keys() {
+        let mut set: RadixSet<[u8]> = (0..100u8).map(|i| vec![i / 10, i % 10]).collect();
+        assert_eq!(set.retain(|key| key[0] == key[1]), 90);
+        assert_eq!(set.len(), 10);
+        assert!(set.iter().all(|key| key[0] == key[1]));
+        assert_eq!(set.retain(|_| false), 10);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn it_moves_keys_out() {
+        let set: RadixSet<[u8]> = vec![&b"yz"[..], b"x", b"y"].into_iter().collect();
+        assert_eq!((&set).into_iter().count(), 3);
+
+        let mut keys = Vec::new();
+        for key in set {
+            keys.push(key);
+        }
+        assert_eq!(keys, vec![b"x".to_vec(), b"y".to_vec(), b"yz".to_vec()]);
+    }
+
+    #[test]
+    fn it_reduces_keys_to_a_minimal_cover() {
+        let set: RadixSet<[u8]> = vec![&b"10"[..], b"101", b"1011", b"11", b"0", b"00", b"01"].into_iter().collect();
+        let cover = set.minimal_cover();
+        assert_eq!(cover.iter().collect::<Vec<_>>(), vec![b"0".to_vec(), b"10".to_vec(), b"11".to_vec()]);
+        assert_eq!(cover.map.tree.check(), Ok(()));
+
+        // every key is covered by exactly one prefix of the cover
+        for key in set.iter() {
+            assert_eq!(cover.iter().filter(|prefix| key.starts_with(prefix)).count(), 1);
+        }
+
+        assert!(RadixSet::<str>::new().minimal_cover().is_empty());
+        let everything: RadixSet<str> = vec!["", "a", "b"].into_iter().collect();
+        assert_eq!(everything.minimal_cover().iter().collect::<Vec<_>>(), vec![""]);
+    }
+
+    #[test]
+    fn it_subtracts_covers() {
+        let set = |keys: &[&str]| -> RadixSet<str> { keys.iter().collect() };
+
+        let blocks = set(&["0", "10", "1011", "11"]);
+        let cover = blocks.subtract_cover(&set(&["01", "1010", "111", "2"]), b"01");
+        assert_eq!(cover.iter().collect::<Vec<_>>(), vec!["00", "100", "1011", "110"]);
+        assert_eq!(cover.map.tree.check(), Ok(()));
+
+        // a key covering a whole block, or one of its prefixes, excludes it
+        assert!(blocks.subtract_cover(&set(&[""]), b"01").is_empty());
+        assert_eq!(blocks.subtract_cover(&set(&["1"]), b"01").iter().collect::<Vec<_>>(), vec!["0"]);
+
+        // nothing to exclude leaves the minimal cover
+        let cover = blocks.subtract_cover(&set(&[]), b"01");
+        assert_eq!(cover.iter().collect::<Vec<_>>(), blocks.minimal_cover().iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_checks_owned_keys_at_once() {
+        let set: RadixSet<str> = vec!["apple", "apricot"].into_iter().collect();
+        let keys = vec![String::from("apricot"), String::from("ap")];
+        assert_eq!(set.contains_each(&keys), vec![true, false]);
+        assert!(set.contains_all(&keys[..1]));
+        assert!(set.is_prefix_of(&String::from("apple pie")));
+    }
 }
@@ -0,0 +1,229 @@
+use map::{RadixMap, RawIter};
+use key::Key;
+
+/// A map stamping each entry with the generation of the map when it was last inserted or
+/// updated, so that the entries modified since a given generation can be exported incrementally.
+///
+/// The generation of the map is bumped by every insertion, update and removal. Removed entries
+/// leave a stamped tombstone behind, so that [`entries_modified_since`] reports them too, until
+/// they are dropped by [`purge_removed`].
+///
+/// [`entries_modified_since`]: #method.entries_modified_since
+/// [`purge_removed`]: #method.purge_removed
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::stamped::StampedRadixMap;
+///
+/// let mut prices = StampedRadixMap::new();
+/// prices.insert("apple", 3);
+/// prices.insert("pear", 4);
+///
+/// let exported = prices.generation();
+/// prices.insert("plum", 2);
+/// prices.remove("pear");
+/// *prices.get_mut("apple").unwrap() += 1;
+///
+/// let changes: Vec<_> = prices.entries_modified_since(exported).collect();
+/// assert_eq!(changes, vec![
+///     ("apple".to_string(), Some(&4)),
+///     ("pear".to_string(), None),
+///     ("plum".to_string(), Some(&2)),
+/// ]);
+/// ```
+pub struct StampedRadixMap<K: Key + ?Sized, V> {
+    map: RadixMap<K, Entry<V>>,
+    generation: u64,
+    // the number of entries which aren't tombstones
+    len: usize,
+}
+
+struct Entry<V> {
+    // `None` for the tombstone of a removed entry
+    value: Option<V>,
+    stamp: u64,
+}
+
+impl<K: Key + ?Sized, V> StampedRadixMap<K, V> {
+    /// Makes a new empty map, at generation 0.
+    pub fn new() -> StampedRadixMap<K, V> {
+        StampedRadixMap {
+            map: RadixMap::new(),
+            generation: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the current generation of the map, i.e. the number of changes made to it so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns a reference to the value of a key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)?.value.as_ref()
+    }
+
+    /// Returns the generation at which a key was last inserted, updated or removed.
+    ///
+    /// The stamp of a removed key is kept until it is purged by [`purge_removed`].
+    ///
+    /// [`purge_removed`]: #method.purge_removed
+    pub fn stamp(&self, key: &K) -> Option<u64> {
+        self.map.get(key).map(|e| e.stamp)
+    }
+
+    /// Returns a mutable reference to the value of a key, stamping it as modified whether or not
+    /// it is changed through the reference.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let entry = self.map.get_mut(key)?;
+        let value = entry.value.as_mut()?;
+        self.generation += 1;
+        entry.stamp = self.generation;
+        Some(value)
+    }
+
+    /// Inserts a key-value pair into the map, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        self.generation += 1;
+        let entry = Entry { value: Some(value), stamp: self.generation };
+        let old = self.map.insert(key, entry).and_then(|e| e.value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Removes a key from the map, returning its value.
+    ///
+    /// The key is left as a tombstone stamped with the new generation.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.map.get_mut(key)?;
+        let removed = entry.value.take()?;
+        self.generation += 1;
+        entry.stamp = self.generation;
+        self.len -= 1;
+        Some(removed)
+    }
+
+    /// Gets an iterator over the entries inserted, updated or removed after `generation`, sorted
+    /// by key. Removed entries are reported with no value.
+    ///
+    /// Every entry of the map is checked, the stamps aren't indexed, but only the keys of the
+    /// reported entries are allocated.
+    pub fn entries_modified_since(&self, generation: u64) -> ModifiedSince<'_, K, V> {
+        ModifiedSince {
+            iter: self.map.raw_iter(),
+            generation,
+        }
+    }
+
+    /// Drops the tombstones of the entries removed up to `generation` included, e.g. once the
+    /// changes up to this generation have been exported. Returns the number of tombstones dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::stamped::StampedRadixMap;
+    ///
+    /// let mut map = StampedRadixMap::new();
+    /// map.insert("a", 1);
+    /// map.remove("a");
+    /// assert_eq!(map.entries_modified_since(0).count(), 1);
+    ///
+    /// let exported = map.generation();
+    /// assert_eq!(map.purge_removed(exported), 1);
+    /// assert_eq!(map.entries_modified_since(0).count(), 0);
+    /// ```
+    pub fn purge_removed(&mut self, generation: u64) -> usize {
+        self.map.retain_and_prune(&[], |_, e| e.value.is_some() || e.stamp > generation)
+    }
+}
+
+/// An iterator over the entries of a [`StampedRadixMap`] modified since a generation, obtained
+/// through [`entries_modified_since`].
+///
+/// [`StampedRadixMap`]: struct.StampedRadixMap.html
+/// [`entries_modified_since`]: struct.StampedRadixMap.html#method.entries_modified_since
+pub struct ModifiedSince<'a, K: 'a + Key + ?Sized, V: 'a> {
+    iter: RawIter<'a, K, Entry<V>>,
+    generation: u64,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] ModifiedSince<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for ModifiedSince<'a, K, V> {
+    type Item = (K::Owned, Option<&'a V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, entry)) = self.iter.next_entry() {
+            if entry.stamp > self.generation {
+                return Some((K::from_vec(key.to_vec()), entry.value.as_ref()));
+            }
+        }
+        None
+    }
+}
+
+impl<K: Key + ?Sized, V> Default for StampedRadixMap<K, V> {
+    fn default() -> StampedRadixMap<K, V> {
+        StampedRadixMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StampedRadixMap;
+
+    #[test]
+    fn it_stamps_modified_entries() {
+        let mut map: StampedRadixMap<str, u32> = StampedRadixMap::new();
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.generation(), 2);
+        assert_eq!(map.stamp("b"), Some(2));
+
+        assert_eq!(map.insert("a", 10), Some(1));
+        assert_eq!(map.stamp("a"), Some(3));
+        assert_eq!(map.remove("b"), Some(2));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.generation(), 4);
+
+        assert!(map.get_mut("b").is_none());
+        assert!(map.get_mut("c").is_none());
+        assert_eq!(map.generation(), 4);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.stamp("b"), Some(4));
+        assert_eq!(map.entries_modified_since(2).collect::<Vec<_>>(), vec![
+            ("a".to_string(), Some(&10)),
+            ("b".to_string(), None),
+        ]);
+        assert_eq!(map.entries_modified_since(3).collect::<Vec<_>>(), vec![("b".to_string(), None)]);
+        assert_eq!(map.entries_modified_since(4).count(), 0);
+
+        // a removed key can be inserted again
+        assert_eq!(map.insert("b", 3), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.remove("b"), Some(3));
+        assert_eq!(map.purge_removed(5), 0);
+        assert_eq!(map.purge_removed(6), 1);
+        assert_eq!(map.stamp("b"), None);
+        assert_eq!(map.entries_modified_since(0).count(), map.len());
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::{Duration, Instant};
+
+use map::RadixMap;
+use key::Key;
+
+/// Statistics on a map or a set, for monitoring, e.g. by a Prometheus exporter.
+///
+/// The counters are kept by the tree as it is modified, so they cover all the mutations of the
+/// map without wrapping them. The trait is object safe, so that maps of different types can be
+/// monitored together as `&dyn Stats`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::{RadixMap, RadixSet};
+/// use panoradix::stats::Stats;
+///
+/// let mut users: RadixMap<str, u32> = RadixMap::new();
+/// let mut banned: RadixSet<[u8]> = RadixSet::new();
+///
+/// let before = [users.snapshot(), banned.snapshot()];
+/// users.insert("alice", 1);
+/// users.insert("bob", 2);
+/// users.insert("bob", 3);
+/// users.remove("alice");
+/// banned.insert(&[10, 0, 0, 1]);
+///
+/// let monitored: [(&str, &dyn Stats); 2] = [("users", &users), ("banned", &banned)];
+/// for (&(name, stats), before) in monitored.iter().zip(&before) {
+///     let now = stats.snapshot();
+///     println!("{}_len {}", name, now.len());
+///     println!("{}_insertions_per_sec {}", name, now.insertions_per_sec(before));
+/// }
+///
+/// assert_eq!(users.insertions(), 2);
+/// assert_eq!(users.removals(), 1);
+/// assert_eq!(Stats::len(&users), 1);
+/// ```
+pub trait Stats {
+    /// Returns the number of keys added so far, replacing the value of a key isn't counted.
+    fn insertions(&self) -> u64;
+
+    /// Returns the number of keys removed so far.
+    fn removals(&self) -> u64;
+
+    /// Returns the number of keys.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no keys.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of nodes of the tree, which walks all of them.
+    fn node_count(&self) -> usize;
+
+    /// Takes a snapshot of the statistics, to compute rates against a later one.
+    ///
+    /// The snapshot only holds the counters kept by the tree, so it costs nothing whatever the
+    /// size of the map: it leaves out the number of nodes, see [`node_count`].
+    ///
+    /// [`node_count`]: #tymethod.node_count
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            taken_at: Instant::now(),
+            insertions: self.insertions(),
+            removals: self.removals(),
+            len: self.len(),
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V> Stats for RadixMap<K, V> {
+    fn insertions(&self) -> u64 {
+        self.tree.insertions()
+    }
+
+    fn removals(&self) -> u64 {
+        self.tree.removals()
+    }
+
+    fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.tree.node_count()
+    }
+}
+
+/// The statistics of a map at some point in time, see [`Stats::snapshot`].
+///
+/// [`Stats::snapshot`]: trait.Stats.html#method.snapshot
+#[derive(Clone, Copy, Debug)]
+pub struct Snapshot {
+    taken_at: Instant,
+    insertions: u64,
+    removals: u64,
+    len: usize,
+}
+
+impl Snapshot {
+    /// Returns when the snapshot was taken.
+    pub fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+
+    /// Returns the number of keys added until the snapshot.
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
+    /// Returns the number of keys removed until the snapshot.
+    pub fn removals(&self) -> u64 {
+        self.removals
+    }
+
+    /// Returns the number of keys at the time of the snapshot.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there were no keys at the time of the snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of keys added per second since an `earlier` snapshot of the same map.
+    pub fn insertions_per_sec(&self, earlier: &Snapshot) -> f64 {
+        per_sec(self.insertions.saturating_sub(earlier.insertions), self.elapsed_since(earlier))
+    }
+
+    /// Returns the number of keys removed per second since an `earlier` snapshot of the same map.
+    pub fn removals_per_sec(&self, earlier: &Snapshot) -> f64 {
+        per_sec(self.removals.saturating_sub(earlier.removals), self.elapsed_since(earlier))
+    }
+
+    fn elapsed_since(&self, earlier: &Snapshot) -> Duration {
+        self.taken_at.saturating_duration_since(earlier.taken_at)
+    }
+}
+
+fn per_sec(count: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Stats;
+    use {RadixMap, RadixSet};
+
+    #[test]
+    fn it_counts_mutations_of_all_kinds() {
+        let mut map: RadixMap<str, u32> = (0..10u32).map(|i| (format!("k{}", i), i)).collect();
+        assert_eq!((map.insertions(), map.removals()), (10, 0));
+
+        map.insert("k1", 10);
+        map.remove("k2");
+        map.remove("k2");
+        assert_eq!((map.insertions(), map.removals()), (10, 1));
+
+        map.retain(|_, &mut v| v != 3);
+        map.rollup_prefix("k", |a, b| a + b);
+        assert_eq!((map.insertions(), map.removals()), (11, 10));
+        assert_eq!(Stats::len(&map), 1);
+        assert_eq!(map.node_count(), 2);
+
+        let mut other: RadixMap<str, u32> = vec![("a", 1), ("k", 2)].into_iter().collect();
+        map.append(&mut other);
+        assert_eq!((map.insertions(), map.removals()), (12, 10));
+        assert_eq!((other.insertions(), other.removals()), (2, 2));
+
+        let removed = map.remove_prefix("a");
+        assert_eq!((removed.insertions(), removed.removals()), (1, 0));
+        map.clear();
+        assert_eq!(map.insertions() - map.removals(), 0);
+
+        let set: RadixSet<str> = vec!["a", "ab", "b"].into_iter().collect();
+        assert_eq!((set.insertions(), set.node_count()), (3, 4));
+    }
+
+    #[test]
+    fn it_computes_rates_between_snapshots() {
+        let mut map: RadixMap<str, ()> = RadixMap::new();
+        let before = map.snapshot();
+        assert_eq!(before.insertions_per_sec(&before), 0.0);
+
+        for key in &["a", "b", "c", "d"] {
+            map.insert(key, ());
+        }
+        map.remove("a");
+        ::std::thread::sleep(Duration::from_millis(10));
+
+        let after = map.snapshot();
+        assert_eq!((after.len(), after.insertions(), after.removals()), (3, 4, 1));
+        assert!(after.insertions_per_sec(&before) > 0.0);
+        assert!(after.insertions_per_sec(&before) > after.removals_per_sec(&before));
+        assert!(after.insertions_per_sec(&before) <= 4.0 / 0.01);
+    }
+}
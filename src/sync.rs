@@ -0,0 +1,709 @@
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+use map::RadixMap;
+use key::Key;
+#[cfg(feature = "metrics")]
+use metrics::{LockCounters, LockMetrics, SharedMetrics};
+
+/// Creates a new empty map shared between a single writer and any number of readers.
+///
+/// The map is kept in two copies: readers always look at the *published* copy, while the writer
+/// mutates the other one and records its operations in a log. Calling [`publish`] swaps both
+/// copies and replays the log on the copy that was just retired, so readers don't wait on the
+/// writer (see [`ReadHandle::read`] for the race with a publish).
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use panoradix::sync;
+///
+/// let (mut w, r) = sync::new::<str, i32>();
+/// w.insert("a", 1);
+///
+/// // writes aren't visible until they are published
+/// assert_eq!(r.read().get("a"), None);
+///
+/// w.publish();
+/// assert_eq!(r.read().get("a"), Some(&1));
+/// ```
+///
+/// With the `metrics` feature, both handles count how often readers and the writer wait on each
+/// other, see [`ReadHandle::metrics`].
+///
+/// Like the other maps of this module, a map isn't poisoned by a thread panicking while using it:
+/// the other threads keep using the map as it was left.
+///
+/// [`publish`]: struct.WriteHandle.html#method.publish
+/// [`ReadHandle::metrics`]: struct.ReadHandle.html#method.metrics
+/// [`ReadHandle::read`]: struct.ReadHandle.html#method.read
+pub fn new<K: Key + ?Sized, V: Clone>() -> (WriteHandle<K, V>, ReadHandle<K, V>) {
+    let shared = Arc::new(Shared {
+        maps: [RwLock::new(RadixMap::new()), RwLock::new(RadixMap::new())],
+        active: AtomicUsize::new(0),
+        #[cfg(feature = "metrics")]
+        readers: LockCounters::new(),
+        #[cfg(feature = "metrics")]
+        writer: LockCounters::new(),
+    });
+
+    let w = WriteHandle {
+        shared: shared.clone(),
+        log: Vec::new(),
+    };
+    let r = ReadHandle { shared };
+
+    (w, r)
+}
+
+struct Shared<K: Key + ?Sized, V> {
+    maps: [RwLock<RadixMap<K, V>>; 2],
+    active: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    readers: LockCounters,
+    #[cfg(feature = "metrics")]
+    writer: LockCounters,
+}
+
+impl<K: Key + ?Sized, V> Shared<K, V> {
+    /// Lock a copy of the map for writing, waiting for the readers still looking at it.
+    #[cfg(not(feature = "metrics"))]
+    fn write(&self, i: usize) -> RwLockWriteGuard<'_, RadixMap<K, V>> {
+        self.maps[i].write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn write(&self, i: usize) -> RwLockWriteGuard<'_, RadixMap<K, V>> {
+        let start = Instant::now();
+        let guard = match self.maps[i].try_write() {
+            Ok(guard) => guard,
+            Err(_)    => {
+                self.writer.contended();
+                self.maps[i].write().unwrap_or_else(PoisonError::into_inner)
+            }
+        };
+        self.writer.acquired(start);
+        guard
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> SharedMetrics {
+        SharedMetrics {
+            readers: self.readers.snapshot(),
+            writer: self.writer.snapshot(),
+        }
+    }
+}
+
+enum Op<K, V> {
+    Insert(Vec<K>, V),
+    Remove(Vec<K>),
+    Clear,
+}
+
+/// The writing half of a map created by [`sync::new`].
+///
+/// [`sync::new`]: fn.new.html
+pub struct WriteHandle<K: Key + ?Sized, V: Clone> {
+    shared: Arc<Shared<K, V>>,
+    log: Vec<Op<K::Component, V>>,
+}
+
+impl<K: Key + ?Sized, V: Clone> WriteHandle<K, V> {
+    /// Inserts a key-value pair into the map, it will be visible to readers on the next call to
+    /// [`publish`].
+    ///
+    /// Returns the value previously stored at this key in the writer's copy.
+    ///
+    /// [`publish`]: #method.publish
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        let key = key.as_slice();
+        self.log.push(Op::Insert(key.to_owned(), value.clone()));
+        self.with_pending(|map| map.insert_components(key, value))
+    }
+
+    /// Removes a key from the map, it will be visible to readers on the next call to
+    /// [`publish`].
+    ///
+    /// Returns the value previously stored at this key in the writer's copy.
+    ///
+    /// [`publish`]: #method.publish
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let key = key.as_slice();
+        self.log.push(Op::Remove(key.to_owned()));
+        self.with_pending(|map| map.remove_components(key))
+    }
+
+    /// Clears the map, it will be visible to readers on the next call to [`publish`].
+    ///
+    /// [`publish`]: #method.publish
+    pub fn clear(&mut self) {
+        self.log.push(Op::Clear);
+        self.with_pending(|map| map.clear());
+    }
+
+    /// Returns the number of operations waiting to be published.
+    pub fn pending(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Makes all the operations since the last call visible to readers.
+    ///
+    /// Readers that are still looking at the previously published copy are waited for before
+    /// replaying the operations on it, readers are never blocked by this call.
+    pub fn publish(&mut self) {
+        if self.log.is_empty() {
+            return;
+        }
+
+        let retired = self.shared.active.fetch_xor(1, Ordering::SeqCst);
+
+        let mut map = self.shared.write(retired);
+        for op in self.log.drain(..) {
+            match op {
+                Op::Insert(key, value) => { map.insert_components(&key, value); },
+                Op::Remove(key)        => { map.remove_components(&key); },
+                Op::Clear              => { map.clear(); },
+            }
+        }
+    }
+
+    fn with_pending<T, F>(&self, f: F) -> T
+        where F: FnOnce(&mut RadixMap<K, V>) -> T,
+    {
+        let pending = self.shared.active.load(Ordering::SeqCst) ^ 1;
+        let mut map = self.shared.write(pending);
+        f(&mut map)
+    }
+
+    /// Returns the metrics of the locks of the map, shared with the readers.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> SharedMetrics {
+        self.shared.metrics()
+    }
+}
+
+/// The reading half of a map created by [`sync::new`], cheap to clone and share between threads.
+///
+/// [`sync::new`]: fn.new.html
+pub struct ReadHandle<K: Key + ?Sized, V> {
+    shared: Arc<Shared<K, V>>,
+}
+
+impl<K: Key + ?Sized, V> ReadHandle<K, V> {
+    /// Gets a view of the last published version of the map.
+    ///
+    /// This doesn't block on the writer: if the writer is replaying its operations on a copy, the
+    /// other copy is the published one. A reader racing with [`publish`] may find the copy it
+    /// picked retired (or already locked by the writer) though, it then yields to the other
+    /// threads and tries again with the newly published copy, which only the next publish can
+    /// take away.
+    ///
+    /// [`publish`]: struct.WriteHandle.html#method.publish
+    pub fn read(&self) -> ReadGuard<'_, K, V> {
+        #[cfg(feature = "metrics")]
+        let (start, mut contended) = (Instant::now(), false);
+
+        loop {
+            let active = self.shared.active.load(Ordering::SeqCst);
+            let guard = match self.shared.maps[active].try_read() {
+                Ok(guard)                      => Some(guard),
+                Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+                Err(TryLockError::WouldBlock)  => None,
+            };
+            if let Some(guard) = guard {
+                // the copy may have been retired between the load and the lock, in which case the
+                // writer is about to replay its log on it: let it go
+                if self.shared.active.load(Ordering::SeqCst) == active {
+                    #[cfg(feature = "metrics")]
+                    self.shared.readers.acquired(start);
+                    return ReadGuard { guard: Guard::Published(guard) };
+                }
+            }
+
+            #[cfg(feature = "metrics")]
+            {
+                if !contended {
+                    self.shared.readers.contended();
+                }
+                contended = true;
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// Returns the metrics of the locks of the map, shared with the writer.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::sync;
+    ///
+    /// let (mut w, r) = sync::new::<str, i32>();
+    /// w.insert("a", 1);
+    /// w.publish();
+    /// assert_eq!(r.read().get("a"), Some(&1));
+    ///
+    /// let metrics = r.metrics();
+    /// assert_eq!(metrics.readers().acquisitions(), 1);
+    /// assert_eq!(metrics.writer().acquisitions(), 2);
+    /// assert_eq!(metrics.writer().contended(), 0);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> SharedMetrics {
+        self.shared.metrics()
+    }
+}
+
+impl<K: Key + ?Sized, V> Clone for ReadHandle<K, V> {
+    fn clone(&self) -> Self {
+        ReadHandle { shared: self.shared.clone() }
+    }
+}
+
+/// A view of a shared map, obtained through [`ReadHandle::read`] or [`PrefixLockMap::read`].
+///
+/// [`ReadHandle::read`]: struct.ReadHandle.html#method.read
+/// [`PrefixLockMap::read`]: struct.PrefixLockMap.html#method.read
+pub struct ReadGuard<'a, K: 'a + Key + ?Sized, V: 'a> {
+    guard: Guard<'a, K, V>,
+}
+
+enum Guard<'a, K: 'a + Key + ?Sized, V: 'a> {
+    Published(RwLockReadGuard<'a, RadixMap<K, V>>),
+    Unlocked(MutexGuard<'a, PrefixLocks<K, V>>),
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Deref for ReadGuard<'a, K, V> {
+    type Target = RadixMap<K, V>;
+
+    fn deref(&self) -> &RadixMap<K, V> {
+        match self.guard {
+            Guard::Published(ref guard) => guard,
+            Guard::Unlocked(ref state)  => &state.map,
+        }
+    }
+}
+
+/// A map written by several threads at once, each locking the entries under a prefix, e.g. the
+/// namespace of a tenant, instead of the whole map.
+///
+/// Locking a prefix waits for the guards of overlapping prefixes (one starting with the other) to
+/// be dropped, then detaches the subtree of the prefix from the map: the guard owns its entries,
+/// and writes to them without any further locking. Dropping the guard grafts the subtree back.
+/// Both cost a descent of the tree, under a lock shared by all the prefixes.
+///
+/// Entries under a locked prefix are only visible through its guard, the other ones through
+/// [`read`]. A thread panicking while holding a guard still gives its entries back.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use std::thread;
+/// use panoradix::sync::PrefixLockMap;
+///
+/// let map = PrefixLockMap::<str, usize>::new();
+/// thread::scope(|s| {
+///     for tenant in &["acme/", "globex/", "initech/"] {
+///         let map = &map;
+///         s.spawn(move || {
+///             let mut entries = map.lock_prefix(tenant);
+///             for i in 0..10 {
+///                 entries.insert(&format!("{}{}", tenant, i), i);
+///             }
+///         });
+///     }
+/// });
+///
+/// let map = map.into_inner();
+/// assert_eq!(map.len(), 30);
+/// assert_eq!(map.find("globex/").count(), 10);
+/// ```
+///
+/// [`read`]: #method.read
+pub struct PrefixLockMap<K: Key + ?Sized, V> {
+    state: Mutex<PrefixLocks<K, V>>,
+    released: Condvar,
+    #[cfg(feature = "metrics")]
+    locks: LockCounters,
+}
+
+struct PrefixLocks<K: Key + ?Sized, V> {
+    // the entries outside of the locked prefixes
+    map: RadixMap<K, V>,
+    locked: Vec<Vec<K::Component>>,
+}
+
+impl<K: Key + ?Sized, V> PrefixLocks<K, V> {
+    fn overlaps(&self, prefix: &[K::Component]) -> bool {
+        self.locked.iter().any(|p| p.starts_with(prefix) || prefix.starts_with(p))
+    }
+}
+
+impl<K: Key + ?Sized, V> PrefixLockMap<K, V> {
+    /// Makes a new empty map.
+    pub fn new() -> PrefixLockMap<K, V> {
+        PrefixLockMap::from(RadixMap::new())
+    }
+
+    /// Locks the entries whose key starts with `prefix`, waiting for the guards of overlapping
+    /// prefixes to be dropped.
+    pub fn lock_prefix(&self, prefix: &K) -> PrefixGuard<'_, K, V> {
+        #[cfg(feature = "metrics")]
+        let (start, mut contended) = (Instant::now(), false);
+
+        let mut state = self.state();
+        while state.overlaps(prefix.as_slice()) {
+            #[cfg(feature = "metrics")]
+            {
+                if !contended {
+                    self.locks.contended();
+                }
+                contended = true;
+            }
+            state = self.released.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+
+        #[cfg(feature = "metrics")]
+        self.locks.acquired(start);
+        self.guard(state, prefix)
+    }
+
+    /// Locks the entries whose key starts with `prefix`, or returns `None` if an overlapping
+    /// prefix is locked.
+    pub fn try_lock_prefix(&self, prefix: &K) -> Option<PrefixGuard<'_, K, V>> {
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
+
+        let state = self.state();
+        if state.overlaps(prefix.as_slice()) {
+            #[cfg(feature = "metrics")]
+            self.locks.contended();
+            return None;
+        }
+
+        #[cfg(feature = "metrics")]
+        self.locks.acquired(start);
+        Some(self.guard(state, prefix))
+    }
+
+    /// Gets a view of the entries outside of the locked prefixes.
+    ///
+    /// The view holds the lock shared by all the prefixes, so locking a prefix or dropping a
+    /// guard waits for it to be dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use panoradix::RadixMap;
+    /// use panoradix::sync::PrefixLockMap;
+    ///
+    /// let map: RadixMap<str, i32> = vec![("a/x", 1), ("b/x", 2)].into_iter().collect();
+    /// let map = PrefixLockMap::from(map);
+    ///
+    /// let a = map.lock_prefix("a/");
+    /// assert_eq!(map.read().keys().collect::<Vec<_>>(), vec!["b/x"]);
+    /// drop(a);
+    /// assert_eq!(map.read().len(), 2);
+    /// ```
+    pub fn read(&self) -> ReadGuard<'_, K, V> {
+        ReadGuard { guard: Guard::Unlocked(self.state()) }
+    }
+
+    /// Returns the metrics of the locks of the prefixes, counting the waits on overlapping
+    /// prefixes as contended.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> LockMetrics {
+        self.locks.snapshot()
+    }
+
+    /// Returns the map, all the guards having been dropped.
+    pub fn into_inner(self) -> RadixMap<K, V> {
+        self.state.into_inner().unwrap_or_else(PoisonError::into_inner).map
+    }
+
+    // a panicking guard leaves the state consistent, see the Drop of PrefixGuard
+    fn state(&self) -> MutexGuard<'_, PrefixLocks<K, V>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn guard(&self, mut state: MutexGuard<'_, PrefixLocks<K, V>>, prefix: &K) -> PrefixGuard<'_, K, V> {
+        let entries = state.map.remove_prefix(prefix);
+        state.locked.push(prefix.as_slice().to_vec());
+        PrefixGuard {
+            owner: self,
+            prefix: prefix.as_slice().to_vec(),
+            entries,
+        }
+    }
+}
+
+impl<K: Key + ?Sized, V> Default for PrefixLockMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Key + ?Sized, V> From<RadixMap<K, V>> for PrefixLockMap<K, V> {
+    fn from(map: RadixMap<K, V>) -> Self {
+        PrefixLockMap {
+            state: Mutex::new(PrefixLocks { map, locked: Vec::new() }),
+            released: Condvar::new(),
+            #[cfg(feature = "metrics")]
+            locks: LockCounters::new(),
+        }
+    }
+}
+
+/// The entries under a prefix of a [`PrefixLockMap`], obtained through
+/// [`PrefixLockMap::lock_prefix`].
+///
+/// The guard dereferences to a map of these entries, keyed by their full key. Writing a key that
+/// doesn't start with the prefix panics.
+///
+/// [`PrefixLockMap`]: struct.PrefixLockMap.html
+/// [`PrefixLockMap::lock_prefix`]: struct.PrefixLockMap.html#method.lock_prefix
+pub struct PrefixGuard<'a, K: 'a + Key + ?Sized, V: 'a> {
+    owner: &'a PrefixLockMap<K, V>,
+    prefix: Vec<K::Component>,
+    entries: RadixMap<K, V>,
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> PrefixGuard<'a, K, V> {
+    /// Inserts a key-value pair, returning the value previously stored at this key.
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        self.check(key);
+        self.entries.insert(key, value)
+    }
+
+    /// Returns a mutable reference to the value of a key.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.check(key);
+        self.entries.get_mut(key)
+    }
+
+    /// Removes a key, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.check(key);
+        self.entries.remove(key)
+    }
+
+    fn check(&self, key: &K) {
+        assert!(key.as_slice().starts_with(&self.prefix), "key outside of the locked prefix");
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Deref for PrefixGuard<'a, K, V> {
+    type Target = RadixMap<K, V>;
+
+    fn deref(&self) -> &RadixMap<K, V> {
+        &self.entries
+    }
+}
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Drop for PrefixGuard<'a, K, V> {
+    fn drop(&mut self) {
+        // a panicking writer still gives its entries back
+        let mut state = self.owner.state();
+        state.map.append(&mut self.entries);
+        if let Some(i) = state.locked.iter().position(|p| *p == self.prefix) {
+            state.locked.swap_remove(i);
+        }
+        self.owner.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::sync::Barrier;
+
+    use super::{new, PrefixLockMap};
+    use RadixMap;
+
+    #[test]
+    fn it_publishes_writes() {
+        let (mut w, r) = new::<str, i32>();
+        w.insert("a", 0);
+        w.insert("b", 1);
+        assert!(r.read().is_empty());
+        assert_eq!(w.pending(), 2);
+
+        w.publish();
+        assert_eq!(r.read().get("a"), Some(&0));
+        assert_eq!(r.read().get("b"), Some(&1));
+        assert_eq!(w.pending(), 0);
+
+        w.remove("a");
+        w.publish();
+        assert_eq!(r.read().get("a"), None);
+
+        // both copies should have caught up
+        w.insert("c", 2);
+        w.publish();
+        assert_eq!(r.read().keys().collect::<Vec<_>>(), vec!["b", "c"]);
+
+        w.clear();
+        w.publish();
+        assert!(r.read().is_empty());
+    }
+
+    #[test]
+    fn it_can_be_read_from_other_threads() {
+        let (mut w, r) = new::<[u8], usize>();
+
+        let readers: Vec<_> = (0..4).map(|_| {
+            let r = r.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                while last < 100 {
+                    let map = r.read();
+                    // published versions only ever grow, and are always complete
+                    assert!(map.len() >= last);
+                    assert!((0..map.len()).all(|i| map.get(&[i as u8]) == Some(&i)));
+                    last = map.len();
+                }
+            })
+        }).collect();
+
+        for i in 0..100 {
+            w.insert(&[i as u8], i);
+            w.publish();
+        }
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn it_locks_disjoint_prefixes_at_once() {
+        let map: RadixMap<str, i32> = vec![("a/x", 1), ("b/x", 2), ("c", 3)].into_iter().collect();
+        let map = PrefixLockMap::from(map);
+
+        {
+            let mut a = map.lock_prefix("a/");
+            assert_eq!(a.keys().collect::<Vec<_>>(), vec!["a/x"]);
+            assert!(map.try_lock_prefix("a/y").is_none());
+            assert!(map.try_lock_prefix("a").is_none());
+            assert!(map.try_lock_prefix("").is_none());
+
+            let mut b = map.try_lock_prefix("b").unwrap();
+            a.insert("a/y", 4);
+            *b.get_mut("b/x").unwrap() += 10;
+            assert_eq!(b.remove("bz"), None);
+        }
+        assert!(map.try_lock_prefix("").is_some());
+
+        // both guards are held at the same time by the threads meeting at the barrier
+        let barrier = Barrier::new(2);
+        thread::scope(|s| {
+            for prefix in &["a/", "b/"] {
+                let (map, barrier) = (&map, &barrier);
+                s.spawn(move || {
+                    let mut entries = map.lock_prefix(prefix);
+                    barrier.wait();
+                    entries.insert(&format!("{}z", prefix), 0);
+                });
+            }
+        });
+
+        let map = map.into_inner();
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["a/x", "a/y", "a/z", "b/x", "b/z", "c"]);
+        assert_eq!(map.get("b/x"), Some(&12));
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn it_counts_waits_on_locks() {
+        use std::time::Duration;
+
+        // contention is counted as soon as a thread starts waiting: the lock is held until then,
+        // and a bit longer to know how long the wait was at least
+        let hold_until_contended = |contended: &dyn Fn() -> u64| {
+            while contended() == 0 {
+                thread::yield_now();
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+
+        let (mut w, r) = new::<str, i32>();
+        w.insert("a", 1);
+        {
+            // the reader holds the copy the writer replays its operations on when publishing
+            let guard = r.read();
+            thread::scope(|s| {
+                s.spawn(|| w.publish());
+                hold_until_contended(&|| r.metrics().writer().contended());
+                drop(guard);
+            });
+        }
+        let metrics = w.metrics();
+        assert_eq!(metrics.writer().acquisitions(), 2);
+        assert!(metrics.writer().contended() >= 1);
+        assert!(metrics.writer().wait().quantile(1.0).unwrap() >= Duration::from_millis(1));
+        assert_eq!(metrics.readers().acquisitions(), 1);
+
+        let map = PrefixLockMap::<str, i32>::new();
+        {
+            let guard = map.lock_prefix("a/");
+            assert!(map.try_lock_prefix("a/b").is_none());
+            thread::scope(|s| {
+                s.spawn(|| map.lock_prefix("a/b").insert("a/b", 1));
+                hold_until_contended(&|| map.metrics().contended() - 1);
+                drop(guard);
+            });
+        }
+        let metrics = map.metrics();
+        assert_eq!(metrics.acquisitions(), 2);
+        assert!(metrics.contended() >= 2);
+        assert_eq!(metrics.wait().count(), 2);
+        assert!(metrics.wait().quantile(1.0).unwrap() >= Duration::from_millis(1));
+    }
+
+    #[test]
+    fn it_keeps_the_entries_of_a_panicking_guard() {
+        let map: RadixMap<str, i32> = vec![("a/x", 1), ("b/x", 2)].into_iter().collect();
+        let map = PrefixLockMap::from(map);
+
+        let panicked = thread::scope(|s| {
+            s.spawn(|| {
+                let mut entries = map.lock_prefix("a/");
+                entries.insert("a/y", 3);
+                // the lock shared by the prefixes is held while panicking, which poisons it
+                let _view = map.read();
+                panic!("tenant crashed");
+            }).join()
+        });
+        assert!(panicked.is_err());
+        assert!(map.state.is_poisoned());
+
+        let mut entries = map.lock_prefix("a/");
+        assert_eq!(entries.keys().collect::<Vec<_>>(), vec!["a/x", "a/y"]);
+        entries.insert("a/z", 4);
+        assert_eq!(map.read().keys().collect::<Vec<_>>(), vec!["b/x"]);
+        drop(entries);
+
+        assert!(map.try_lock_prefix("").is_some());
+        assert_eq!(map.read().keys().collect::<Vec<_>>(), vec!["a/x", "a/y", "a/z", "b/x"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "key outside of the locked prefix")]
+    fn it_only_writes_under_the_locked_prefix() {
+        let map = PrefixLockMap::<str, i32>::new();
+        map.lock_prefix("a/").insert("b/x", 1);
+    }
+}
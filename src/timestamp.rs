@@ -0,0 +1,266 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use map::{RadixMap, Range};
+
+const SECS_PER_DAY: u64 = 86_400;
+
+// the length of an encoded RFC 3339 timestamp, e.g. `2024-01-01T10:00:00Z`
+const RFC3339_LEN: usize = 20;
+
+// the last second of the year 9999, the last one fitting 4 digits
+const RFC3339_MAX: u64 = 253_402_300_799;
+
+fn epoch_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .expect("timestamps can't be before the Unix epoch")
+        .as_secs()
+}
+
+/// Encodes a timestamp as the big-endian number of seconds since the Unix epoch, so that keys
+/// sort like timestamps.
+///
+/// Sub-second precision is truncated.
+///
+/// # Panics
+///
+/// Panics if the timestamp is before the Unix epoch.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use panoradix::timestamp::{encode_epoch, decode_epoch};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+/// assert_eq!(encode_epoch(time), [0, 0, 0, 0, 0x65, 0x92, 0x00, 0x80]);
+/// assert_eq!(decode_epoch(&encode_epoch(time)), Some(time));
+/// ```
+pub fn encode_epoch(time: SystemTime) -> [u8; 8] {
+    epoch_secs(time).to_be_bytes()
+}
+
+/// Decodes a timestamp encoded by [`encode_epoch`] at the start of a key.
+///
+/// [`encode_epoch`]: fn.encode_epoch.html
+pub fn decode_epoch(key: &[u8]) -> Option<SystemTime> {
+    let mut secs = [0; 8];
+    secs.copy_from_slice(key.get(..8)?);
+    Some(UNIX_EPOCH + Duration::from_secs(u64::from_be_bytes(secs)))
+}
+
+/// Encodes a timestamp as an RFC 3339 date and time in UTC, e.g. `2024-01-01T10:00:00Z`, whose
+/// fixed width makes keys sort like timestamps.
+///
+/// Sub-second precision is truncated. Keys of the same day, hour, etc. share a prefix, e.g. to
+/// [`rollup_prefix`] them.
+///
+/// [`rollup_prefix`]: ../map/struct.RadixMap.html#method.rollup_prefix
+///
+/// # Panics
+///
+/// Panics if the timestamp is before the Unix epoch or after the year 9999.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use panoradix::timestamp::{encode_rfc3339, decode_rfc3339};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_704_103_200);
+/// assert_eq!(encode_rfc3339(time), "2024-01-01T10:00:00Z");
+/// assert_eq!(decode_rfc3339("2024-01-01T10:00:00Z/request-42"), Some(time));
+/// ```
+pub fn encode_rfc3339(time: SystemTime) -> String {
+    let secs = epoch_secs(time);
+    assert!(secs <= RFC3339_MAX, "timestamps can't be after the year 9999");
+
+    let (year, month, day) = civil_from_days(secs / SECS_PER_DAY);
+    let secs = secs % SECS_PER_DAY;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Decodes a timestamp encoded by [`encode_rfc3339`] at the start of a key.
+///
+/// [`encode_rfc3339`]: fn.encode_rfc3339.html
+pub fn decode_rfc3339(key: &str) -> Option<SystemTime> {
+    let encoded = key.get(..RFC3339_LEN)?;
+    // the fields are sliced by byte, other characters than ASCII can't be part of a timestamp
+    if !encoded.is_ascii() {
+        return None;
+    }
+    let field = |range: ::std::ops::Range<usize>| encoded[range].parse::<u64>().ok();
+
+    let days = days_from_civil(field(0..4)?, field(5..7)?, field(8..10)?);
+    let secs = days * SECS_PER_DAY + field(11..13)? * 3600 + field(14..16)? * 60 + field(17..19)?;
+    let time = UNIX_EPOCH + Duration::from_secs(secs);
+
+    // out of range fields and separators are caught by encoding back
+    if secs <= RFC3339_MAX && encode_rfc3339(time) == encoded {
+        Some(time)
+    } else {
+        None
+    }
+}
+
+// the date of a number of days since the Unix epoch, in the proleptic Gregorian calendar
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // shifted to 0000-03-01, so that leap days end years
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = era * 400 + year_of_era + (month <= 2) as u64;
+    (year, month, day)
+}
+
+// the inverse of `civil_from_days`, for dates from the Unix epoch on
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    if year < 1970 || month == 0 || month > 12 || day == 0 {
+        return 0;
+    }
+
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    (era * 146_097 + day_of_era).saturating_sub(719_468)
+}
+
+impl<V> RadixMap<[u8], V> {
+    /// Gets an iterator over the entries whose key starts with a timestamp encoded by
+    /// [`encode_epoch`] in `start..end`, sorted by key.
+    ///
+    /// [`encode_epoch`]: fn.encode_epoch.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` or `end` can't be encoded, see [`encode_epoch`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use panoradix::RadixMap;
+    /// use panoradix::timestamp::encode_epoch;
+    ///
+    /// let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+    ///
+    /// let mut events: RadixMap<[u8], &str> = RadixMap::new();
+    /// events.insert(&encode_epoch(at(100)), "start");
+    /// events.insert(&encode_epoch(at(200)), "request");
+    /// events.insert(&encode_epoch(at(300)), "stop");
+    ///
+    /// let found: Vec<_> = events.find_time_range(at(150), at(300)).map(|(_, &e)| e).collect();
+    /// assert_eq!(found, vec!["request"]);
+    /// ```
+    pub fn find_time_range(&self, start: SystemTime, end: SystemTime) -> Range<'_, [u8], V> {
+        self.range(&encode_epoch(start), &encode_epoch(end))
+    }
+}
+
+impl<V> RadixMap<str, V> {
+    /// Gets an iterator over the entries whose key starts with a timestamp encoded by
+    /// [`encode_rfc3339`] in `start..end`, sorted by key.
+    ///
+    /// [`encode_rfc3339`]: fn.encode_rfc3339.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` or `end` can't be encoded, see [`encode_rfc3339`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use panoradix::RadixMap;
+    /// use panoradix::timestamp::encode_rfc3339;
+    ///
+    /// let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+    ///
+    /// let mut logs: RadixMap<str, &str> = RadixMap::new();
+    /// logs.insert(&format!("{}/web-1", encode_rfc3339(at(3600))), "GET /");
+    /// logs.insert(&format!("{}/web-2", encode_rfc3339(at(3600))), "GET /about");
+    /// logs.insert(&format!("{}/web-1", encode_rfc3339(at(7200))), "POST /login");
+    ///
+    /// let found: Vec<_> = logs.find_time_range(at(0), at(7200)).map(|(_, &l)| l).collect();
+    /// assert_eq!(found, vec!["GET /", "GET /about"]);
+    /// ```
+    pub fn find_time_range(&self, start: SystemTime, end: SystemTime) -> Range<'_, str, V> {
+        self.range(&encode_rfc3339(start), &encode_rfc3339(end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{encode_epoch, decode_epoch, encode_rfc3339, decode_rfc3339, RFC3339_MAX};
+    use RadixMap;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn it_encodes_dates() {
+        assert_eq!(encode_rfc3339(at(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(encode_rfc3339(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(encode_rfc3339(at(4_107_542_399)), "2100-02-28T23:59:59Z");
+        assert_eq!(encode_rfc3339(at(4_107_542_400)), "2100-03-01T00:00:00Z");
+        assert_eq!(encode_rfc3339(at(RFC3339_MAX)), "9999-12-31T23:59:59Z");
+
+        assert_eq!(decode_rfc3339("2100-02-29T00:00:00Z"), None);
+        assert_eq!(decode_rfc3339("2024-13-01T00:00:00Z"), None);
+        assert_eq!(decode_rfc3339("2024-01-01 00:00:00Z"), None);
+        assert_eq!(decode_rfc3339("1969-12-31T23:59:59Z"), None);
+        assert_eq!(decode_rfc3339("9999-12-31T99:59:59Z"), None);
+        assert_eq!(decode_rfc3339("2024-01-01"), None);
+        assert_eq!(decode_rfc3339("202é-01-01T00:00:00Z"), None);
+        assert_eq!(decode_epoch(&[0; 7]), None);
+    }
+
+    #[test]
+    fn it_keeps_the_order_of_timestamps() {
+        let mut last: Option<(String, [u8; 8])> = None;
+        for secs in (0..RFC3339_MAX).step_by(7_777_777).chain(vec![RFC3339_MAX]) {
+            let (text, bytes) = (encode_rfc3339(at(secs)), encode_epoch(at(secs)));
+            assert_eq!(decode_rfc3339(&text), Some(at(secs)));
+            assert_eq!(decode_epoch(&bytes), Some(at(secs)));
+
+            if let Some((last_text, last_bytes)) = last {
+                assert!(last_text < text && last_bytes < bytes, "{} >= {}", last_text, text);
+            }
+            last = Some((text, bytes));
+        }
+    }
+
+    #[test]
+    fn it_finds_time_ranges_of_suffixed_keys() {
+        let mut map: RadixMap<[u8], u64> = RadixMap::new();
+        for secs in 0..100 {
+            let mut key = encode_epoch(at(secs * 10)).to_vec();
+            key.extend_from_slice(b"/event");
+            map.insert(&key, secs * 10);
+        }
+
+        let found: Vec<_> = map.find_time_range(at(95), at(130)).map(|(_, &s)| s).collect();
+        assert_eq!(found, vec![100, 110, 120]);
+        assert_eq!(map.find_time_range(at(130), at(95)).count(), 0);
+    }
+}
@@ -1,25 +1,113 @@
+use std::fmt;
+use std::iter;
 use std::mem;
+use std::cmp::Ordering;
 use std::slice;
+use std::ops::Deref;
 use std::borrow::Cow;
+use std::sync::atomic::{self, AtomicU64};
+#[cfg(feature = "tracing")]
+use std::cell::Cell;
 
 use key::KeyComponent;
+use map::Outcome;
+
+#[cfg(feature = "tracing")]
+thread_local! {
+    // the nodes gone through by the descent being traced on this thread
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Count a node gone through by the descent being traced, with the `tracing` feature.
+#[cfg(feature = "tracing")]
+fn count_node() {
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+}
+
+/// The span of a descent traced by `Tree::trace_descent`, reporting it on drop if it went
+/// through more than `DEEP_DESCENT` nodes.
+#[cfg(feature = "tracing")]
+struct Descent {
+    _span: tracing::span::EnteredSpan,
+    // the depth of a descent this one is nested in, e.g. through the closure of `apply`
+    outer: usize,
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Descent {
+    fn drop(&mut self) {
+        let depth = DEPTH.with(|depth| depth.replace(self.outer));
+        if depth > DEEP_DESCENT {
+            tracing::debug!(depth, "deep descent");
+        }
+    }
+}
+
+/// Draw a generation no tree had before.
+fn next_generation() -> u64 {
+    static GENERATIONS: AtomicU64 = AtomicU64::new(1);
+    GENERATIONS.fetch_add(1, atomic::Ordering::Relaxed)
+}
+
+/// The number of nodes above which a descent is reported, with the `tracing` feature.
+#[cfg(feature = "tracing")]
+const DEEP_DESCENT: usize = 256;
+
+/// The number of edges above which scanning a node is reported, with the `tracing` feature.
+#[cfg(feature = "tracing")]
+const WIDE_SCAN: usize = 1024;
 
 pub struct Tree<K: KeyComponent, V> {
     root: Node<K, V>,
-    len: usize,
+    // renewed whenever nodes may be added or removed, invalidating paths to nodes, and unique
+    // across trees (but for the empty trees of `new`, at 0) so that paths can't be validated
+    // against another tree
+    generation: u64,
+    // keys added and removed since the tree was made
+    insertions: u64,
+    removals: u64,
 }
 
 impl<K: KeyComponent, V> Tree<K, V> {
-    pub fn new() -> Tree<K, V> {
+    pub const fn new() -> Tree<K, V> {
         Tree {
             root: Node::new(),
-            len: 0,
+            generation: 0,
+            insertions: 0,
+            removals: 0,
+        }
+    }
+
+    /// Make a tree of the keys below `root`, counted as inserted.
+    fn from_root(root: Node<K, V>) -> Tree<K, V> {
+        Tree {
+            insertions: root.count as u64,
+            removals: 0,
+            root,
+            generation: next_generation(),
         }
     }
 
     pub fn clear(&mut self) {
+        self.removals += self.len() as u64;
         self.root.clear();
-        self.len = 0;
+        self.generation = next_generation();
+    }
+
+    /// Get the number of keys added to the tree so far, replacing the value of a key isn't
+    /// counted.
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
+    /// Get the number of keys removed from the tree so far.
+    pub fn removals(&self) -> u64 {
+        self.removals
+    }
+
+    /// Count the nodes of the tree, including its root, by walking all of them.
+    pub fn node_count(&self) -> usize {
+        self.root.node_count()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -27,7 +115,7 @@ impl<K: KeyComponent, V> Tree<K, V> {
     }
 
     pub fn len(&self) -> usize {
-        self.len
+        self.root.count
     }
 
     pub fn get(&self, key: &[K]) -> Option<&V> {
@@ -38,43 +126,692 @@ impl<K: KeyComponent, V> Tree<K, V> {
         self.root.get_mut(key)
     }
 
+    /// Adds `delta` (wrapping) to the sums of the root and of the nodes along the path of `key`,
+    /// which should be in the tree.
+    pub fn add_to_sums(&mut self, key: &[K], delta: u128) {
+        let (mut node, mut key) = (&mut self.root, key);
+        loop {
+            node.sum = node.sum.wrapping_add(delta);
+            let i = match key.first().map(|first| node.dispatch(first)) {
+                Some(Ok(i)) if key.starts_with(&node.edges[i].prefix) => i,
+                _ => return,
+            };
+            let edge = &mut node.edges[i];
+            key = &key[edge.prefix.len()..];
+            node = &mut edge.node;
+        }
+    }
+
+    /// Returns the sum of the subtree of the keys starting with `prefix`, in one descent.
+    pub fn sum(&self, prefix: &[K]) -> u128 {
+        let (mut node, mut key) = (&self.root, prefix);
+        while let Some(first) = key.first() {
+            let edge = match node.dispatch(first) {
+                Ok(i) => &node.edges[i],
+                Err(_) => return 0,
+            };
+            // the prefix may end inside the edge, its whole subtree matches
+            if !key.starts_with(&edge.prefix) && !edge.prefix.starts_with(key) {
+                return 0;
+            }
+            key = &key[key.len().min(edge.prefix.len())..];
+            node = &edge.node;
+        }
+        node.sum
+    }
+
+    /// Swaps the values of two keys, returning `false` (and leaving the tree as is) unless both
+    /// are in the tree.
+    pub fn swap(&mut self, a: &[K], b: &[K]) -> bool {
+        if a == b {
+            return self.get(a).is_some();
+        }
+
+        match self.root.get_pair_mut(a, b) {
+            Some((x, y)) => {
+                mem::swap(x, y);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn insert(&mut self, key: &[K], value: V) -> Option<V> {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace_descent("insert", key);
+
         let old_value = self.root.insert(key, value);
         if old_value.is_none() {
-            self.len += 1;
+            self.generation = next_generation();
+            self.insertions += 1;
         }
         old_value
     }
 
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
         self.root.iter()
     }
 
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut::new(Vec::new(), &mut self.root)
+    }
+
+    /// Find the keys starting with `key`, with mutable references to their values.
+    pub fn find_mut(&mut self, key: &[K]) -> IterMut<'_, K, V> {
+        match self.root.find_subtree_mut(key, Vec::new()) {
+            Some((prefix, node)) => IterMut::new(prefix, node),
+            None                 => IterMut::empty(),
+        }
+    }
+
     pub fn remove(&mut self, key: &[K]) -> Option<V> {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace_descent("remove", key);
+
         let old_value = self.root.remove(key);
         if old_value.is_some() {
-            self.len -= 1;
+            self.generation = next_generation();
+            self.removals += 1;
         }
         old_value
     }
 
+    /// Update, insert or remove the value of a key in a single descent, as decided by `f`.
+    pub fn apply<F>(&mut self, key: &[K], f: F) -> (Option<V>, Change)
+        where F: FnOnce(Option<&mut V>) -> Outcome<V>
+    {
+        #[cfg(feature = "tracing")]
+        let _span = self.trace_descent("apply", key);
+
+        let (old_value, change) = self.root.apply(key, f);
+        match change {
+            Change::Inserted => {
+                self.generation = next_generation();
+                self.insertions += 1;
+            },
+            Change::Removed => {
+                self.generation = next_generation();
+                self.removals += 1;
+            },
+            Change::Kept | Change::Replaced => {},
+        }
+        (old_value, change)
+    }
+
+    /// Moves the value of `old` to `new`, returning `false` (and leaving the tree as is) if `old`
+    /// isn't in the tree or `new` already is.
+    pub fn rename_key(&mut self, old: &[K], new: &[K]) -> bool {
+        if old == new {
+            return self.get(old).is_some();
+        }
+
+        let renamed = self.root.rename_key(old, new);
+        if renamed {
+            self.generation = next_generation();
+        }
+        renamed
+    }
+
+    /// Moves the entries whose key starts with `prefix` to a new tree, detaching their subtree
+    /// instead of removing them one by one.
+    pub fn remove_prefix(&mut self, prefix: &[K]) -> Tree<K, V> {
+        let mut key = Vec::new();
+        let subtree = match self.root.remove_prefix(prefix, &mut key) {
+            Some(subtree) => subtree,
+            None => return Tree::new(),
+        };
+        self.generation = next_generation();
+        self.removals += subtree.count as u64;
+
+        // the subtree hangs from the root of the new tree by its whole path
+        let mut root = if key.is_empty() {
+            subtree
+        } else {
+            let mut root = Node::new();
+            root.count = subtree.count;
+            root.insert_edge(0, Edge { prefix: Label::new(&key), node: subtree });
+            root
+        };
+        root.update_lengths();
+
+        Tree::from_root(root)
+    }
+
+    /// Moves the entries of `other` into the tree, grafting its edges where they diverge from
+    /// those of the tree. The values of `other` replace those of the keys in both.
+    pub fn append(&mut self, other: Tree<K, V>) {
+        let len = self.len();
+        self.root.append(other.root);
+        self.generation = next_generation();
+        self.insertions += (self.len() - len) as u64;
+    }
+
+    /// Moves all the entries to a new tree, leaving this one empty.
+    pub fn take(&mut self) -> Tree<K, V> {
+        let root = mem::replace(&mut self.root, Node::new());
+        self.generation = next_generation();
+        self.removals += root.count as u64;
+        Tree::from_root(root)
+    }
+
+    /// Replaces the entries whose key starts with `prefix` by a single entry at `prefix`, folding
+    /// their values in key order. Returns the number of entries replaced.
+    pub fn rollup_prefix<F>(&mut self, prefix: &[K], mut fold: F) -> usize
+        where F: FnMut(V, V) -> V,
+    {
+        let subtree = match self.root.remove_prefix(prefix, &mut Vec::new()) {
+            Some(subtree) => subtree,
+            None => return 0,
+        };
+
+        // the tree is consistent before folding, in case `fold` panics
+        let rolled_up = subtree.count;
+        self.removals += rolled_up as u64;
+        self.generation = next_generation();
+        if let Some(value) = subtree.fold_values(None, &mut fold) {
+            self.root.insert(prefix, value);
+            self.generation = next_generation();
+            self.insertions += 1;
+        }
+        rolled_up
+    }
+
+    /// Removes the entries whose key starts with one of `pruned`, then those for which `keep`
+    /// returns `false`, and merges the nodes left with a single child and no value into their
+    /// parent edge, all in a single pass. Returns the number of entries removed.
+    pub fn retain_and_prune<F>(&mut self, pruned: &[&[K]], mut keep: F) -> usize
+        where F: FnMut(&[K], &mut V) -> bool,
+    {
+        self.removing(|root| root.retain_and_prune(&mut Vec::new(), pruned, &mut keep, &mut |_, _| ()))
+    }
+
+    /// Get a lazy pass removing the entries for which `pred` returns `true`, yielding them in key
+    /// order. The nodes it empties are compacted when it's dropped.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F>
+        where F: FnMut(&[K], &mut V) -> bool,
+    {
+        self.generation = next_generation();
+        ExtractIf {
+            tree: self,
+            pred,
+            path: Vec::new(),
+            next_edge: None,
+            key: Vec::new(),
+            removed: 0,
+        }
+    }
+
+    /// Run a pass removing entries from the root, which only fixes the counts and lengths of the
+    /// nodes it went through once their children are done. If the pass panics part-way (e.g. in
+    /// a closure of the caller), the whole tree is repaired as the panic unwinds, so that it's
+    /// left with fewer entries but intact invariants.
+    fn removing<F>(&mut self, pass: F) -> usize
+        where F: FnOnce(&mut Node<K, V>) -> usize,
+    {
+        struct Repair<'a, K: 'a + KeyComponent, V: 'a> {
+            tree: &'a mut Tree<K, V>,
+            count: usize,
+        }
+
+        impl<'a, K: 'a + KeyComponent, V: 'a> Drop for Repair<'a, K, V> {
+            fn drop(&mut self) {
+                self.tree.root.repair();
+                self.tree.removals += (self.count - self.tree.root.count) as u64;
+            }
+        }
+
+        self.generation = next_generation();
+        let repair = Repair { count: self.root.count, tree: self };
+        let removed = pass(&mut repair.tree.root);
+        mem::forget(repair);
+
+        self.removals += removed as u64;
+        removed
+    }
+
+    /// Get the generation of the structure of the tree, renewed by the changes which invalidate
+    /// the paths returned by `node_path`. No two trees share a generation, but empty ones.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Get the path to the subtree of the keys starting with `prefix`, as the index of the edge
+    /// taken at each node, along with the prefix of the subtree's keys.
+    pub fn node_path(&self, prefix: &[K]) -> Option<(Vec<usize>, Vec<K>)> {
+        let (mut node, mut rest) = (&self.root, prefix);
+        let (mut path, mut key) = (Vec::new(), Vec::with_capacity(prefix.len()));
+
+        while let Some(first) = rest.first() {
+            let i = node.dispatch(first).ok()?;
+            let edge = &node.edges[i];
+            if rest.starts_with(&edge.prefix) {
+                rest = &rest[edge.prefix.len()..];
+            } else if edge.prefix.starts_with(rest) {
+                rest = &[];
+            } else {
+                return None;
+            }
+
+            path.push(i);
+            key.add_suffix(&edge.prefix);
+            node = &edge.node;
+        }
+
+        Some((path, key))
+    }
+
+    /// Get the entries of the subtree at the end of `path` (see `node_path`), whose keys start
+    /// with `prefix`.
+    ///
+    /// Paths going through missing edges have no subtree.
+    pub fn resolve<'a>(&'a self, path: &[usize], prefix: Vec<K>) -> Option<Matches<'a, K, V>> {
+        self.node_at(path).map(|node| Matches::found(prefix, node))
+    }
+
+    /// Get the value of the node at the end of `path` (see `node_path`), without comparing any
+    /// label.
+    pub fn value_at(&self, path: &[usize]) -> Option<&V> {
+        self.node_at(path)?.value.as_ref()
+    }
+
+    fn node_at(&self, path: &[usize]) -> Option<&Node<K, V>> {
+        let mut node = &self.root;
+        for &i in path.iter() {
+            node = &node.edges.get(i)?.node;
+        }
+        Some(node)
+    }
+
+    /// Turn the tree into one with the same keys, mapping each value (in key order) with `f`.
+    ///
+    /// Each value is moved either into `f` or, if `f` panics, into the tree being dropped.
+    pub fn map_values<W, F>(self, mut f: F) -> Tree<K, W>
+        where F: FnMut(V) -> W,
+    {
+        Tree {
+            root: self.root.map_values(&mut f),
+            generation: next_generation(),
+            insertions: self.insertions,
+            removals: self.removals,
+        }
+    }
+
+    /// Move all the entries out of the tree, sorted by key.
+    pub fn into_entries(self) -> Vec<(Vec<K>, V)> {
+        let mut entries = Vec::with_capacity(self.len());
+        self.root.into_entries(&mut Vec::new(), &mut entries);
+        entries
+    }
+
+    /// Move all the values out of the tree, sorted by key.
+    pub fn into_values(self) -> Vec<V> {
+        let mut values = Vec::with_capacity(self.len());
+        self.root.into_values(&mut values);
+        values
+    }
+
+    /// Get the entry of the given rank, i.e. the `n`-th smallest key.
+    pub fn select(&self, n: usize) -> Option<(Vec<K>, &V)> {
+        self.root.select(n, Vec::new())
+    }
+
+    /// Get the entry of the given rank among the keys starting with `prefix`.
+    pub fn select_in(&self, prefix: &[K], n: usize) -> Option<(Vec<K>, &V)> {
+        let (key, node) = self.root.find_subtree(prefix, Vec::new())?;
+        node.select(n, key)
+    }
+
+    /// Get the entry of the shortest key starting with `prefix` (the smallest one of that length).
+    pub fn shortest_key(&self, prefix: &[K]) -> Option<(Vec<K>, &V)> {
+        let (key, node) = self.root.find_subtree(prefix, Vec::new())?;
+        node.descend_to_length(key, |n| n.shortest)
+    }
+
+    /// Get the entry of the longest key starting with `prefix` (the smallest one of that length).
+    pub fn longest_key(&self, prefix: &[K]) -> Option<(Vec<K>, &V)> {
+        let (key, node) = self.root.find_subtree(prefix, Vec::new())?;
+        node.descend_to_length(key, |n| n.longest)
+    }
+
+    /// Count the keys sorting before `key`, whether it's in the tree or not.
+    pub fn rank(&self, key: &[K]) -> usize {
+        let (mut node, mut rest) = (&self.root, key);
+        let mut rank = 0;
+
+        while let Some(first) = rest.first() {
+            // a key that is a prefix of another sorts before it
+            if node.value.is_some() {
+                rank += 1;
+            }
+
+            let i = match node.dispatch(first) {
+                Ok(i)  => i,
+                Err(i) => return rank + node.edges[..i].iter().map(|e| e.node.count).sum::<usize>(),
+            };
+            rank += node.edges[..i].iter().map(|e| e.node.count).sum::<usize>();
+
+            let edge = &node.edges[i];
+            if rest.starts_with(&edge.prefix) {
+                rest = &rest[edge.prefix.len()..];
+                node = &edge.node;
+            } else {
+                // the keys below the edge all sort either before or after `key`
+                if edge.prefix[..] < *rest {
+                    rank += edge.node.count;
+                }
+                return rank;
+            }
+        }
+
+        rank
+    }
+
+    /// Get an iterator over the entries whose key is in `start..end`, sorted by key.
+    ///
+    /// The iterator starts from a single descent along `start`, the subtrees sorting before it
+    /// are never visited.
+    pub fn range(&self, start: &[K], end: &[K]) -> iter::Take<Iter<'_, K, V>> {
+        let len = self.rank(end).saturating_sub(self.rank(start));
+        Iter::seek(&self.root, start).take(len)
+    }
+
+    /// Get an iterator over the entries whose key is `start` or sorts after it, sorted by key,
+    /// for callers checking the end of a range on the lent keys (see `Iter::next_raw`).
+    #[cfg(feature = "icu")]
+    pub fn iter_from(&self, start: &[K]) -> Iter<'_, K, V> {
+        Iter::seek(&self.root, start)
+    }
+
     pub fn find<'a>(&'a self, key: &[K]) -> Matches<'a, K, V> {
-        self.root.find(key)
+        #[cfg(feature = "tracing")]
+        let _span = self.trace_descent("find", key);
+
+        self.root.find(key, Vec::new())
     }
-}
 
-trait PrefixExt<K> {
-    fn add_prefix(&mut self, other: &[K]);
+    /// Find the entries starting with each of `prefixes`, in the order of the prefixes. The tree
+    /// is descended once to the subtree of each prefix which isn't below another one, and the
+    /// prefixes below it are only descended to from there.
+    pub fn find_batch<'a>(&'a self, prefixes: &[&[K]]) -> Vec<Matches<'a, K, V>> {
+        let mut order: Vec<usize> = (0..prefixes.len()).collect();
+        order.sort_by_key(|&i| prefixes[i]);
+
+        let mut found: Vec<_> = prefixes.iter().map(|_| Matches::none()).collect();
+        let mut rest = &order[..];
+        while let Some(&first) = rest.first() {
+            // the prefixes starting with the first one follow it once sorted
+            let n = rest.iter().take_while(|&&i| prefixes[i].starts_with(prefixes[first])).count();
+            let (group, next) = rest.split_at(n);
+            rest = next;
+
+            // the subtree of the first prefix, whose path may go past it (to the end of an edge)
+            let (path, node) = match self.root.find_subtree(prefixes[first], Vec::new()) {
+                Some(subtree) => subtree,
+                None          => continue,
+            };
+            for &i in group {
+                let prefix = prefixes[i];
+                found[i] = if path.starts_with(prefix) {
+                    Matches::found(path.clone(), node)
+                } else if prefix.starts_with(&path) {
+                    node.find(&prefix[path.len()..], path.clone())
+                } else {
+                    Matches::none()
+                };
+            }
+        }
+        found
+    }
 
-    fn add_suffix(&mut self, other: &[K]);
+    /// Get the value of the key made of the components yielded by `key`, which are only read as
+    /// the tree is descended.
+    pub fn get_by_components<I: IntoIterator<Item=K>>(&self, key: I) -> Option<&V> {
+        match self.descend_by_components(key, |_| ()) {
+            Some((node, true)) => node.value.as_ref(),
+            _                  => None,
+        }
+    }
 
-    fn with_prefix(mut self, other: &[K]) -> Self
-        where Self: Sized,
+    /// Find the keys starting with the components yielded by `key`, which are only read as the
+    /// tree is descended.
+    pub fn find_by_components<I: IntoIterator<Item=K>>(&self, key: I) -> Matches<'_, K, V> {
+        let mut prefix = Vec::new();
+        match self.descend_by_components(key, |label| prefix.add_suffix(label)) {
+            Some((node, _)) => Matches::found(prefix, node),
+            None            => Matches::none(),
+        }
+    }
+
+    /// Descend along the components yielded by `key`, returning the node below them and whether
+    /// the key ends at that node rather than in the middle of its edge. The labels of the edges
+    /// followed are passed to `follow`, e.g. to collect the prefix of the node.
+    fn descend_by_components<I, F>(&self, key: I, mut follow: F) -> Option<(&Node<K, V>, bool)>
+        where I: IntoIterator<Item=K>,
+              F: FnMut(&[K]),
     {
-        self.add_prefix(other);
-        self
+        let mut node = &self.root;
+        let mut key = key.into_iter();
+
+        while let Some(first) = key.next() {
+            let edge = &node.edges[node.dispatch(&first).ok()?];
+            let mut exact = true;
+            for component in edge.prefix[1..].iter() {
+                match key.next() {
+                    Some(ref c) if c == component => {},
+                    Some(_)                       => return None,
+                    None                          => { exact = false; break },
+                }
+            }
+
+            follow(&edge.prefix);
+            if !exact {
+                return Some((&edge.node, false));
+            }
+            node = &edge.node;
+        }
+
+        Some((node, true))
+    }
+
+    /// Enter a span for an operation descending the tree along `key`, and report descents going
+    /// through more than `DEEP_DESCENT` nodes, the operations recursing once per node.
+    ///
+    /// The nodes are counted by the descent itself (see `count_node`), and reported once it's
+    /// done, as the returned guard is dropped.
+    #[cfg(feature = "tracing")]
+    fn trace_descent(&self, operation: &'static str, key: &[K]) -> Descent {
+        let span = tracing::trace_span!("panoradix", operation, key_len = key.len()).entered();
+        Descent { _span: span, outer: DEPTH.with(|depth| depth.replace(0)) }
+    }
+
+    /// Get the entries whose key has the same length as `pattern` and matches each of its
+    /// components, `None` matching any component.
+    ///
+    /// Fixed components are dispatched to through `firsts`, so absent branches are skipped
+    /// without being visited.
+    pub fn find_pattern(&self, pattern: &[Option<K>]) -> Vec<(Vec<K>, &V)> {
+        let mut entries = Vec::new();
+        self.root.find_pattern(pattern, &mut Vec::new(), &mut entries);
+        entries
+    }
+
+    /// Check which of the keys are in the tree, in the order of `keys`.
+    ///
+    /// The keys are looked up in sorted order (unless they already are), each descent resuming from
+    /// the deepest node shared with the previous key's.
+    pub fn contains_each(&self, keys: &[&[K]]) -> Vec<bool> {
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        if !keys.windows(2).all(|w| w[0] <= w[1]) {
+            order.sort_by_key(|&i| keys[i]);
+        }
+
+        let mut found = vec![false; keys.len()];
+        // the nodes descended for the previous key, along with their depth
+        let mut path = vec![(&self.root, 0)];
+        let mut previous: &[K] = &[];
+
+        for i in order {
+            let key = keys[i];
+            let shared = key.iter().zip(previous.iter()).take_while(|(a, b)| a == b).count();
+            while path.last().unwrap().1 > shared {
+                path.pop();
+            }
+            previous = key;
+
+            let (mut node, mut depth) = *path.last().unwrap();
+            found[i] = loop {
+                let rest = &key[depth..];
+                let edge = match rest.first() {
+                    None => break node.value.is_some(),
+                    Some(first) => match node.dispatch(first) {
+                        Ok(j) if rest.starts_with(&node.edges[j].prefix) => &node.edges[j],
+                        _ => break false,
+                    },
+                };
+
+                node = &edge.node;
+                depth += edge.prefix.len();
+                path.push((node, depth));
+            };
+        }
+
+        found
+    }
+
+    /// Get the entries whose key is a prefix of `key` (including `key` itself), from the shortest
+    /// to the longest key.
+    pub fn ancestors<'a, 'k>(&'a self, key: &'k [K]) -> Ancestors<'a, 'k, K, V> {
+        Ancestors {
+            node: Some(&self.root),
+            key,
+            depth: 0,
+        }
     }
 
+    /// Get the smallest entry whose key starts with `prefix`.
+    pub fn find_first(&self, prefix: &[K]) -> Option<(Vec<K>, &V)> {
+        let (mut key, mut node) = self.root.find_subtree(prefix, Vec::new())?;
+
+        // the smallest key of a subtree is its root's, otherwise the smallest of its first edge
+        loop {
+            if let Some(ref value) = node.value {
+                return Some((key, value));
+            }
+
+            let edge = node.edges.first()?;
+            key.add_suffix(&edge.prefix);
+            node = &edge.node;
+        }
+    }
+
+    /// Walk the entries whose key starts with `prefix` and is between `min_len` and `max_len`
+    /// long (inclusive), sorted by key, until `f` returns `false`.
+    ///
+    /// Subtrees whose keys are all too short or too long aren't descended.
+    pub fn walk<'a, F>(&'a self, prefix: &[K], min_len: usize, max_len: usize, mut f: F)
+        where F: FnMut(&[K], &'a V) -> bool,
+    {
+        if let Some((mut key, node)) = self.root.find_subtree(prefix, Vec::new()) {
+            if node.has_lengths_in(key.len(), min_len, max_len) {
+                node.walk(&mut key, min_len, max_len, &mut f);
+            }
+        }
+    }
+
+    /// Visit the entries sorted by key, along with the number of leading components each key
+    /// shares with the previous one, until `f` returns an error.
+    pub fn visit_entries<E, F>(&self, mut f: F) -> Result<(), E>
+        where F: FnMut(&[K], usize, &V) -> Result<(), E>,
+    {
+        self.root.visit_entries(&mut Vec::new(), &mut 0, &mut f)
+    }
+
+    /// Write an indented rendering of the nodes, with their edge labels (formatted by
+    /// `fmt_label`), whether they hold a value and the number of values below them.
+    pub fn dump<W, F>(&self, w: &mut W, fmt_label: &F) -> fmt::Result
+        where W: fmt::Write,
+              F: Fn(&[K], &mut W) -> fmt::Result,
+    {
+        write!(w, "(root)")?;
+        self.root.dump(w, 0, fmt_label)
+    }
+
+    /// Check the invariants of the tree, describing the first one that doesn't hold.
+    pub fn check(&self) -> Result<(), String> {
+        self.root.check()
+    }
+
+    /// Get a cursor at the root of the tree, to descend it one component at a time.
+    pub fn cursor(&self) -> Cursor<'_, K, V> {
+        Cursor {
+            root: &self.root,
+            key: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Get a cursor at the root of the tree which only descends it, without keeping track of the
+    /// components descended.
+    pub fn prefix_cursor(&self) -> PrefixCursor<'_, K, V> {
+        PrefixCursor {
+            position: Some(Position::at(&self.root)),
+        }
+    }
+
+    /// Find the subtrees whose path is accepted by a matcher, fed one component at a time.
+    ///
+    /// The matcher's state is updated by `step`, which returns `false` when the path can't
+    /// match, and a subtree is matched as soon as `complete` holds for its state.
+    pub fn find_by<'a, S, F, C>(&'a self, state: S, step: &F, complete: &C) -> Vec<Matches<'a, K, V>>
+        where S: Clone,
+              F: Fn(&mut S, &K) -> bool,
+              C: Fn(&S) -> bool,
+    {
+        let mut matches = Vec::new();
+        self.root.find_by(&mut Vec::new(), state, step, complete, &mut matches);
+        matches
+    }
+
+    /// Count the keys by their prefix of `len` components, extended past the components for
+    /// which `continues` holds (e.g. the continuation bytes of a UTF-8 character).
+    pub fn group_count<F: Fn(&K) -> bool>(&self, len: usize, continues: &F) -> Vec<(Vec<K>, usize)> {
+        let mut groups = Vec::new();
+        if len == 0 {
+            if !self.is_empty() {
+                groups.push((Vec::new(), self.len()));
+            }
+        } else {
+            self.root.group_count(len, &mut Vec::new(), &mut groups, continues);
+        }
+        groups
+    }
+
+    pub fn visit_postorder<A, F>(&self, mut f: F) -> A
+        where F: FnMut(Option<(&[K], &V)>, Vec<A>) -> A,
+    {
+        self.root.visit_postorder(&mut Vec::new(), &mut f)
+    }
+
+    /// Get the smallest set of prefixes covering the keys starting with a key of the tree, except
+    /// those starting with a key of `other`. Where a key of the tree is partially covered by
+    /// `other`, it is split into its extensions by each component of `alphabet`.
+    pub fn subtract_cover<W>(&self, other: &Tree<K, W>, alphabet: &[K]) -> Tree<K, ()> {
+        let mut cover = Tree::new();
+        let other = if other.is_empty() { None } else { Some(Position::at(&other.root)) };
+        self.root.subtract_cover(&mut Vec::new(), other, alphabet, &mut cover);
+        cover
+    }
+
+    /// Get the keys of the tree that have no other key as a prefix, i.e. the smallest set of
+    /// prefixes covering all the keys.
+    pub fn minimal_cover(&self) -> Tree<K, ()> {
+        Tree::from_root(self.root.minimal_cover())
+    }
+}
+
+trait PrefixExt<K> {
+    fn add_suffix(&mut self, other: &[K]);
+
     fn with_suffix(mut self, other: &[K]) -> Self
         where Self: Sized,
     {
@@ -84,27 +821,42 @@ trait PrefixExt<K> {
 }
 
 impl<K: Clone> PrefixExt<K> for Vec<K> {
-    fn add_prefix(&mut self, other: &[K]) {
-        let mut old = Vec::with_capacity(self.len() + other.len());
-        mem::swap(self, &mut old);
-        self.extend_from_slice(other);
-        self.extend_from_slice(&old);
-    }
-
     fn add_suffix(&mut self, other: &[K]) {
         self.extend_from_slice(other);
     }
 }
 
+/// A node of the tree.
+///
+/// The first component of each edge's label is duplicated in `firsts`, which is kept parallel to
+/// `edges`: descending the tree only scans this contiguous array, and touches a single edge once
+/// it has been dispatched to.
+///
+/// Each node also keeps track of the number of values stored in its subtree (its own included).
 pub struct Node<K: KeyComponent, V> {
     value: Option<V>,
+    count: usize,
+    // lengths of the shortest and longest keys below the node, relative to it (0 if it's empty)
+    shortest: usize,
+    longest: usize,
+    // wrapping sum of a `u64` per value of the subtree (e.g. the digests of `merkle`, the counts
+    // of `counter`), wide enough not to wrap when the values are counts: only maintained through
+    // `add_to_sums`, splits and merges keep it, the other bulk operations (e.g. `append`,
+    // `retain`) don't
+    sum: u128,
+    firsts: Vec<K>,
     edges: Vec<Edge<K, V>>,
 }
 
 impl<K: KeyComponent, V> Node<K, V> {
-    fn new() -> Node<K, V> {
+    const fn new() -> Node<K, V> {
         Node {
             value: None,
+            count: 0,
+            shortest: 0,
+            longest: 0,
+            sum: 0,
+            firsts: Vec::new(),
             edges: Vec::new(),
         }
     }
@@ -117,14 +869,29 @@ impl<K: KeyComponent, V> Node<K, V> {
         self.value.is_none() && self.edges.is_empty()
     }
 
+    /// Look a key up, this being the hottest path (e.g. of `RadixSet::contains`): the descent is
+    /// a loop borrowing the suffixes of the key, and each label is compared with `starts_with`,
+    /// which is a `memcmp` for bytes (e.g. the components of `str` keys).
     fn get(&self, key: &[K]) -> Option<&V> {
-        if key.is_empty() {
-            self.value.as_ref()
-        } else if let Some((i, PrefixCmp::Full(suffix))) = self.search_for_prefix(key) {
-            self.edges[i].node.get(&suffix)
-        } else {
-            None
+        let (mut node, mut key) = (self, key);
+        while let Some(first) = key.first() {
+            // a node with a single edge (e.g. in a small map, or along a chain of keys) has
+            // nothing to dispatch on, its label is compared right away
+            let edge = match *node.edges {
+                [ref edge] => edge,
+                _          => &node.edges[node.dispatch(first).ok()?],
+            };
+            if !key.starts_with(&edge.prefix) {
+                #[cfg(feature = "complexity")]
+                ::complexity::count_edge(key.iter().zip(edge.prefix.iter()).take_while(|&(a, b)| a == b).count());
+                return None;
+            }
+            #[cfg(feature = "complexity")]
+            ::complexity::count_edge(edge.prefix.len());
+            key = &key[edge.prefix.len()..];
+            node = &edge.node;
         }
+        node.value.as_ref()
     }
 
     fn get_mut(&mut self, key: &[K]) -> Option<&mut V> {
@@ -137,70 +904,745 @@ impl<K: KeyComponent, V> Node<K, V> {
         }
     }
 
+    /// Mutable references to the values of two distinct keys, borrowed from the node where their
+    /// paths diverge.
+    fn get_pair_mut(&mut self, a: &[K], b: &[K]) -> Option<(&mut V, &mut V)> {
+        if b.is_empty() {
+            return self.get_pair_mut(b, a).map(|(y, x)| (x, y));
+        }
+
+        let (j, b_suffix) = match self.search_for_prefix(b) {
+            Some((j, PrefixCmp::Full(suffix))) => (j, suffix),
+            _ => return None,
+        };
+
+        if a.is_empty() {
+            let x = self.value.as_mut()?;
+            let y = self.edges[j].node.get_mut(&b_suffix)?;
+            return Some((x, y));
+        }
+
+        let (i, a_suffix) = match self.search_for_prefix(a) {
+            Some((i, PrefixCmp::Full(suffix))) => (i, suffix),
+            _ => return None,
+        };
+
+        if i == j {
+            return self.edges[i].node.get_pair_mut(&a_suffix, &b_suffix);
+        }
+
+        let (x, y) = if i < j {
+            let (left, right) = self.edges.split_at_mut(j);
+            (&mut left[i], &mut right[0])
+        } else {
+            let (left, right) = self.edges.split_at_mut(i);
+            (&mut right[0], &mut left[j])
+        };
+        Some((x.node.get_mut(&a_suffix)?, y.node.get_mut(&b_suffix)?))
+    }
+
     fn insert(&mut self, key: &[K], value: V) -> Option<V> {
-        if key.is_empty() {
-            let mut value = Some(value);
-            mem::swap(&mut self.value, &mut value);
-            value
+        let old_value = if key.is_empty() {
+            self.value.replace(value)
         } else {
-            if let Some((i, cmp)) = self.search_for_prefix(key) {
-                match cmp {
-                    // Full prefix: insert in the child
-                    PrefixCmp::Full(suffix) => {
-                        return self.edges[i].node.insert(&suffix, value);
-                    },
+            match self.dispatch(&key[0]) {
+                Ok(i) => {
+                    match cmp_prefix(&self.edges[i].prefix, key) {
+                        // Full prefix: insert in the child
+                        Some(PrefixCmp::Full(suffix)) => {
+                            #[cfg(feature = "tracing")]
+                            count_node();
+                            self.edges[i].node.insert(&suffix, value)
+                        },
+
+                        // Partial prefix: split the key and replace the edge's node with a new one
+                        // that holds both nodes to insert.
+                        Some(PrefixCmp::Partial(j)) => {
+                            self.edges[i].split_insert(j, key, value);
+                            None
+                        },
+
+                        None => unreachable!("edge was dispatched on its first component"),
+                    }
+                },
+
+                // No match in edges: insert a new edge where it keeps the edges sorted
+                Err(i) => {
+                    self.insert_edge(i, Edge::new(key, Some(value)));
+                    None
+                },
+            }
+        };
+
+        if old_value.is_none() {
+            if self.count == 0 {
+                self.shortest = key.len();
+                self.longest = key.len();
+            } else {
+                self.shortest = self.shortest.min(key.len());
+                self.longest = self.longest.max(key.len());
+            }
+            self.count += 1;
+        }
+        old_value
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self)
+    }
+
+    /// Move the entries of `other` into the node, replacing the values of the keys in both.
+    fn append(&mut self, other: Node<K, V>) {
+        if other.value.is_some() {
+            self.value = other.value;
+        }
+        for edge in other.edges {
+            self.append_edge(edge);
+        }
+
+        self.count = self.value.is_some() as usize + self.edges.iter().map(|e| e.node.count).sum::<usize>();
+        self.update_lengths();
+    }
+
+    fn append_edge(&mut self, edge: Edge<K, V>) {
+        let i = match self.dispatch(&edge.prefix[0]) {
+            Ok(i) => i,
+            Err(i) => return self.insert_edge(i, edge),
+        };
+
+        let n = self.edges[i].prefix.iter().zip(edge.prefix.iter()).take_while(|&(a, b)| a == b).count();
+        if n < self.edges[i].prefix.len() {
+            self.edges[i].split(n);
+        }
+
+        // the rest of the edge hangs from the node where both labels end or diverge
+        let node = if n == edge.prefix.len() {
+            edge.node
+        } else {
+            let mut node = Node::new();
+            node.count = edge.node.count;
+            node.insert_edge(0, Edge { prefix: Label::new(&edge.prefix[n..]), node: edge.node });
+            node.update_lengths();
+            node
+        };
+        self.edges[i].node.append(node);
+    }
+
+    fn remove(&mut self, key: &[K]) -> Option<V> {
+        let old_value = if key.is_empty() {
+            self.value.take()
+        } else if let Some((i, cmp)) = self.search_for_prefix(key) {
+            match cmp {
+                PrefixCmp::Full(suffix) => {
+                    #[cfg(feature = "tracing")]
+                    count_node();
+                    let ret = self.edges[i].node.remove(&suffix);
+
+                    if self.edges[i].node.is_empty() {
+                        self.remove_edge(i);
+                    }
+
+                    ret
+                },
+                PrefixCmp::Partial(_) => None,
+            }
+        } else {
+            None
+        };
+
+        if old_value.is_some() {
+            self.count -= 1;
+            if key.len() == self.shortest || key.len() == self.longest {
+                self.update_lengths();
+            }
+        }
+        old_value
+    }
 
-                    // Partial prefix: split the key and replace the edge's node with a new one
-                    // that holds both nodes to insert.
-                    PrefixCmp::Partial(j) => {
-                        self.edges[i].split_insert(j, key, value);
+    fn apply<F>(&mut self, key: &[K], f: F) -> (Option<V>, Change)
+        where F: FnOnce(Option<&mut V>) -> Outcome<V>
+    {
+        let (old_value, change) = if key.is_empty() {
+            match f(self.value.as_mut()) {
+                Outcome::Keep => (None, Change::Kept),
+                Outcome::Set(value) => match self.value.replace(value) {
+                    Some(old) => (Some(old), Change::Replaced),
+                    None      => (None, Change::Inserted),
+                },
+                Outcome::Remove => match self.value.take() {
+                    Some(old) => (Some(old), Change::Removed),
+                    None      => (None, Change::Kept),
+                },
+            }
+        } else {
+            match self.dispatch(&key[0]) {
+                Ok(i) => {
+                    match cmp_prefix(&self.edges[i].prefix, key) {
+                        Some(PrefixCmp::Full(suffix)) => {
+                            #[cfg(feature = "tracing")]
+                            count_node();
+                            let ret = self.edges[i].node.apply(&suffix, f);
+
+                            if self.edges[i].node.is_empty() {
+                                self.remove_edge(i);
+                            }
+
+                            ret
+                        },
+
+                        // the key ends or diverges inside the edge, so it isn't in the tree
+                        Some(PrefixCmp::Partial(j)) => match f(None) {
+                            Outcome::Set(value) => {
+                                self.edges[i].split_insert(j, key, value);
+                                (None, Change::Inserted)
+                            },
+                            Outcome::Keep | Outcome::Remove => (None, Change::Kept),
+                        },
+
+                        None => unreachable!("edge was dispatched on its first component"),
+                    }
+                },
+
+                Err(i) => match f(None) {
+                    Outcome::Set(value) => {
+                        self.insert_edge(i, Edge::new(key, Some(value)));
+                        (None, Change::Inserted)
                     },
-                };
+                    Outcome::Keep | Outcome::Remove => (None, Change::Kept),
+                },
+            }
+        };
+
+        match change {
+            Change::Inserted => {
+                if self.count == 0 {
+                    self.shortest = key.len();
+                    self.longest = key.len();
+                } else {
+                    self.shortest = self.shortest.min(key.len());
+                    self.longest = self.longest.max(key.len());
+                }
+                self.count += 1;
+            },
+            Change::Removed => {
+                self.count -= 1;
+                if key.len() == self.shortest || key.len() == self.longest {
+                    self.update_lengths();
+                }
+            },
+            Change::Kept | Change::Replaced => {},
+        }
+        (old_value, change)
+    }
+
+    /// Check if some keys below the node, whose prefix is `depth` long, may be between `min_len`
+    /// and `max_len` long.
+    fn has_lengths_in(&self, depth: usize, min_len: usize, max_len: usize) -> bool {
+        depth + self.shortest <= max_len && depth + self.longest >= min_len
+    }
+
+    /// Recompute the lengths of the shortest and longest keys below the node, from its children.
+    fn update_lengths(&mut self) {
+        let (shortest, longest) = self.lengths_from_children();
+        self.shortest = shortest;
+        self.longest = longest;
+    }
+
+    fn lengths_from_children(&self) -> (usize, usize) {
+        let mut lengths = self.edges.iter()
+            .map(|e| (e.prefix.len() + e.node.shortest, e.prefix.len() + e.node.longest));
+
+        let (mut shortest, mut longest) = match self.value {
+            Some(_) => (0, 0),
+            None    => lengths.next().unwrap_or((0, 0)),
+        };
+        for (s, l) in lengths {
+            shortest = shortest.min(s);
+            longest = longest.max(l);
+        }
+        (shortest, longest)
+    }
+
+    fn rename_key(&mut self, old: &[K], new: &[K]) -> bool {
+        // descend while both keys go through the same edge, the counts above don't change (but the
+        // lengths of the keys do)
+        if let Some((i, PrefixCmp::Full(old_suffix))) = self.search_for_prefix(old) {
+            if let Some(PrefixCmp::Full(new_suffix)) = cmp_prefix(&self.edges[i].prefix, new) {
+                let renamed = self.edges[i].node.rename_key(&old_suffix, &new_suffix);
+                if renamed {
+                    self.update_lengths();
+                }
+                return renamed;
+            }
+        }
+
+        if self.get(new).is_some() {
+            return false;
+        }
+        match self.remove(old) {
+            Some(value) => {
+                self.insert(new, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Detach the subtree of the keys starting with `prefix`, appending the path to its root
+    /// (which may be longer than `prefix`) to `key`.
+    fn remove_prefix(&mut self, prefix: &[K], key: &mut Vec<K>) -> Option<Node<K, V>> {
+        if prefix.is_empty() {
+            return Some(mem::replace(self, Node::new()));
+        }
+
+        let subtree = match self.search_for_prefix(prefix) {
+            Some((i, PrefixCmp::Full(suffix))) => {
+                key.add_suffix(&self.edges[i].prefix);
+                let subtree = self.edges[i].node.remove_prefix(&suffix, key)?;
+                if self.edges[i].node.is_empty() {
+                    self.remove_edge(i);
+                }
+                subtree
+            },
+
+            // the prefix ends inside the edge, its whole subtree is removed
+            Some((i, PrefixCmp::Partial(n))) if n == prefix.len() => {
+                let edge = self.remove_edge(i);
+                key.add_suffix(&edge.prefix);
+                edge.node
+            },
+
+            _ => return None,
+        };
+
+        self.count -= subtree.count;
+        self.update_lengths();
+        Some(subtree)
+    }
+
+    fn fold_values<F>(self, mut acc: Option<V>, fold: &mut F) -> Option<V>
+        where F: FnMut(V, V) -> V,
+    {
+        if let Some(value) = self.value {
+            acc = Some(match acc {
+                Some(acc) => fold(acc, value),
+                None      => value,
+            });
+        }
+
+        for edge in self.edges {
+            acc = edge.node.fold_values(acc, fold);
+        }
+        acc
+    }
+
+    fn retain_and_prune<F, R>(&mut self, key: &mut Vec<K>, pruned: &[&[K]], keep: &mut F, removed_value: &mut R) -> usize
+        where F: FnMut(&[K], &mut V) -> bool,
+              R: FnMut(&[K], V),
+    {
+        if pruned.iter().any(|p| p.is_empty()) {
+            let removed = self.count;
+            self.clear();
+            return removed;
+        }
+
+        let mut removed = 0;
+        if let Some(ref mut value) = self.value {
+            if !keep(key, value) {
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            removed_value(key, self.value.take().unwrap());
+        }
+
+        let mut i = 0;
+        while i < self.edges.len() {
+            let edge = &mut self.edges[i];
+
+            // the pruned prefixes going through the edge, an empty one if it's pruned entirely
+            let suffixes: Vec<&[K]> = pruned.iter().filter_map(|p| {
+                let n = p.iter().zip(edge.prefix.iter()).take_while(|&(a, b)| a == b).count();
+                if n == p.len() || n == edge.prefix.len() {
+                    Some(&p[n..])
+                } else {
+                    None
+                }
+            }).collect();
+
+            key.add_suffix(&edge.prefix);
+            removed += edge.node.retain_and_prune(key, &suffixes, keep, removed_value);
+            key.truncate(key.len() - edge.prefix.len());
+
+            if edge.node.is_empty() {
+                self.remove_edge(i);
             } else {
-                // No match in edges: insert a new edge
-                let new_edge = Edge::new(key.to_owned(), Some(value));
+                edge.merge_child();
+                i += 1;
+            }
+        }
+
+        self.count -= removed;
+        self.update_lengths();
+        removed
+    }
+
+    /// Recompute the counts and lengths of the nodes below, removing the empty edges and merging
+    /// the nodes with a single child and no value, after a pass interrupted by a panic.
+    fn repair(&mut self) {
+        let mut i = 0;
+        while i < self.edges.len() {
+            let edge = &mut self.edges[i];
+            edge.node.repair();
+            if edge.node.is_empty() {
+                self.remove_edge(i);
+            } else {
+                edge.merge_child();
+                i += 1;
+            }
+        }
+
+        self.count = self.value.is_some() as usize + self.edges.iter().map(|e| e.node.count).sum::<usize>();
+        self.update_lengths();
+    }
+
+    /// Descend to the smallest key whose length below the node is `length(self)`, following the
+    /// first edge whose subtree holds such a key at each node.
+    fn descend_to_length<F>(&self, mut key: Vec<K>, length: F) -> Option<(Vec<K>, &V)>
+        where F: Fn(&Node<K, V>) -> usize,
+    {
+        let mut node = self;
+        loop {
+            let len = length(node);
+            if len == 0 {
+                return node.value.as_ref().map(|value| (key, value));
+            }
+
+            let edge = node.edges.iter().find(|e| e.prefix.len() + length(&e.node) == len)?;
+            key.add_suffix(&edge.prefix);
+            node = &edge.node;
+        }
+    }
+
+    fn subtract_cover<W>(&self, key: &mut Vec<K>, other: Option<Position<'_, K, W>>, alphabet: &[K],
+                         cover: &mut Tree<K, ()>)
+    {
+        if other.is_some_and(|p| p.value().is_some()) {
+            return;
+        }
+        if self.value.is_some() {
+            Position::complement(other, key, alphabet, cover);
+            return;
+        }
+
+        for edge in &self.edges {
+            let mut position = other;
+            for component in edge.prefix.iter() {
+                position = position.and_then(|p| p.step(component));
+                if position.is_some_and(|p| p.value().is_some()) {
+                    break;
+                }
+            }
+
+            key.add_suffix(&edge.prefix);
+            edge.node.subtract_cover(key, position, alphabet, cover);
+            key.truncate(key.len() - edge.prefix.len());
+        }
+    }
+
+    fn node_count(&self) -> usize {
+        1 + self.edges.iter().map(|e| e.node.node_count()).sum::<usize>()
+    }
+
+    fn minimal_cover(&self) -> Node<K, ()> {
+        let mut cover = Node::new();
+        if self.value.is_some() {
+            // the key covers the whole subtree
+            cover.value = Some(());
+            cover.count = 1;
+            return cover;
+        }
+
+        for edge in &self.edges {
+            let node = edge.node.minimal_cover();
+            cover.count += node.count;
+            let i = cover.edges.len();
+            cover.insert_edge(i, Edge { prefix: Label::new(&edge.prefix), node });
+        }
+        cover.update_lengths();
+        cover
+    }
+
+    fn into_values(self, values: &mut Vec<V>) {
+        values.extend(self.value);
+        for edge in self.edges {
+            edge.node.into_values(values);
+        }
+    }
+
+    fn into_entries(self, prefix: &mut Vec<K>, entries: &mut Vec<(Vec<K>, V)>) {
+        if let Some(value) = self.value {
+            entries.push((prefix.clone(), value));
+        }
+
+        for edge in self.edges {
+            prefix.add_suffix(&edge.prefix);
+            edge.node.into_entries(prefix, entries);
+            prefix.truncate(prefix.len() - edge.prefix.len());
+        }
+    }
+
+    fn select(&self, mut n: usize, mut prefix: Vec<K>) -> Option<(Vec<K>, &V)> {
+        if let Some(ref value) = self.value {
+            if n == 0 {
+                return Some((prefix, value));
+            }
+            n -= 1;
+        }
+
+        for edge in self.edges.iter() {
+            if n < edge.node.count {
+                prefix.add_suffix(&edge.prefix);
+                return edge.node.select(n, prefix);
+            }
+            n -= edge.node.count;
+        }
+
+        None
+    }
+
+    fn find<'a>(&'a self, key: &[K], prefix: Vec<K>) -> Matches<'a, K, V> {
+        match self.find_subtree(key, prefix) {
+            Some((prefix, node)) => Matches::found(prefix, node),
+            None                 => Matches::none(),
+        }
+    }
 
-                // TODO: this should be revamped along with `search_for_prefix`
-                let i = self.edges.binary_search_by(|e| e.prefix.as_slice().cmp(key)).unwrap_err();
-                self.edges.insert(i, new_edge);
+    fn walk<'a, F>(&'a self, key: &mut Vec<K>, min_len: usize, max_len: usize, f: &mut F) -> bool
+        where F: FnMut(&[K], &'a V) -> bool,
+    {
+        if let Some(ref value) = self.value {
+            if key.len() >= min_len && !f(key, value) {
+                return false;
+            }
+        }
+
+        for edge in self.edges.iter() {
+            // all the keys below the edge are too short or too long
+            if !edge.node.has_lengths_in(key.len() + edge.prefix.len(), min_len, max_len) {
+                continue;
+            }
+
+            key.add_suffix(&edge.prefix);
+            let more = edge.node.walk(key, min_len, max_len, f);
+            key.truncate(key.len() - edge.prefix.len());
+
+            if !more {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn visit_entries<E, F>(&self, key: &mut Vec<K>, shared: &mut usize, f: &mut F) -> Result<(), E>
+        where F: FnMut(&[K], usize, &V) -> Result<(), E>,
+    {
+        if let Some(ref value) = self.value {
+            f(key, *shared, value)?;
+            *shared = key.len();
+        }
+
+        for edge in self.edges.iter() {
+            key.add_suffix(&edge.prefix);
+            edge.node.visit_entries(key, shared, f)?;
+            key.truncate(key.len() - edge.prefix.len());
+            *shared = (*shared).min(key.len());
+        }
+
+        Ok(())
+    }
+
+    fn find_by<'a, S, F, C>(&'a self, prefix: &mut Vec<K>, state: S, step: &F, complete: &C,
+                            matches: &mut Vec<Matches<'a, K, V>>)
+        where S: Clone,
+              F: Fn(&mut S, &K) -> bool,
+              C: Fn(&S) -> bool,
+    {
+        if complete(&state) {
+            matches.push(Matches::found(prefix.clone(), self));
+            return;
+        }
+
+        #[cfg(feature = "tracing")]
+        self.trace_scan(prefix.len());
+
+        'edges: for edge in self.edges.iter() {
+            let mut state = state.clone();
+            for (i, component) in edge.prefix.iter().enumerate() {
+                if !step(&mut state, component) {
+                    continue 'edges;
+                }
+
+                // matched in the middle of the edge, the whole subtree below matches
+                if i + 1 < edge.prefix.len() && complete(&state) {
+                    matches.push(Matches::found(prefix.clone().with_suffix(&edge.prefix), &edge.node));
+                    continue 'edges;
+                }
             }
 
-            None
+            prefix.add_suffix(&edge.prefix);
+            edge.node.find_by(prefix, state, step, complete, matches);
+            prefix.truncate(prefix.len() - edge.prefix.len());
+        }
+    }
+
+    fn map_values<W, F>(self, f: &mut F) -> Node<K, W>
+        where F: FnMut(V) -> W,
+    {
+        let Node { value, count, shortest, longest, sum, firsts, edges } = self;
+        let value = value.map(&mut *f);
+        let edges = edges.into_iter()
+            .map(|Edge { prefix, node }| Edge { prefix, node: node.map_values(f) })
+            .collect();
+
+        Node { value, count, shortest, longest, sum, firsts, edges }
+    }
+
+    fn find_pattern<'a>(&'a self, pattern: &[Option<K>], prefix: &mut Vec<K>,
+                        entries: &mut Vec<(Vec<K>, &'a V)>) {
+        let edges = match pattern.first() {
+            None => {
+                if let Some(ref value) = self.value {
+                    entries.push((prefix.clone(), value));
+                }
+                return;
+            }
+            Some(Some(first)) => match self.dispatch(first) {
+                Ok(i)  => &self.edges[i..i + 1],
+                Err(_) => return,
+            },
+            Some(None) => {
+                #[cfg(feature = "tracing")]
+                self.trace_scan(prefix.len());
+
+                &self.edges[..]
+            }
+        };
+
+        for edge in edges.iter() {
+            let len = edge.prefix.len();
+            if len > pattern.len() {
+                continue;
+            }
+
+            let matches = edge.prefix.iter().zip(pattern.iter())
+                .all(|(c, p)| p.as_ref().is_none_or(|p| p == c));
+            if matches {
+                prefix.add_suffix(&edge.prefix);
+                edge.node.find_pattern(&pattern[len..], prefix, entries);
+                prefix.truncate(prefix.len() - len);
+            }
+        }
+    }
+
+    fn group_count<F>(&self, len: usize, prefix: &mut Vec<K>, groups: &mut Vec<(Vec<K>, usize)>, continues: &F)
+        where F: Fn(&K) -> bool,
+    {
+        let depth = prefix.len();
+        if depth >= len {
+            // the prefix was extended down to this node, the keys ending here and those not
+            // continuing it are cut here
+            let count = self.edges.iter()
+                .filter(|edge| !continues(&edge.prefix[0]))
+                .fold(self.value.is_some() as usize, |count, edge| count + edge.node.count);
+            if count > 0 {
+                groups.push((prefix.clone(), count));
+            }
+        }
+
+        for edge in self.edges.iter() {
+            if depth + edge.prefix.len() < len {
+                prefix.add_suffix(&edge.prefix);
+                edge.node.group_count(len, prefix, groups, continues);
+                prefix.truncate(depth);
+                continue;
+            }
+
+            match (len.saturating_sub(depth)..edge.prefix.len()).find(|&i| !continues(&edge.prefix[i])) {
+                // counted with the keys ending at this node
+                Some(0)   => {},
+                // all the keys below this edge share the same prefix
+                Some(cut) => {
+                    let group = prefix.clone().with_suffix(&edge.prefix[..cut]);
+                    groups.push((group, edge.node.count));
+                }
+                // the prefix goes on below this edge
+                None      => {
+                    prefix.add_suffix(&edge.prefix);
+                    edge.node.group_count(prefix.len(), prefix, groups, continues);
+                    prefix.truncate(depth);
+                }
+            }
+        }
+    }
+
+    fn dump<W, F>(&self, w: &mut W, depth: usize, fmt_label: &F) -> fmt::Result
+        where W: fmt::Write,
+              F: Fn(&[K], &mut W) -> fmt::Result,
+    {
+        if self.value.is_some() {
+            write!(w, " *")?;
+        }
+        writeln!(w, " ({})", self.count)?;
+
+        for edge in self.edges.iter() {
+            write!(w, "{:1$}", "", 2 * (depth + 1))?;
+            fmt_label(&edge.prefix, w)?;
+            edge.node.dump(w, depth + 1, fmt_label)?;
         }
-    }
 
-    fn iter(&self) -> Iter<K, V> {
-        Iter::new(self)
+        Ok(())
     }
 
-    fn remove(&mut self, key: &[K]) -> Option<V> {
-        if key.is_empty() {
-            self.value.take()
-        } else if let Some((i, cmp)) = self.search_for_prefix(key) {
-            match cmp {
-                PrefixCmp::Full(suffix) => {
-                    let ret = self.edges[i].node.remove(&suffix);
-
-                    if self.edges[i].node.is_empty() {
-                        self.edges.remove(i);
-                    }
+    fn check(&self) -> Result<(), String> {
+        if self.firsts.len() != self.edges.len() {
+            return Err(format!("{} first components for {} edges", self.firsts.len(), self.edges.len()));
+        }
 
-                    ret
-                },
-                PrefixCmp::Partial(_) => None,
+        for (i, edge) in self.edges.iter().enumerate() {
+            if edge.prefix.is_empty() {
+                return Err(format!("edge {} has an empty label", i));
             }
-        } else {
-            None
+            if edge.prefix[0] != self.firsts[i] {
+                return Err(format!("first component of edge {} is out of sync", i));
+            }
+            if edge.node.is_empty() {
+                return Err(format!("edge {} leads to an empty node", i));
+            }
+            edge.node.check()?;
+        }
+
+        if !self.firsts.windows(2).all(|w| w[0] < w[1]) {
+            return Err("edges aren't sorted by their first component".to_string());
         }
-    }
 
-    fn find<'a>(&'a self, key: &[K]) -> Matches<'a, K, V> {
-        self.find_subtree(key, Vec::new())
+        let count = self.value.is_some() as usize + self.edges.iter().map(|e| e.node.count).sum::<usize>();
+        if self.count != count {
+            return Err(format!("count is {} instead of {}", self.count, count));
+        }
+
+        let lengths = self.lengths_from_children();
+        if (self.shortest, self.longest) != lengths {
+            return Err(format!("key lengths are {:?} instead of {:?}", (self.shortest, self.longest), lengths));
+        }
+
+        Ok(())
     }
 
-    fn find_subtree<'a>(&'a self, key: &[K], prefix: Vec<K>) -> Matches<'a, K, V> {
+    fn find_subtree(&self, key: &[K], prefix: Vec<K>) -> Option<(Vec<K>, &Node<K, V>)> {
         if key.is_empty() {
-            Matches::found(prefix, self)
+            Some((prefix, self))
         } else if let Some((i, cmp)) = self.search_for_prefix(key) {
             let (key_prefix, key_suffix) = match cmp {
                 PrefixCmp::Full(suffix) => {
@@ -208,71 +1650,194 @@ impl<K: KeyComponent, V> Node<K, V> {
                     (&key[..key.len() - suffix_len], suffix)
                 }
 
-                PrefixCmp::Partial(_) => {
-                    let suffix = self.edges[i].prefix.as_slice();
+                // the key ends inside the edge, its whole subtree matches
+                PrefixCmp::Partial(n) if n == key.len() => {
+                    let suffix = &self.edges[i].prefix[..];
                     (suffix, Cow::default())
                 }
+
+                // the key diverges from the edge, no key of the subtree starts with it
+                PrefixCmp::Partial(_) => return None,
             };
 
+            #[cfg(feature = "tracing")]
+            count_node();
             self.edges[i].node.find_subtree(&key_suffix, prefix.with_suffix(key_prefix))
         } else {
-            Matches::none()
+            None
+        }
+    }
+
+    fn find_subtree_mut(&mut self, key: &[K], prefix: Vec<K>) -> Option<(Vec<K>, &mut Node<K, V>)> {
+        if key.is_empty() {
+            return Some((prefix, self));
         }
+
+        let (i, suffix) = match self.search_for_prefix(key)? {
+            (i, PrefixCmp::Full(suffix)) => (i, suffix),
+            // the key ends inside the edge, its whole subtree matches
+            (i, PrefixCmp::Partial(n)) if n == key.len() => (i, Cow::default()),
+            _ => return None,
+        };
+
+        let edge = &mut self.edges[i];
+        edge.node.find_subtree_mut(&suffix, prefix.with_suffix(&edge.prefix))
+    }
+
+    fn visit_postorder<A, F>(&self, prefix: &mut Vec<K>, f: &mut F) -> A
+        where F: FnMut(Option<(&[K], &V)>, Vec<A>) -> A,
+    {
+        let children = self.edges.iter()
+            .map(|e| {
+                prefix.add_suffix(&e.prefix);
+                let child = e.node.visit_postorder(prefix, f);
+                prefix.truncate(prefix.len() - e.prefix.len());
+                child
+            })
+            .collect();
+
+        f(self.value.as_ref().map(|v| (prefix.as_slice(), v)), children)
     }
 
     fn search_for_prefix<'a>(&self, key: &'a [K]) -> Option<(usize, PrefixCmp<'a, K>)> {
-        self.edges.iter()
-            .enumerate()
-            .flat_map(|(i, e)| cmp_prefix(&e.prefix, key).map(|cmp| (i, cmp)))
-            .next()
+        key.first()
+            .and_then(|first| self.dispatch(first).ok())
+            .and_then(|i| cmp_prefix(&self.edges[i].prefix, key).map(|cmp| (i, cmp)))
+    }
+
+    /// Find the edge starting with the given component.
+    ///
+    /// Edges are sorted by prefix and no two edges of a node share the same first component, so
+    /// this is a binary search on the first component of each edge, the full prefixes only need to
+    /// be compared on the dispatched edge. Returns the position where such an edge should be
+    /// inserted if there is none.
+    ///
+    /// Leaves and chains of single edges, which make up most of the nodes of small trees, are
+    /// dispatched on without searching.
+    fn dispatch(&self, first: &K) -> Result<usize, usize> {
+        match *self.firsts {
+            [] => Err(0),
+            [ref only] => match only.cmp(first) {
+                Ordering::Equal   => Ok(0),
+                Ordering::Less    => Err(1),
+                Ordering::Greater => Err(0),
+            },
+            _ => self.firsts.binary_search(first),
+        }
+    }
+
+    /// Report scanning all the edges of this node, if it has more than `WIDE_SCAN`.
+    #[cfg(feature = "tracing")]
+    fn trace_scan(&self, depth: usize) {
+        if self.edges.len() > WIDE_SCAN {
+            tracing::debug!(fan_out = self.edges.len(), depth, "scanning a wide node");
+        }
+    }
+
+    fn insert_edge(&mut self, i: usize, edge: Edge<K, V>) {
+        self.firsts.insert(i, edge.prefix[0].clone());
+        self.edges.insert(i, edge);
     }
+
+    fn remove_edge(&mut self, i: usize) -> Edge<K, V> {
+        self.firsts.remove(i);
+        self.edges.remove(i)
+    }
+}
+
+/// What a call to `apply` did to the entry of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Kept,
+    Inserted,
+    Replaced,
+    Removed,
 }
 
 struct Edge<K: KeyComponent, V> {
-    prefix: Vec<K>,
+    prefix: Label<K>,
     node: Node<K, V>,
 }
 
 impl<K: KeyComponent, V> Edge<K, V> {
-    fn new(prefix: Vec<K>, value: Option<V>) -> Edge<K, V> {
+    fn new(prefix: &[K], value: Option<V>) -> Edge<K, V> {
         let mut node = Node::new();
+        node.count = value.is_some() as usize;
         node.value = value;
 
-        Edge { prefix, node }
+        Edge { prefix: Label::new(prefix), node }
     }
 
     fn split_insert(&mut self, i: usize, key: &[K], value: V) {
-        let (prefix, (key_suffix, edge_suffix)) = {
-            let (prefix, key_suffix) = key.split_at(i);
-            let (_, edge_suffix) = self.prefix.split_at(i);
+        self.split(i);
 
-            (prefix.to_owned(), (key_suffix.to_owned(), edge_suffix.to_owned()))
-        };
+        // update the parent edge: if the key is contained in the existing prefix, then it should
+        // be exactly equal to the prefix of the parent edge, hence the parent edge's value should
+        // be updated with the value we're trying to insert
+        let key_suffix = &key[i..];
+        if !key_suffix.is_empty() {
+            let j = self.node.dispatch(&key_suffix[0]).unwrap_err();
+            self.node.insert_edge(j, Edge::new(key_suffix, Some(value)));
+        } else {
+            self.node.value = Some(value);
+        }
+        self.node.count += 1;
+        self.node.update_lengths();
+    }
 
-        // assign the new prefix
+    /// Split the edge after its `i` first components, moving its node under a new one.
+    fn split(&mut self, i: usize) {
+        let (prefix, edge_suffix) = (Label::new(&self.prefix[..i]), Label::new(&self.prefix[i..]));
         self.prefix = prefix;
 
-        // move out the node's value for future use
-        let moved_value = self.node.value.take();
+        let moved_node = mem::replace(&mut self.node, Node::new());
+        self.node.count = moved_node.count;
+        self.node.sum = moved_node.sum;
+        self.node.insert_edge(0, Edge { prefix: edge_suffix, node: moved_node });
+        self.node.update_lengths();
+    }
+
+    /// Merge the node below the edge into it if the node only holds an edge.
+    fn merge_child(&mut self) {
+        if self.node.value.is_some() || self.node.edges.len() != 1 {
+            return;
+        }
 
-        // swap the old and new node's edges
-        let mut new_edges = Vec::with_capacity(2);
-        mem::swap(&mut self.node.edges, &mut new_edges);
+        let child = self.node.remove_edge(0);
+        let mut prefix = self.prefix.to_vec();
+        prefix.extend_from_slice(&child.prefix);
+        self.prefix = Label::new(&prefix);
+        self.node = child.node;
+    }
+}
 
-        let mut moved_edge = Edge::new(edge_suffix, moved_value);
-        moved_edge.node.edges = new_edges;
+/// The label of an edge, i.e. the components of the key that are consumed when following it.
+///
+/// Most edges of a dense tree only hold a single component, so these are stored inline instead
+/// of requiring a heap allocation each.
+enum Label<K> {
+    Inline(K),
+    Boxed(Box<[K]>),
+}
 
-        // update the parent edge: if the key is contained in the existing prefix, then it should
-        // be exactly equal to the prefix of the parent edge, hence the parent edge's value should
-        // be updated with the value we're trying to insert
-        self.node.edges.push(moved_edge);
-        if !key_suffix.is_empty() {
-            self.node.edges.push(Edge::new(key_suffix, Some(value)));
+impl<K: Clone> Label<K> {
+    fn new(components: &[K]) -> Label<K> {
+        if components.len() == 1 {
+            Label::Inline(components[0].clone())
         } else {
-            self.node.value = Some(value);
+            Label::Boxed(components.into())
+        }
+    }
+}
+
+impl<K> Deref for Label<K> {
+    type Target = [K];
+
+    fn deref(&self) -> &[K] {
+        match *self {
+            Label::Inline(ref component) => slice::from_ref(component),
+            Label::Boxed(ref components) => components,
         }
-        // finally, make sure the edges are sorted by prefix
-        self.node.edges.sort_by(|a, b| a.prefix.cmp(&b.prefix));
     }
 }
 
@@ -285,6 +1850,8 @@ fn cmp_prefix<'a, K: KeyComponent>(haystack: &[K], needle: &'a [K]) -> Option<Pr
     let nb = haystack.iter().zip(needle.iter())
         .take_while(|&(a, b)| a == b)
         .count();
+    #[cfg(feature = "complexity")]
+    ::complexity::count_edge(nb);
     if nb == 0 {
         None
     } else if nb < haystack.len() {
@@ -300,6 +1867,9 @@ fn cmp_prefix<'a, K: KeyComponent>(haystack: &[K], needle: &'a [K]) -> Option<Pr
 pub struct Iter<'a, K: 'a + KeyComponent, V: 'a> {
     path: Vec<IterPath<'a, K, V>>,
     prefix: Vec<K>,
+    remaining: usize,
+    #[cfg(debug_assertions)]
+    last: Option<Vec<K>>,
 }
 
 impl<'a, K: KeyComponent, V: 'a> Iter<'a, K, V> {
@@ -307,19 +1877,87 @@ impl<'a, K: KeyComponent, V: 'a> Iter<'a, K, V> {
         Iter {
             path: vec![IterPath::from_node(node)],
             prefix: Vec::new(),
+            remaining: node.count,
+            #[cfg(debug_assertions)]
+            last: None,
         }
     }
-}
 
-impl<'a, K: KeyComponent, V: 'a> Iterator for Iter<'a, K, V> {
-    type Item = (Vec<K>, &'a V);
+    /// Make an iterator starting at the first key that doesn't sort before `start`.
+    fn seek(node: &'a Node<K, V>, start: &[K]) -> Iter<'a, K, V> {
+        let mut iter = Iter::new(node);
+        let (mut node, mut rest) = (node, start);
 
-    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(first) = rest.first() {
+            // the value of the node sorts before `start`, and so do the edges before `i`
+            let i = match node.dispatch(first) {
+                Ok(i)  => i,
+                Err(i) => i,
+            };
+            let mut skipped = node.value.is_some() as usize;
+            skipped += node.edges[..i].iter().map(|e| e.node.count).sum::<usize>();
+
+            let edge = match node.edges.get(i) {
+                Some(edge) if edge.prefix[0] == *first => edge,
+                _ => {
+                    iter.skip_to(&node.edges[i..], skipped);
+                    break;
+                },
+            };
+
+            if rest.starts_with(&edge.prefix) {
+                iter.skip_to(&node.edges[i + 1..], skipped);
+                iter.prefix.add_suffix(&edge.prefix);
+                iter.path.push(IterPath::from_edge(edge));
+                rest = &rest[edge.prefix.len()..];
+                node = &edge.node;
+            } else {
+                // the keys below the edge all sort either before or after `start`
+                if edge.prefix[..] < *rest {
+                    iter.skip_to(&node.edges[i + 1..], skipped + edge.node.count);
+                } else {
+                    iter.skip_to(&node.edges[i..], skipped);
+                }
+                break;
+            }
+        }
+
+        iter
+    }
+
+    /// Skip the rest of the node on top of the path, up to the given edges.
+    fn skip_to(&mut self, edges: &'a [Edge<K, V>], skipped: usize) {
+        self.path.last_mut().unwrap().edge_iter = Some(edges.iter());
+        self.remaining -= skipped;
+    }
+
+    /// Check that keys are yielded in strictly increasing order, in debug builds.
+    #[cfg(debug_assertions)]
+    fn assert_sorted(&mut self) {
+        match self.last {
+            Some(ref mut last) => {
+                assert!(*last < self.prefix, "keys should be iterated in increasing order");
+                last.clear();
+                last.extend_from_slice(&self.prefix);
+            }
+            None => self.last = Some(self.prefix.clone()),
+        }
+    }
+}
+
+impl<'a, K: KeyComponent, V: 'a> Iter<'a, K, V> {
+    /// Advances the iterator, lending the key from the iterator's internal buffer instead of
+    /// cloning it.
+    pub fn next_raw(&mut self) -> Option<(&[K], &'a V)> {
         while !self.path.is_empty() {
             if let Some(adv) = self.path.last_mut().unwrap().advance() {
                 match adv {
                     Ok(value) => {
-                        return Some((self.prefix.clone(), value));
+                        #[cfg(debug_assertions)]
+                        self.assert_sorted();
+
+                        self.remaining -= 1;
+                        return Some((&self.prefix, value));
                     },
                     Err(elem) => {
                         self.prefix.add_suffix(&elem.prefix);
@@ -334,10 +1972,23 @@ impl<'a, K: KeyComponent, V: 'a> Iterator for Iter<'a, K, V> {
         }
 
         None
+    }
+}
 
+impl<'a, K: KeyComponent, V: 'a> Iterator for Iter<'a, K, V> {
+    type Item = (Vec<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_raw().map(|(k, v)| (k.to_owned(), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
+impl<'a, K: KeyComponent, V: 'a> ExactSizeIterator for Iter<'a, K, V> {}
+
 struct IterPath<'a, K: 'a + KeyComponent, V: 'a> {
     node: &'a Node<K, V>,
     edge_iter: Option<slice::Iter<'a, Edge<K, V>>>,
@@ -361,48 +2012,412 @@ impl<'a, K: 'a + KeyComponent, V: 'a> IterPath<'a, K, V> {
         }
     }
 
-    /// Returns None if there are no more elements to yield under this node, otherwise return
-    /// Ok(value) if there is a value to yield, or Err(new_elem) if there is an underlying
-    /// element to consider.
-    fn advance(&mut self) -> Option<Result<&'a V, IterPath<'a, K, V>>> {
-        if self.edge_iter.is_none() {
-            self.edge_iter = Some(self.node.edges.iter());
-            if let Some(ref value) = self.node.value {
-                return Some(Ok(value));
+    /// Returns None if there are no more elements to yield under this node, otherwise return
+    /// Ok(value) if there is a value to yield, or Err(new_elem) if there is an underlying
+    /// element to consider.
+    fn advance(&mut self) -> Option<Result<&'a V, IterPath<'a, K, V>>> {
+        if self.edge_iter.is_none() {
+            self.edge_iter = Some(self.node.edges.iter());
+            if let Some(ref value) = self.node.value {
+                return Some(Ok(value));
+            }
+        }
+
+        self.edge_iter.as_mut().unwrap().next()
+            .map(IterPath::from_edge)
+            .map(Err)
+    }
+}
+
+pub struct IterMut<'a, K: 'a + KeyComponent, V: 'a> {
+    // the edges left to visit at each node of the path, with the length of the label leading to it
+    path: Vec<(usize, slice::IterMut<'a, Edge<K, V>>)>,
+    prefix: Vec<K>,
+    // the value of the root, which comes first
+    first: Option<&'a mut V>,
+    remaining: usize,
+}
+
+impl<'a, K: KeyComponent, V: 'a> IterMut<'a, K, V> {
+    fn new(prefix: Vec<K>, node: &'a mut Node<K, V>) -> IterMut<'a, K, V> {
+        IterMut {
+            remaining: node.count,
+            first: node.value.as_mut(),
+            path: vec![(0, node.edges.iter_mut())],
+            prefix,
+        }
+    }
+
+    fn empty() -> IterMut<'a, K, V> {
+        IterMut {
+            remaining: 0,
+            first: None,
+            path: Vec::new(),
+            prefix: Vec::new(),
+        }
+    }
+
+    /// Advances the iterator, lending the key from the iterator's internal buffer instead of
+    /// cloning it.
+    pub fn next_raw(&mut self) -> Option<(&[K], &'a mut V)> {
+        if let Some(value) = self.first.take() {
+            self.remaining -= 1;
+            return Some((&self.prefix, value));
+        }
+
+        loop {
+            let edge = match self.path.last_mut()?.1.next() {
+                Some(edge) => edge,
+                None => {
+                    let (label_len, _) = self.path.pop().unwrap();
+                    let len = self.prefix.len() - label_len;
+                    self.prefix.truncate(len);
+                    continue;
+                },
+            };
+
+            let Edge { ref prefix, ref mut node } = *edge;
+            let Node { ref mut value, ref mut edges, .. } = *node;
+            self.prefix.add_suffix(prefix);
+            self.path.push((prefix.len(), edges.iter_mut()));
+
+            if let Some(value) = value.as_mut() {
+                self.remaining -= 1;
+                return Some((&self.prefix, value));
+            }
+        }
+    }
+}
+
+impl<'a, K: KeyComponent, V: 'a> Iterator for IterMut<'a, K, V> {
+    type Item = (Vec<K>, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_raw().map(|(k, v)| (k.to_owned(), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: KeyComponent, V: 'a> ExactSizeIterator for IterMut<'a, K, V> {}
+
+/// A pass taking the values for which a predicate returns `true` out of their nodes, in key
+/// order. Like `Tree::removing`, it leaves the counts and lengths of the nodes alone, and only
+/// repairs the tree once dropped, be it exhausted or not, or dropped by a panic of the predicate.
+pub struct ExtractIf<'a, K: 'a + KeyComponent, V: 'a, F> {
+    tree: &'a mut Tree<K, V>,
+    pred: F,
+    // the edges taken from the root to the current node, with the lengths of their labels
+    path: Vec<(usize, usize)>,
+    // the next edge to visit from the current node, `None` until its value was visited
+    next_edge: Option<usize>,
+    key: Vec<K>,
+    removed: usize,
+}
+
+impl<'a, K: KeyComponent, V: 'a, F> ExtractIf<'a, K, V, F>
+    where F: FnMut(&[K], &mut V) -> bool,
+{
+    /// Advances the pass, lending the key from its internal buffer instead of cloning it.
+    pub fn next_raw(&mut self) -> Option<(&[K], V)> {
+        loop {
+            // the structure doesn't change during the pass, so the path stays valid
+            let mut node = &mut self.tree.root;
+            for &(i, _) in self.path.iter() {
+                node = &mut node.edges[i].node;
+            }
+
+            match self.next_edge {
+                None => {
+                    self.next_edge = Some(0);
+                    let extracted = match node.value {
+                        Some(ref mut value) => (self.pred)(&self.key, value),
+                        None                => false,
+                    };
+                    if extracted {
+                        self.removed += 1;
+                        let value = node.value.take().unwrap();
+                        return Some((&self.key, value));
+                    }
+                },
+                Some(i) if i < node.edges.len() => {
+                    let label = &node.edges[i].prefix;
+                    self.key.extend_from_slice(label);
+                    self.path.push((i, label.len()));
+                    self.next_edge = None;
+                },
+                Some(_) => {
+                    let (i, label_len) = self.path.pop()?;
+                    let len = self.key.len() - label_len;
+                    self.key.truncate(len);
+                    self.next_edge = Some(i + 1);
+                },
+            }
+        }
+    }
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a, F> Drop for ExtractIf<'a, K, V, F> {
+    fn drop(&mut self) {
+        if self.removed > 0 {
+            self.tree.root.repair();
+            self.tree.removals += self.removed as u64;
+        }
+    }
+}
+
+pub struct Matches<'a, K: 'a + KeyComponent, V: 'a> {
+    // buffer holding the key being yielded, which always starts with the matched prefix
+    key: Vec<K>,
+    prefix_len: usize,
+    total: usize,
+    node: Option<&'a Node<K, V>>,
+    iter: Option<Iter<'a, K, V>>,
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> Matches<'a, K, V> {
+    fn found(prefix: Vec<K>, node: &'a Node<K, V>) -> Matches<'a, K, V> {
+        Matches {
+            prefix_len: prefix.len(),
+            key: prefix,
+            total: node.count,
+            node: Some(node),
+            iter: Some(node.iter()),
+        }
+    }
+
+    fn none() -> Matches<'a, K, V> {
+        Matches {
+            key: Vec::new(),
+            prefix_len: 0,
+            total: 0,
+            node: None,
+            iter: None,
+        }
+    }
+
+    /// Get the match of the given rank, including those already yielded, descending the matched
+    /// subtree again.
+    pub fn select(&self, n: usize) -> Option<(Vec<K>, &'a V)> {
+        self.node?.select(n, self.matched_prefix().to_vec())
+    }
+
+    /// The path to the root of the matched subtree, which may be longer than the key searched.
+    pub fn matched_prefix(&self) -> &[K] {
+        &self.key[..self.prefix_len]
+    }
+
+    /// The number of matches, including those already yielded.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Advances the iterator, lending the full key from an internal buffer instead of building a
+    /// new one.
+    pub fn next_raw(&mut self) -> Option<(&[K], &'a V)> {
+        let (suffix, value) = self.iter.as_mut()?.next_raw()?;
+        self.key.truncate(self.prefix_len);
+        self.key.extend_from_slice(suffix);
+        Some((&self.key, value))
+    }
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> Iterator for Matches<'a, K, V> {
+    type Item = (Vec<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_raw().map(|(k, v)| (k.to_owned(), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.as_ref().map_or((0, Some(0)), |it| it.size_hint())
+    }
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> ExactSizeIterator for Matches<'a, K, V> {}
+
+/// An iterator over the values stored on the path to a key, along with the length of their key.
+pub struct Ancestors<'a, 'k, K: 'a + 'k + KeyComponent, V: 'a> {
+    node: Option<&'a Node<K, V>>,
+    key: &'k [K],
+    depth: usize,
+}
+
+impl<'a, 'k, K: 'a + 'k + KeyComponent, V: 'a> Iterator for Ancestors<'a, 'k, K, V> {
+    type Item = (usize, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.node.take()?;
+            let depth = self.depth;
+
+            let rest = &self.key[depth..];
+            if let Some(first) = rest.first() {
+                if let Ok(i) = node.dispatch(first) {
+                    let edge = &node.edges[i];
+                    if rest.starts_with(&edge.prefix) {
+                        self.node = Some(&edge.node);
+                        self.depth += edge.prefix.len();
+                    }
+                }
+            }
+
+            if let Some(ref value) = node.value {
+                return Some((depth, value));
+            }
+        }
+    }
+}
+
+/// A position in the tree reached by descending it one component at a time, which can move back
+/// up to any of its previous positions.
+pub struct Cursor<'a, K: 'a + KeyComponent, V: 'a> {
+    root: &'a Node<K, V>,
+    key: Vec<K>,
+    // position after each component of the key, None once the key left the tree
+    path: Vec<Option<Position<'a, K, V>>>,
+}
+
+struct Position<'a, K: 'a + KeyComponent, V: 'a> {
+    node: &'a Node<K, V>,
+    // when the offset isn't 0, the position is inside the label of an edge of the node
+    edge: usize,
+    offset: usize,
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> Clone for Position<'a, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> Copy for Position<'a, K, V> {}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> Position<'a, K, V> {
+    fn at(node: &'a Node<K, V>) -> Position<'a, K, V> {
+        Position { node, edge: 0, offset: 0 }
+    }
+
+    fn step(self, component: &K) -> Option<Position<'a, K, V>> {
+        let (edge, offset) = if self.offset == 0 {
+            (self.node.dispatch(component).ok()?, 0)
+        } else {
+            (self.edge, self.offset)
+        };
+
+        let e = &self.node.edges[edge];
+        if e.prefix[offset] != *component {
+            return None;
+        }
+
+        if offset + 1 == e.prefix.len() {
+            Some(Position::at(&e.node))
+        } else {
+            Some(Position { node: self.node, edge, offset: offset + 1 })
+        }
+    }
+
+    fn value(self) -> Option<&'a V> {
+        if self.offset == 0 {
+            self.node.value.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Add to `cover` the smallest set of prefixes covering the keys starting with `key`, except
+    /// those in the tree below the position, splitting `key` by the components of `alphabet`.
+    fn complement(position: Option<Position<'a, K, V>>, key: &mut Vec<K>, alphabet: &[K], cover: &mut Tree<K, ()>) {
+        let position = match position {
+            Some(position) => position,
+            None => {
+                cover.insert(key, ());
+                return;
+            },
+        };
+        if position.value().is_some() {
+            return;
+        }
+
+        for component in alphabet {
+            key.push(component.clone());
+            Position::complement(position.step(component), key, alphabet, cover);
+            key.pop();
+        }
+    }
+}
+
+impl<'a, K: 'a + KeyComponent, V: 'a> Cursor<'a, K, V> {
+    fn position(&self) -> Option<Position<'a, K, V>> {
+        match self.path.last() {
+            Some(&position) => position,
+            None            => Some(Position::at(self.root)),
+        }
+    }
+
+    /// The components descended so far.
+    pub fn key(&self) -> &[K] {
+        &self.key
+    }
+
+    /// Descend by one component, returning `false` if no key starts with the new prefix.
+    pub fn push(&mut self, component: K) -> bool {
+        let position = self.position().and_then(|p| p.step(&component));
+        self.path.push(position);
+        self.key.push(component);
+        position.is_some()
+    }
+
+    /// Move back up by one component, returning it.
+    pub fn pop(&mut self) -> Option<K> {
+        self.path.pop();
+        self.key.pop()
+    }
+
+    /// Get the entries whose key starts with the components descended so far.
+    pub fn matches(&self) -> Matches<'a, K, V> {
+        match self.position() {
+            Some(p) if p.offset == 0 => Matches::found(self.key.clone(), p.node),
+            Some(p) => {
+                let edge = &p.node.edges[p.edge];
+                Matches::found(self.key.clone().with_suffix(&edge.prefix[p.offset..]), &edge.node)
             }
+            None => Matches::none(),
         }
-
-        self.edge_iter.as_mut().unwrap().next()
-            .map(IterPath::from_edge)
-            .map(Err)
     }
 }
 
-pub struct Matches<'a, K: 'a + KeyComponent, V: 'a> {
-    result: Option<(Vec<K>, Iter<'a, K, V>)>
+/// A position in the tree reached by descending it one component at a time, which unlike a
+/// `Cursor` can't move back up, but doesn't allocate.
+pub struct PrefixCursor<'a, K: 'a + KeyComponent, V: 'a> {
+    // None once the prefix left the tree
+    position: Option<Position<'a, K, V>>,
 }
 
-impl<'a, K: 'a + KeyComponent, V: 'a> Matches<'a, K, V> {
-    fn found(prefix: Vec<K>, node: &'a Node<K, V>) -> Matches<'a, K, V> {
-        Matches {
-            result: Some((prefix, node.iter())),
-        }
+impl<'a, K: 'a + KeyComponent, V: 'a> Clone for PrefixCursor<'a, K, V> {
+    fn clone(&self) -> Self {
+        PrefixCursor { position: self.position }
     }
+}
 
-    fn none() -> Matches<'a, K, V> {
-        Matches {
-            result: None,
-        }
+impl<'a, K: 'a + KeyComponent, V: 'a> PrefixCursor<'a, K, V> {
+    /// Descend by one component, returning `false` if no key starts with the new prefix.
+    pub fn push(&mut self, component: &K) -> bool {
+        self.position = self.position.and_then(|p| p.step(component));
+        self.is_live()
     }
-}
 
-impl<'a, K: 'a + KeyComponent, V: 'a> Iterator for Matches<'a, K, V> {
-    type Item = (Vec<K>, &'a V);
+    /// Check if some keys start with the components descended so far.
+    pub fn is_live(&self) -> bool {
+        // only the root may be an empty node
+        self.position.is_some_and(|p| p.offset != 0 || !p.node.is_empty())
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.result.as_mut().and_then(|&mut (ref prefix, ref mut it)| {
-            it.next().map(|(s, v)| (s.with_prefix(prefix), v))
-        })
+    /// Get the value of the key made of the components descended so far.
+    pub fn value(&self) -> Option<&'a V> {
+        self.position.and_then(|p| p.value())
     }
 }
 
@@ -499,7 +2514,7 @@ mod tests {
         }
 
         for &(key, value) in items.iter() {
-            assert_eq!(tree.get(key).map(|v| *v), Some(value));
+            assert_eq!(tree.get(key).copied(), Some(value));
         }
 
         let mut got_items: Vec<_> = tree.iter().map(|(k, v)| (k, *v)).collect();
@@ -511,6 +2526,54 @@ mod tests {
         assert_eq!(got_items, items);
     }
 
+    #[test]
+    fn it_can_iterate_on_mutable_items() {
+        let mut t: Tree<u8, usize> = Tree::new();
+        let keys: [&[u8]; 6] = [b"", b"b", b"abc", b"a", b"ab", b"abd"];
+        for (i, key) in keys.iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        let mut iter = t.iter_mut();
+        assert_eq!(iter.len(), 6);
+        while let Some((key, value)) = iter.next_raw() {
+            *value += key.len() * 10;
+        }
+        assert_eq!(iter.len(), 0);
+
+        let items: Vec<_> = t.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(items, vec![
+            (b"".to_vec(), 0),
+            (b"a".to_vec(), 13),
+            (b"ab".to_vec(), 24),
+            (b"abc".to_vec(), 32),
+            (b"abd".to_vec(), 35),
+            (b"b".to_vec(), 11),
+        ]);
+    }
+
+    #[test]
+    fn it_can_complete_a_prefix_mutably() {
+        let mut t: Tree<u8, u32> = Tree::new();
+        for &key in [&b"user:4"[..], b"user:42", b"user:42:a", b"user:42:b", b"user:43", b"users"].iter() {
+            t.insert(key, 0);
+        }
+
+        for (key, value) in t.find_mut(b"user:42") {
+            *value = key.len() as u32;
+        }
+        assert_eq!(t.find_mut(b"user:5").count(), 0);
+        assert_eq!(t.find_mut(b"user:42:c").count(), 0);
+        assert_eq!(t.find_mut(b"usex").count(), 0);
+        assert_eq!(t.find_mut(b"user:4").len(), 5);
+        for (_, value) in t.find_mut(b"use") {
+            *value += 1;
+        }
+
+        let values: Vec<_> = t.iter().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![1, 8, 10, 10, 1, 1]);
+    }
+
     #[test]
     fn it_can_complete_a_prefix() {
         let items: Vec<&'static [u8]> = vec![
@@ -547,6 +2610,24 @@ mod tests {
         assert_eq!(matches, vec![b"abc".to_owned()]);
     }
 
+    #[test]
+    fn it_does_not_complete_diverging_keys() {
+        let mut t = Tree::new();
+        t.insert(b"abc", 0);
+        t.insert(b"abcde", 1);
+
+        assert_eq!(t.find(b"abd").count(), 0);
+        assert_eq!(t.find(b"abcdx").count(), 0);
+        assert_eq!(t.find(b"abcdx").total(), 0);
+        assert!(t.find_first(b"abd").is_none());
+        assert!(t.select_in(b"abx", 0).is_none());
+
+        let matches = t.find(b"ab");
+        assert_eq!(matches.matched_prefix(), b"abc");
+        assert_eq!(matches.total(), 2);
+        assert_eq!(t.find(b"abcd").matched_prefix(), b"abcde");
+    }
+
     #[test]
     fn it_has_sorted_iterators() {
         let items: Vec<&'static [u8]> = vec![
@@ -565,6 +2646,669 @@ mod tests {
         assert_eq!(found, expected);
     }
 
+    #[test]
+    fn it_visits_children_before_their_parent() {
+        let mut t = Tree::new();
+        t.insert(b"a", 1);
+        t.insert(b"ab", 2);
+        t.insert(b"ac", 3);
+        t.insert(b"b", 4);
+
+        let mut visited = Vec::new();
+        let total = t.visit_postorder(|entry, children: Vec<i32>| {
+            let own = entry.map_or(0, |(k, v)| {
+                visited.push(k.to_owned());
+                *v
+            });
+            own + children.iter().sum::<i32>()
+        });
+
+        assert_eq!(total, 10);
+        assert_eq!(visited, vec![b"ab".to_vec(), b"ac".to_vec(), b"a".to_vec(), b"b".to_vec()]);
+    }
+
+    #[test]
+    fn it_handles_single_entries_and_chains() {
+        fn is_chain<K: super::KeyComponent, V>(node: &super::Node<K, V>) -> bool {
+            node.edges.len() <= 1 && node.edges.iter().all(|e| is_chain(&e.node))
+        }
+
+        let mut t = Tree::<u8, i32>::new();
+        t.insert(b"config", 1);
+        assert_eq!(t.get(b"config"), Some(&1));
+        for key in &[&b""[..], b"c", b"conf", b"configs", b"x", b"donfig"] {
+            assert_eq!(t.get(key), None);
+        }
+
+        // splitting the single edge makes a chain
+        t.insert(b"conf", 2);
+        t.insert(b"configuration", 3);
+        t.insert(b"", 0);
+        assert!(is_chain(&t.root));
+        assert_eq!((t.get(b""), t.get(b"conf"), t.get(b"configuration")), (Some(&0), Some(&2), Some(&3)));
+        for key in &[&b"co"[..], b"confi", b"confx", b"configurations", b"a"] {
+            assert_eq!(t.get(key), None);
+        }
+        t.check().unwrap();
+
+        // branching in the middle and at the root, before and after the single edges
+        t.insert(b"confetti", 4);
+        t.insert(b"b", 5);
+        t.insert(b"d", 6);
+        assert!(!is_chain(&t.root));
+        assert_eq!((t.get(b"confetti"), t.get(b"b"), t.get(b"d"), t.get(b"config")), (Some(&4), Some(&5), Some(&6), Some(&1)));
+        t.check().unwrap();
+
+        // and back to a single entry
+        for key in &[&b""[..], b"b", b"d", b"confetti", b"conf", b"configuration"] {
+            assert!(t.remove(key).is_some());
+        }
+        assert!(is_chain(&t.root));
+        assert_eq!(t.get(b"config"), Some(&1));
+        assert_eq!(t.get(b"conf"), None);
+        t.check().unwrap();
+    }
+
+    #[test]
+    fn it_dispatches_on_wide_nodes() {
+        let mut t = Tree::new();
+        for b in (0..=255u8).rev() {
+            t.insert(&[b, b], b);
+        }
+
+        for b in 0..=255u8 {
+            assert_eq!(t.get(&[b, b]), Some(&b));
+            assert_eq!(t.get(&[b]), None);
+            assert_eq!(t.find(&[b]).count(), 1);
+        }
+
+        let keys: Vec<_> = t.iter().map(|(k, _)| k).collect();
+        let expected: Vec<_> = (0..=255u8).map(|b| vec![b, b]).collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn it_does_not_allocate_single_component_labels() {
+        use std::mem;
+        use super::Label;
+
+        assert!(mem::size_of::<Label<u8>>() < mem::size_of::<Vec<u8>>());
+
+        let mut t = Tree::new();
+        t.insert(b"ab", 0);
+        t.insert(b"ac", 1);
+        t.insert(b"a", 2);
+
+        let edge = &t.root.edges[0];
+        assert!(matches!(edge.prefix, Label::Inline(b'a')));
+        assert!(edge.node.edges.iter().all(|e| matches!(e.prefix, Label::Inline(_))));
+        assert_eq!(t.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn it_keeps_first_components_in_sync_with_edges() {
+        use super::Node;
+
+        fn check<V>(node: &Node<u8, V>) {
+            let firsts: Vec<_> = node.edges.iter().map(|e| e.prefix[0]).collect();
+            assert_eq!(node.firsts, firsts);
+            assert!(node.firsts.windows(2).all(|w| w[0] < w[1]));
+            node.edges.iter().for_each(|e| check(&e.node));
+
+            let count = node.value.is_some() as usize + node.edges.iter().map(|e| e.node.count).sum::<usize>();
+            assert_eq!(node.count, count);
+        }
+
+        let mut t = Tree::new();
+        for key in [&b"romane"[..], b"romanus", b"romulus", b"rubens", b"ruber", b"rom", b"r"].iter() {
+            t.insert(key, ());
+            check(&t.root);
+        }
+        for key in [&b"romulus"[..], b"r", b"rubens", b"romane"].iter() {
+            t.remove(key);
+            check(&t.root);
+            assert_eq!(t.check(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn it_only_stores_a_flag_for_zero_sized_values() {
+        use std::mem;
+        use super::Node;
+
+        #[derive(Debug, PartialEq)]
+        struct Unit;
+
+        // the value of a zero-sized type takes no more room than the flag telling if the node
+        // holds a key, which nodes need anyway
+        assert_eq!(mem::size_of::<Option<()>>(), mem::size_of::<bool>());
+        assert_eq!(mem::size_of::<Node<u8, ()>>(), mem::size_of::<Node<u8, bool>>());
+        assert_eq!(mem::size_of::<Node<u8, Unit>>(), mem::size_of::<Node<u8, bool>>());
+
+        let mut t = Tree::new();
+        assert_eq!(t.insert(b"a", Unit), None);
+        assert_eq!(t.insert(b"ab", Unit), None);
+        assert_eq!(t.insert(b"a", Unit), Some(Unit));
+        assert_eq!(t.len(), 2);
+        assert_eq!(t.remove(b"a"), Some(Unit));
+        assert_eq!(t.get(b"ab"), Some(&Unit));
+    }
+
+    #[test]
+    fn it_swaps_values_in_place() {
+        let mut tree: Tree<u8, String> = Tree::new();
+        for &key in [&b""[..], b"a", b"ab", b"abc", b"b"].iter() {
+            tree.insert(key, String::from_utf8(key.to_vec()).unwrap());
+        }
+
+        assert!(tree.swap(b"a", b"b"));
+        assert!(tree.swap(b"ab", b"abc"));
+        assert!(tree.swap(b"", b"abc"));
+        assert!(tree.swap(b"a", b"a"));
+        assert_eq!(tree.get(b""), Some(&"ab".to_string()));
+        assert_eq!(tree.get(b"a"), Some(&"b".to_string()));
+        assert_eq!(tree.get(b"ab"), Some(&"abc".to_string()));
+        assert_eq!(tree.get(b"abc"), Some(&"".to_string()));
+        assert_eq!(tree.get(b"b"), Some(&"a".to_string()));
+
+        assert!(!tree.swap(b"a", b"c"));
+        assert!(!tree.swap(b"abcd", b""));
+        assert!(!tree.swap(b"x", b"x"));
+        assert_eq!(tree.get(b"a"), Some(&"b".to_string()));
+    }
+
+    #[test]
+    fn it_renames_keys() {
+        let mut tree: Tree<u8, u32> = Tree::new();
+        tree.insert(b"user:1", 1);
+        tree.insert(b"user:2", 2);
+        tree.insert(b"user:10", 10);
+
+        assert!(tree.rename_key(b"user:10", b"user:3"));
+        assert!(tree.rename_key(b"user:1", b"member:1"));
+        assert!(tree.rename_key(b"user:2", b"user:"));
+        assert!(!tree.rename_key(b"user:1", b"user:4"));
+        assert!(!tree.rename_key(b"user:3", b"member:1"));
+
+        let entries: Vec<_> = tree.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(entries, vec![
+            (b"member:1".to_vec(), 1),
+            (b"user:".to_vec(), 2),
+            (b"user:3".to_vec(), 10),
+        ]);
+        assert_eq!(tree.len(), 3);
+        assert!(tree.check().is_ok());
+    }
+
+    #[test]
+    fn it_rolls_up_prefixes() {
+        let mut tree: Tree<u8, u32> = Tree::new();
+        for &(key, value) in [(&b"2024-01-01T10"[..], 1), (b"2024-01-01T11", 2), (b"2024-01-02T09", 4),
+                              (b"2024-01-02", 8), (b"2024-02-01T00", 16)].iter() {
+            tree.insert(key, value);
+        }
+
+        assert_eq!(tree.rollup_prefix(b"2024-01-01", |a, b| a + b), 2);
+        assert_eq!(tree.rollup_prefix(b"2024-01-02", |a, b| a * 10 + b), 2);
+        assert_eq!(tree.rollup_prefix(b"2024-03", |a, b| a + b), 0);
+        assert_eq!(tree.rollup_prefix(b"2024-02-01T00", |a, b| a + b), 1);
+
+        let entries: Vec<_> = tree.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(entries, vec![
+            (b"2024-01-01".to_vec(), 3),
+            (b"2024-01-02".to_vec(), 84),
+            (b"2024-02-01T00".to_vec(), 16),
+        ]);
+        assert_eq!(tree.len(), 3);
+        assert!(tree.check().is_ok());
+
+        assert_eq!(tree.rollup_prefix(b"", |a, b| a + b), 3);
+        assert_eq!(tree.get(b""), Some(&103));
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn it_renews_the_generation_of_a_rollup_panicking_part_way() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut tree: Tree<u8, u32> = Tree::new();
+        for &key in [&b"ab"[..], b"abc", b"b"].iter() {
+            tree.insert(key, 1);
+        }
+
+        let generation = tree.generation();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            tree.rollup_prefix(b"a", |_, _| panic!("fold"))
+        }));
+        assert!(result.is_err());
+        assert_ne!(tree.generation(), generation);
+        assert_eq!(tree.len(), 1);
+        assert!(tree.check().is_ok());
+    }
+
+    #[test]
+    fn it_retains_and_prunes_in_a_single_pass() {
+        use super::Node;
+
+        fn is_compact<V>(node: &Node<u8, V>, is_root: bool) -> bool {
+            (is_root || node.value.is_some() || node.edges.len() != 1)
+                && node.edges.iter().all(|e| is_compact(&e.node, false))
+        }
+
+        let mut tree: Tree<u8, u32> = Tree::new();
+        for (i, &key) in [&b"a"[..], b"ab", b"abc", b"abcd", b"abd", b"b", b"ba", b"bb", b"c"].iter().enumerate() {
+            tree.insert(key, i as u32);
+        }
+
+        let mut kept = Vec::new();
+        let removed = tree.retain_and_prune(&[b"abc", b"b", b"cd"], |key, value| {
+            kept.push(key.to_vec());
+            *value *= 10;
+            key != b"ab"
+        });
+        assert_eq!(removed, 6);
+        assert_eq!(kept, vec![b"a".to_vec(), b"ab".to_vec(), b"abd".to_vec(), b"c".to_vec()]);
+
+        let entries: Vec<_> = tree.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(entries, vec![(b"a".to_vec(), 0), (b"abd".to_vec(), 40), (b"c".to_vec(), 80)]);
+        assert!(is_compact(&tree.root, true));
+        assert_eq!(tree.check(), Ok(()));
+
+        assert_eq!(tree.retain_and_prune(&[b""], |_, _| true), 3);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn it_finds_the_shortest_and_longest_keys() {
+        let mut tree: Tree<u8, u32> = Tree::new();
+        assert_eq!(tree.shortest_key(b""), None);
+        assert_eq!(tree.longest_key(b""), None);
+
+        for (i, &key) in [&b"abcd"[..], b"abce", b"ab", b"abd", b"b", b"bcdef"].iter().enumerate() {
+            tree.insert(key, i as u32);
+        }
+
+        assert_eq!(tree.shortest_key(b""), Some((b"b".to_vec(), &4)));
+        assert_eq!(tree.longest_key(b""), Some((b"bcdef".to_vec(), &5)));
+        assert_eq!(tree.shortest_key(b"a"), Some((b"ab".to_vec(), &2)));
+        assert_eq!(tree.longest_key(b"a"), Some((b"abcd".to_vec(), &0)));
+        assert_eq!(tree.shortest_key(b"abc"), Some((b"abcd".to_vec(), &0)));
+        assert_eq!(tree.longest_key(b"bc"), Some((b"bcdef".to_vec(), &5)));
+        assert_eq!(tree.shortest_key(b"c"), None);
+
+        tree.remove(b"b");
+        tree.remove(b"bcdef");
+        assert_eq!(tree.shortest_key(b""), Some((b"ab".to_vec(), &2)));
+        assert_eq!(tree.longest_key(b""), Some((b"abcd".to_vec(), &0)));
+    }
+
+    #[test]
+    fn it_detaches_subtrees_by_prefix() {
+        let mut tree: Tree<u8, u32> = Tree::new();
+        for (i, &key) in [&b"cache:1"[..], b"cache:10", b"cache:2", b"car", b"cat", b""].iter().enumerate() {
+            tree.insert(key, i as u32);
+        }
+
+        let removed = tree.remove_prefix(b"cache:1");
+        let entries: Vec<_> = removed.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(entries, vec![(b"cache:1".to_vec(), 0), (b"cache:10".to_vec(), 1)]);
+        assert_eq!(removed.check(), Ok(()));
+        assert_eq!(tree.check(), Ok(()));
+
+        // the prefix ends inside the label of an edge
+        let removed = tree.remove_prefix(b"cac");
+        assert_eq!(removed.iter().map(|(k, _)| k).collect::<Vec<_>>(), vec![b"cache:2".to_vec()]);
+        assert_eq!(removed.get(b"cache:2"), Some(&2));
+        assert_eq!(removed.check(), Ok(()));
+
+        assert!(tree.remove_prefix(b"cax").is_empty());
+        assert!(tree.remove_prefix(b"cart").is_empty());
+        assert_eq!(tree.len(), 3);
+
+        let removed = tree.remove_prefix(b"");
+        assert_eq!(removed.len(), 3);
+        assert_eq!(removed.check(), Ok(()));
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn it_grafts_appended_trees() {
+        let keys: [&[u8]; 10] = [b"", b"a", b"abc", b"abd", b"b", b"bcd", b"bcde", b"bx", b"c", b"cab"];
+        for split in 0..keys.len() {
+            for &(left, right) in &[(&keys[..split], &keys[split..]), (&keys[split..], &keys[..split])] {
+                let mut tree: Tree<u8, usize> = Tree::new();
+                let mut other = Tree::new();
+                for (i, &key) in left.iter().enumerate() {
+                    tree.insert(key, i);
+                }
+                for &key in right.iter() {
+                    other.insert(key, 100);
+                }
+                // overlapping keys take the value of the appended tree
+                other.insert(b"b", 200);
+
+                tree.append(other);
+                assert_eq!(tree.check(), Ok(()));
+                assert_eq!(tree.len(), keys.len());
+                assert_eq!(tree.iter().map(|(k, _)| k).collect::<Vec<_>>(), keys.iter().map(|k| k.to_vec()).collect::<Vec<_>>());
+                assert_eq!(tree.get(b"b"), Some(&200));
+            }
+        }
+    }
+
+    #[test]
+    fn it_iterates_over_ranges() {
+        let keys: [&[u8]; 8] = [b"", b"a", b"ab", b"abc", b"abd", b"b", b"ba", b"c"];
+        let mut tree: Tree<u8, ()> = Tree::new();
+        for key in keys.iter() {
+            tree.insert(key, ());
+        }
+
+        let bounds: [&[u8]; 10] = [b"", b"a", b"aa", b"ab", b"abc", b"abcd", b"abz", b"b", b"bz", b"d"];
+        for start in bounds.iter() {
+            for end in bounds.iter() {
+                let range = tree.range(start, end);
+                let expected: Vec<_> = keys.iter()
+                    .filter(|k| start <= *k && *k < end)
+                    .map(|k| k.to_vec())
+                    .collect();
+
+                assert_eq!(range.len(), expected.len());
+                assert_eq!(range.map(|(k, _)| k).collect::<Vec<_>>(), expected, "{:?}..{:?}", start, end);
+            }
+        }
+    }
+
+    #[test]
+    fn it_descends_along_rewritten_components() {
+        let mut tree: Tree<u8, u32> = Tree::new();
+        tree.insert(b"abc", 1);
+        tree.insert(b"abd", 2);
+        tree.insert(b"b", 3);
+
+        let upper = |key: &'static [u8]| key.iter().map(|c| c.to_ascii_lowercase());
+        assert_eq!(tree.get_by_components(upper(b"ABD")), Some(&2));
+        assert_eq!(tree.get_by_components(upper(b"AB")), None);
+        assert_eq!(tree.get_by_components(upper(b"ABE")), None);
+        assert_eq!(tree.get_by_components(upper(b"")), None);
+
+        let found: Vec<_> = tree.find_by_components(upper(b"A")).map(|(k, _)| k).collect();
+        assert_eq!(found, vec![b"abc".to_vec(), b"abd".to_vec()]);
+        assert_eq!(tree.find_by_components(upper(b"ABCD")).count(), 0);
+        assert_eq!(tree.find_by_components(upper(b"AC")).count(), 0);
+        assert_eq!(tree.find_by_components(upper(b"")).count(), 3);
+    }
+
+    #[test]
+    fn it_selects_entries_by_rank() {
+        let keys: Vec<&'static [u8]> = vec![b"", b"a", b"ab", b"abc", b"abd", b"b", b"ba"];
+
+        let mut t = Tree::new();
+        for (i, key) in keys.iter().enumerate().rev() {
+            t.insert(key, i);
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(t.select(i), Some((key.to_vec(), &i)));
+        }
+        assert_eq!(t.select(keys.len()), None);
+    }
+
+    #[test]
+    fn it_moves_entries_out_in_order() {
+        let keys: Vec<&'static [u8]> = vec![b"", b"a", b"ab", b"abc", b"abd", b"b", b"ba"];
+
+        let mut t = Tree::new();
+        for (i, key) in keys.iter().enumerate().rev() {
+            t.insert(key, i);
+        }
+
+        let expected: Vec<_> = keys.iter().enumerate().map(|(i, k)| (k.to_vec(), i)).collect();
+        assert_eq!(t.into_entries(), expected);
+    }
+
+    #[test]
+    fn it_finds_subtrees_accepted_by_a_matcher() {
+        let mut t = Tree::new();
+        for (i, key) in [&b"Abc"[..], b"aBd", b"abe", b"b"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        // case-insensitive "ab", the state being the number of components matched
+        let query = b"ab";
+        let step = |n: &mut usize, c: &u8| {
+            let ok = c.to_ascii_lowercase() == query[*n];
+            *n += 1;
+            ok
+        };
+        let complete = |n: &usize| *n == query.len();
+
+        let keys: Vec<_> = t.find_by(0, &step, &complete).into_iter()
+            .flat_map(|m| m.map(|(k, _)| k))
+            .collect();
+        assert_eq!(keys, vec![b"Abc".to_vec(), b"aBd".to_vec(), b"abe".to_vec()]);
+    }
+
+    #[test]
+    fn it_moves_a_cursor_up_and_down_the_tree() {
+        let mut t = Tree::new();
+        for (i, key) in [&b"abc"[..], b"abd", b"b"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        let keys = |c: &super::Cursor<u8, usize>| c.matches().map(|(k, _)| k).collect::<Vec<_>>();
+
+        let mut c = t.cursor();
+        assert_eq!(keys(&c).len(), 3);
+        assert!(c.push(b'a'));
+        assert_eq!(keys(&c), vec![b"abc".to_vec(), b"abd".to_vec()]);
+        assert!(c.push(b'b'));
+        assert!(c.push(b'd'));
+        assert_eq!(keys(&c), vec![b"abd".to_vec()]);
+        assert!(!c.push(b'x'));
+        assert!(!c.push(b'y'));
+        assert!(keys(&c).is_empty());
+
+        assert_eq!(c.pop(), Some(b'y'));
+        assert_eq!(c.pop(), Some(b'x'));
+        assert_eq!(c.pop(), Some(b'd'));
+        assert_eq!(c.key(), b"ab");
+        assert_eq!(keys(&c), vec![b"abc".to_vec(), b"abd".to_vec()]);
+    }
+
+    #[test]
+    fn it_walks_entries_up_to_a_length() {
+        let mut t = Tree::new();
+        for (i, key) in [&b"a"[..], b"abc", b"abcd", b"abd", b"b"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        let mut keys = Vec::new();
+        t.walk(b"ab", 0, 3, |k, _| { keys.push(k.to_vec()); true });
+        assert_eq!(keys, vec![b"abc".to_vec(), b"abd".to_vec()]);
+
+        let mut keys = Vec::new();
+        t.walk(b"", 0, 10, |k, _| { keys.push(k.to_vec()); keys.len() < 2 });
+        assert_eq!(keys, vec![b"a".to_vec(), b"abc".to_vec()]);
+    }
+
+    #[test]
+    fn it_keeps_track_of_key_lengths() {
+        let mut t = Tree::new();
+        let mut keys: Vec<Vec<u8>> = Vec::new();
+        // grow and shrink the tree, with keys sharing prefixes of varying lengths
+        for i in 0..400u32 {
+            let key: Vec<u8> = (0..(i * 7 % 13)).map(|j| b'a' + ((i >> j) % 3) as u8).collect();
+            if i % 3 == 2 {
+                let old = keys.swap_remove((i as usize * 5) % keys.len());
+                t.remove(&old);
+            }
+            t.insert(&key, ());
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+            assert!(t.check().is_ok(), "{:?}", t.check());
+        }
+
+        for &(min_len, max_len) in [(0, 0), (3, 3), (2, 6), (7, 12), (13, 20)].iter() {
+            let mut walked = Vec::new();
+            t.walk(b"a", min_len, max_len, |k, _| { walked.push(k.to_vec()); true });
+
+            let mut expected: Vec<_> = keys.iter()
+                .filter(|k| k.starts_with(b"a") && min_len <= k.len() && k.len() <= max_len)
+                .cloned()
+                .collect();
+            expected.sort();
+            assert_eq!(walked, expected);
+        }
+    }
+
+    #[test]
+    fn it_dumps_its_structure() {
+        use std::fmt::Write;
+
+        let mut t = Tree::new();
+        for key in [&b"abc"[..], b"abd", b"a", b"b"].iter() {
+            t.insert(key, ());
+        }
+        assert_eq!(t.check(), Ok(()));
+
+        let mut dump = String::new();
+        t.dump(&mut dump, &|label: &[u8], w: &mut String| write!(w, "{:?}", label)).unwrap();
+        assert_eq!(dump, "(root) (4)\n  [97] * (3)\n    [98] (2)\n      [99] * (1)\n      [100] * (1)\n  [98] * (1)\n");
+    }
+
+    #[test]
+    fn it_finds_the_first_entry_of_a_prefix() {
+        let mut t = Tree::new();
+        assert_eq!(t.find_first(b""), None);
+
+        for (i, key) in [&b"abcd"[..], b"abd", b"abce", b"b"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        assert_eq!(t.find_first(b""), Some((b"abcd".to_vec(), &0)));
+        assert_eq!(t.find_first(b"abd"), Some((b"abd".to_vec(), &1)));
+        assert_eq!(t.find_first(b"b"), Some((b"b".to_vec(), &3)));
+        assert_eq!(t.find_first(b"c"), None);
+    }
+
+    #[test]
+    fn it_ranks_keys() {
+        let keys: [&[u8]; 7] = [b"", b"ab", b"abc", b"abd", b"ac", b"b", b"ba"];
+        let mut t = Tree::new();
+        for key in keys.iter() {
+            t.insert(key, ());
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(t.rank(key), i);
+            assert_eq!(t.select(i).unwrap().0, key.to_vec());
+        }
+
+        for key in [&b"a"[..], b"aa", b"abb", b"abcd", b"abz", b"c", b"bb", b"az"].iter() {
+            assert_eq!(t.rank(key), keys.iter().filter(|k| k < &key).count(), "{:?}", key);
+        }
+
+        assert_eq!(t.select_in(b"ab", 1).unwrap().0, b"abc".to_vec());
+        assert_eq!(t.select_in(b"a", 3).unwrap().0, b"ac".to_vec());
+        assert!(t.select_in(b"a", 4).is_none());
+        assert!(t.select_in(b"c", 0).is_none());
+    }
+
+    #[test]
+    fn it_resolves_paths_to_nodes() {
+        let mut t = Tree::new();
+        for (i, key) in [&b"abc"[..], b"abd", b"b"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        let (path, prefix) = t.node_path(b"a").unwrap();
+        assert_eq!((&path[..], &prefix[..]), (&[0][..], &b"ab"[..]));
+        assert!(t.node_path(b"ac").is_none());
+
+        let keys: Vec<_> = t.resolve(&path, prefix).unwrap().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![b"abc".to_vec(), b"abd".to_vec()]);
+        assert!(t.resolve(&[0, 2], Vec::new()).is_none());
+
+        // only structural changes renew the generation
+        let generation = t.generation();
+        t.insert(b"b", 3);
+        assert_eq!(t.generation(), generation);
+        t.remove(b"abd");
+        assert_ne!(t.generation(), generation);
+
+        // nor do trees derived from another share its generation
+        let generation = t.generation();
+        assert_ne!(t.map_values(|v| v).generation(), generation);
+    }
+
+    #[test]
+    fn it_checks_many_keys_at_once() {
+        let mut t = Tree::new();
+        for key in [&b"abc"[..], b"abd", b"ab", b"b", b"abcde"].iter() {
+            t.insert(key, ());
+        }
+
+        let keys: [&[u8]; 9] = [b"abcde", b"ab", b"abd", b"a", b"abcd", b"", b"abd", b"b", b"abcdef"];
+        let expected: Vec<bool> = keys.iter().map(|k| t.get(k).is_some()).collect();
+        assert_eq!(t.contains_each(&keys), expected);
+        assert_eq!(expected, vec![true, true, true, false, false, false, true, true, false]);
+    }
+
+    #[test]
+    fn it_finds_keys_matching_a_pattern() {
+        let mut t = Tree::new();
+        for (i, key) in [&b"ACGT"[..], b"ACTT", b"AGGT", b"ACG", b"TCGT", b"ACGTA"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        let keys = |pattern: &[Option<u8>]| -> Vec<Vec<u8>> {
+            t.find_pattern(pattern).into_iter().map(|(k, _)| k).collect()
+        };
+        assert_eq!(keys(&[Some(b'A'), None, Some(b'G'), Some(b'T')]), vec![b"ACGT".to_vec(), b"AGGT".to_vec()]);
+        assert_eq!(keys(&[None, Some(b'C'), None, None]), vec![b"ACGT".to_vec(), b"ACTT".to_vec(), b"TCGT".to_vec()]);
+        assert_eq!(keys(&[None, None, None]), vec![b"ACG".to_vec()]);
+        assert_eq!(keys(&[Some(b'G'), None]), Vec::<Vec<u8>>::new());
+        assert_eq!(keys(&[]), Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    fn it_yields_the_values_on_the_path_to_a_key() {
+        let mut t = Tree::new();
+        for (i, key) in [&b""[..], b"a", b"abc", b"abcde", b"abd"].iter().enumerate() {
+            t.insert(key, i);
+        }
+
+        assert_eq!(t.ancestors(b"abcdef").collect::<Vec<_>>(), vec![(0, &0), (1, &1), (3, &2), (5, &3)]);
+        assert_eq!(t.ancestors(b"abc").collect::<Vec<_>>(), vec![(0, &0), (1, &1), (3, &2)]);
+        assert_eq!(t.ancestors(b"ab").collect::<Vec<_>>(), vec![(0, &0), (1, &1)]);
+        assert_eq!(t.ancestors(b"b").collect::<Vec<_>>(), vec![(0, &0)]);
+    }
+
+    #[test]
+    fn it_counts_keys_by_prefix() {
+        let mut t = Tree::new();
+        for key in [&b"a"[..], b"abc", b"abd", b"acd", b"b", b"bcd"].iter() {
+            t.insert(key, ());
+        }
+
+        let never = |_: &u8| false;
+        assert_eq!(t.group_count(0, &never), vec![(vec![], 6)]);
+        assert_eq!(t.group_count(1, &never), vec![(b"a".to_vec(), 4), (b"b".to_vec(), 2)]);
+        assert_eq!(t.group_count(2, &never), vec![(b"ab".to_vec(), 2), (b"ac".to_vec(), 1), (b"bc".to_vec(), 1)]);
+        assert_eq!(t.group_count(4, &never), vec![]);
+
+        // groups are extended past the components continuing them
+        let continues = |c: &u8| *c == b'c';
+        assert_eq!(t.group_count(1, &continues), vec![
+            (b"a".to_vec(), 3), (b"ac".to_vec(), 1), (b"b".to_vec(), 1), (b"bc".to_vec(), 1),
+        ]);
+        assert_eq!(t.group_count(2, &continues), vec![
+            (b"ab".to_vec(), 1), (b"abc".to_vec(), 1), (b"ac".to_vec(), 1), (b"bc".to_vec(), 1),
+        ]);
+    }
+
     #[test]
     fn it_tracks_the_number_of_elements_inserted() {
         let mut t = Tree::new();
@@ -0,0 +1,299 @@
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use map::{RadixMap, Matches as MapMatches};
+use key::Key;
+
+/// A source of time for a [`TtlRadixMap`].
+///
+/// [`TtlRadixMap`]: struct.TtlRadixMap.html
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// The system's monotonic clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use std::time::Duration;
+/// use panoradix::ttl::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+///
+/// clock.advance(Duration::from_secs(5));
+/// assert_eq!(clock.now() - start, Duration::from_secs(5));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl ManualClock {
+    /// Makes a new clock, starting at the current time.
+    pub fn new() -> ManualClock {
+        ManualClock { now: Cell::new(Instant::now()) }
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// A map whose entries can expire after a given time-to-live.
+///
+/// Expired entries are never returned, but they are only removed from the map lazily: when
+/// looked up through [`get`] or [`remove`], or when sweeping the whole map with
+/// [`purge_expired`].
+///
+/// [`get`]: #method.get
+/// [`remove`]: #method.remove
+/// [`purge_expired`]: #method.purge_expired
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use std::time::Duration;
+/// use panoradix::ttl::{TtlRadixMap, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let mut sessions = TtlRadixMap::with_clock(&clock);
+/// sessions.insert_with_ttl("session:1", "alice", Duration::from_secs(60));
+/// sessions.insert("session:2", "bob");
+///
+/// clock.advance(Duration::from_secs(61));
+/// assert_eq!(sessions.get("session:1"), None);
+/// assert_eq!(sessions.get("session:2"), Some(&"bob"));
+/// ```
+pub struct TtlRadixMap<K: Key + ?Sized, V, C: Clock = SystemClock> {
+    map: RadixMap<K, Entry<V>>,
+    clock: C,
+}
+
+struct Entry<V> {
+    value: V,
+    expires_at: Option<Instant>,
+}
+
+impl<V> Entry<V> {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+}
+
+impl<K: Key + ?Sized, V> TtlRadixMap<K, V> {
+    /// Makes a new empty map, using the system's clock.
+    pub fn new() -> TtlRadixMap<K, V> {
+        TtlRadixMap::with_clock(SystemClock)
+    }
+}
+
+impl<K: Key + ?Sized, V> Default for TtlRadixMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Key + ?Sized, V, C: Clock> TtlRadixMap<K, V, C> {
+    /// Makes a new empty map, using the given clock.
+    pub fn with_clock(clock: C) -> TtlRadixMap<K, V, C> {
+        TtlRadixMap {
+            map: RadixMap::new(),
+            clock,
+        }
+    }
+
+    /// Returns the number of entries in the map, including the expired entries that weren't
+    /// purged yet.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries, expired or not.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Inserts a key-value pair that never expires.
+    ///
+    /// Returns the value previously stored at this key, if it hadn't expired.
+    pub fn insert(&mut self, key: &K, value: V) -> Option<V> {
+        self.insert_entry(key, Entry { value, expires_at: None })
+    }
+
+    /// Inserts a key-value pair that expires after `ttl`.
+    ///
+    /// Returns the value previously stored at this key, if it hadn't expired.
+    ///
+    /// A `ttl` too long to be represented (e.g. `Duration::MAX`) never expires.
+    pub fn insert_with_ttl(&mut self, key: &K, value: V, ttl: Duration) -> Option<V> {
+        let expires_at = self.clock.now().checked_add(ttl);
+        self.insert_entry(key, Entry { value, expires_at })
+    }
+
+    /// Returns a reference to the value of the key, removing it if it expired.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let now = self.clock.now();
+        if self.map.get(key)?.is_expired(now) {
+            self.map.remove(key);
+            return None;
+        }
+        self.map.get(key).map(|e| &e.value)
+    }
+
+    /// Returns if the key is present in the map and hasn't expired.
+    pub fn contains_key(&self, key: &K) -> bool {
+        let now = self.clock.now();
+        self.map.get(key).is_some_and(|e| !e.is_expired(now))
+    }
+
+    /// Removes a key from the map, returning its value if it hadn't expired.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let now = self.clock.now();
+        self.map.remove(key)
+            .filter(|e| !e.is_expired(now))
+            .map(|e| e.value)
+    }
+
+    /// Removes all the expired entries, returning how many were removed.
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let expired: Vec<_> = self.map.iter()
+            .filter(|(_, e)| e.is_expired(now))
+            .map(|(k, _)| k)
+            .collect();
+
+        for key in expired.iter() {
+            self.map.remove(key.borrow());
+        }
+        expired.len()
+    }
+
+    /// Gets an iterator over the entries whose key starts with `key` and that haven't expired,
+    /// sorted by key.
+    pub fn find<'a>(&'a self, key: &K) -> Matches<'a, K, V> {
+        Matches {
+            matches: self.map.find(key),
+            now: self.clock.now(),
+        }
+    }
+
+    fn insert_entry(&mut self, key: &K, entry: Entry<V>) -> Option<V> {
+        let now = self.clock.now();
+        self.map.insert(key, entry)
+            .filter(|e| !e.is_expired(now))
+            .map(|e| e.value)
+    }
+}
+
+/// An iterator over the entries matching a call to [`find`].
+///
+/// [`find`]: struct.TtlRadixMap.html#method.find
+pub struct Matches<'a, K: 'a + Key + ?Sized, V: 'a> {
+    matches: MapMatches<'a, K, Entry<V>>,
+    now: Instant,
+}
+
+debug_iterator!(['a, K: 'a + Key + ?Sized, V: 'a] Matches<'a, K, V>);
+
+impl<'a, K: 'a + Key + ?Sized, V: 'a> Iterator for Matches<'a, K, V> {
+    type Item = (K::Owned, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.now;
+        self.matches.by_ref()
+            .find(|(_, e)| !e.is_expired(now))
+            .map(|(k, e)| (k, &e.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{TtlRadixMap, ManualClock};
+
+    #[test]
+    fn it_expires_entries() {
+        let clock = ManualClock::new();
+        let mut map = TtlRadixMap::with_clock(&clock);
+        map.insert_with_ttl("a", 0, Duration::from_secs(10));
+        map.insert_with_ttl("b", 1, Duration::from_secs(20));
+        map.insert("c", 2);
+
+        clock.advance(Duration::from_secs(10));
+        assert!(!map.contains_key("a"));
+        assert!(map.contains_key("b"));
+        assert_eq!(map.find("").map(|(k, _)| k).collect::<Vec<_>>(), vec!["b", "c"]);
+
+        // expired entries are kept until they are purged
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.len(), 2);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(map.purge_expired(), 1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("c"), Some(&2));
+    }
+
+    #[test]
+    fn it_does_not_return_expired_values() {
+        let clock = ManualClock::new();
+        let mut map = TtlRadixMap::with_clock(&clock);
+        map.insert_with_ttl("a", 0, Duration::from_secs(1));
+        map.insert_with_ttl("b", 1, Duration::from_secs(1));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(map.insert("a", 2), None);
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.get("a"), Some(&2));
+    }
+
+    #[test]
+    fn it_never_expires_overflowing_ttls() {
+        let clock = ManualClock::new();
+        let mut map = TtlRadixMap::with_clock(&clock);
+        map.insert_with_ttl("a", 0, Duration::MAX);
+
+        clock.advance(Duration::from_secs(1_000_000));
+        assert_eq!(map.purge_expired(), 0);
+        assert_eq!(map.get("a"), Some(&0));
+    }
+}
@@ -0,0 +1,127 @@
+//! Checks that lookups don't allocate, with an allocator counting the allocations of each thread.
+
+extern crate panoradix;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use panoradix::{RadixMap, RadixSet};
+#[cfg(feature = "icu")]
+use panoradix::collation::CollatedMap;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Calls `f`, returning the number of allocations it made on the current thread.
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn it_counts_allocations() {
+    assert_eq!(allocations(|| drop(String::from("id:0"))), 1);
+}
+
+#[test]
+fn it_looks_short_keys_up_without_allocating() {
+    // the keys of the `lookup_short_keys` bench, half of the queries being missing
+    let keys: Vec<String> = (0..1000).map(|i| format!("id:{:x}", i * 7919)).collect();
+    let queries: Vec<String> = (0..1000).map(|i| format!("id:{:x}", i * 7919 + i % 2)).collect();
+
+    let set: RadixSet<str> = keys.iter().collect();
+    let map: RadixMap<str, usize> = keys.iter().enumerate().map(|(i, k)| (k, i)).collect();
+
+    let mut found = 0;
+    let n = allocations(|| {
+        for query in queries.iter() {
+            found += set.contains(query) as usize;
+            found += set.contains(query.as_str()) as usize;
+            found += map.contains_key(query) as usize;
+            found += map.get(query.as_str()).is_some() as usize;
+        }
+    });
+    assert_eq!(n, 0);
+    assert_eq!(found, 4 * 500);
+}
+
+#[test]
+fn it_looks_byte_keys_up_without_allocating() {
+    let set: RadixSet<[u8]> = (0..1000u32).map(|i| i.to_be_bytes()).collect::<Vec<_>>().iter().collect();
+    let queries: Vec<Vec<u8>> = (0..2000u32).map(|i| i.to_be_bytes().to_vec()).collect();
+
+    let mut found = 0;
+    let n = allocations(|| {
+        for query in queries.iter() {
+            found += set.contains(query) as usize;
+            found += set.contains(&query[..]) as usize;
+        }
+    });
+    assert_eq!(n, 0);
+    assert_eq!(found, 2 * 1000);
+}
+
+#[test]
+fn it_looks_rewritten_keys_up_without_allocating() {
+    let map: RadixMap<str, usize> = vec![("content-type", 0), ("content-length", 1), ("room 42", 2)].into_iter().collect();
+    let ascii_digit = |c: char| match c {
+        '٠'..='٩' => (b'0' + (c as u32 - '٠' as u32) as u8) as char,
+        c         => c,
+    };
+
+    let mut found = Vec::with_capacity(3);
+    let n = allocations(|| {
+        found.push(map.get_rewritten("Content-Type", |c| c.to_ascii_lowercase()));
+        found.push(map.get_rewritten("Content", |c| c.to_ascii_lowercase()));
+        found.push(map.get_rewritten_chars("room ٤٢", ascii_digit));
+    });
+    assert_eq!(n, 0);
+    assert_eq!(found, vec![Some(&0), None, Some(&2)]);
+}
+
+#[test]
+fn it_only_builds_the_keys_yielded_by_weight() {
+    let map: RadixMap<str, usize> = (0..1000).map(|i| (format!("word:{}", i), i % 97)).collect();
+
+    let mut best = None;
+    let n = allocations(|| best = map.find_by_weight("word:", |&w| w).next());
+    // ties are won by the smallest key
+    assert_eq!(best, Some(("word:193".to_string(), &96)));
+    // the heap, the buffers of the traversal and the key of the best entry, whatever the number
+    // of matches
+    assert!(n < 16, "{} allocations", n);
+}
+
+#[test]
+#[cfg(feature = "icu")]
+fn it_ranges_over_collated_keys_without_copying_their_sort_keys() {
+    let mut map = CollatedMap::new(|s: &str| s.to_lowercase().into_bytes());
+    for i in 0..1000 {
+        map.insert(&format!("Key:{:03}", i), i);
+    }
+
+    let mut found = 0;
+    let n = allocations(|| found = map.range("key:000", "key:900").count());
+    assert_eq!(found, 900);
+    // the sort keys of the bounds and the buffers of the traversal, whatever the number of
+    // entries in the range
+    assert!(n < 16, "{} allocations", n);
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "complexity")]
+
+extern crate panoradix;
+
+use panoradix::RadixMap;
+use panoradix::complexity::{measure, OpCounts};
+
+const SIZES: [usize; 3] = [16, 128, 1024];
+const KEY_LENGTHS: [usize; 3] = [8, 32, 128];
+
+// Keys of `len` components out of 16, drawn with a xorshift so that the maps are the same on
+// every run.
+fn keys(n: usize, len: usize, seed: u64) -> Vec<String> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..n)
+        .map(|_| {
+            (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    char::from(b'a' + (state % 16) as u8)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn map_of(keys: &[String]) -> RadixMap<str, usize> {
+    keys.iter().enumerate().map(|(i, k)| (k.as_str(), i)).collect()
+}
+
+// A descent along a key of length `len` compares at most one edge per component, plus the one it
+// stops at, and matches each component at most once.
+fn assert_bounded(operation: &str, counts: OpCounts, len: usize, size: usize) {
+    assert!(counts.edges() <= len as u64 + 1,
+            "{} compared {} edges for a key of length {} in a map of {} keys", operation, counts.edges(), len, size);
+    assert!(counts.components() <= len as u64,
+            "{} matched {} components for a key of length {} in a map of {} keys", operation, counts.components(), len, size);
+}
+
+#[test]
+fn it_gets_keys_in_time_linear_in_their_length() {
+    for &len in &KEY_LENGTHS {
+        for &size in &SIZES {
+            let keys = keys(size, len, 1);
+            let missing = self::keys(size, len, 2);
+            let map = map_of(&keys);
+
+            for key in keys.iter().step_by(size / 16) {
+                let (value, counts) = measure(|| map.get(key.as_str()).copied());
+                assert!(value.is_some());
+                assert_bounded("get", counts, len, size);
+                // a key in the map is matched whole
+                assert_eq!(counts.components(), len as u64);
+            }
+            for key in missing.iter().step_by(size / 16) {
+                let (_, counts) = measure(|| map.get(key.as_str()).copied());
+                assert_bounded("get", counts, len, size);
+            }
+        }
+    }
+}
+
+#[test]
+fn it_inserts_and_removes_keys_in_time_linear_in_their_length() {
+    for &len in &KEY_LENGTHS {
+        for &size in &SIZES {
+            let keys = keys(size, len, 3);
+            let new_keys = self::keys(16, len, 4);
+            let mut map = map_of(&keys);
+
+            for key in &new_keys {
+                let (_, counts) = measure(|| map.insert(key, 0));
+                assert_bounded("insert", counts, len, size);
+            }
+            for key in new_keys.iter().chain(keys.iter().step_by(size / 16)) {
+                let (value, counts) = measure(|| map.remove(key.as_str()));
+                assert!(value.is_some());
+                assert_bounded("remove", counts, len, size);
+                assert_eq!(counts.components(), len as u64);
+            }
+        }
+    }
+}
+
+#[test]
+fn it_finds_prefixes_in_time_linear_in_their_length() {
+    for &len in &KEY_LENGTHS {
+        for &size in &SIZES {
+            let keys = keys(size, len, 5);
+            let map = map_of(&keys);
+
+            for key in keys.iter().step_by(size / 16) {
+                for prefix in &[&key[..1], &key[..len / 2], &key[..]] {
+                    // finding the subtree is bounded by the length of the prefix, iterating it
+                    // doesn't compare keys
+                    let (matches, counts) = measure(|| map.find(prefix).count());
+                    assert!(matches >= 1);
+                    assert_bounded("find", counts, prefix.len(), size);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn it_does_the_same_work_whatever_the_size_of_the_map() {
+    // the same key, looked up in maps of increasing sizes sharing none of its prefixes
+    let key = "z".repeat(64);
+    let counts: Vec<_> = SIZES.iter()
+        .map(|&size| {
+            let mut map = map_of(&keys(size, 64, 6));
+            map.insert(&key, 0);
+            measure(|| map.get(key.as_str()).copied()).1
+        })
+        .collect();
+
+    assert!(counts.windows(2).all(|w| w[0] == w[1]), "{:?}", counts);
+    assert_eq!(counts[0].components(), 64);
+}
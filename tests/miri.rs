@@ -0,0 +1,96 @@
+//! Small workloads over the code paths relying on `unsafe` (the unchecked UTF-8 conversions of
+//! `str` keys) or on borrowing from iterators, sized to run under Miri:
+//!
+//! ```sh
+//! cargo +nightly miri test --test miri
+//! ```
+
+extern crate panoradix;
+
+use panoradix::{RadixMap, RadixSet};
+
+// multi-byte characters sharing their first bytes, so edges are split inside characters
+static WORDS: [&str; 8] = ["été", "étal", "éte", "ça", "çà", "日本", "日本語", "日曜"];
+
+fn map() -> RadixMap<str, usize> {
+    WORDS.iter().enumerate().map(|(i, &w)| (w, i)).collect()
+}
+
+#[test]
+fn it_rebuilds_keys_split_inside_characters() {
+    let map = map();
+
+    let mut expected: Vec<_> = WORDS.to_vec();
+    expected.sort();
+    assert_eq!(map.keys().collect::<Vec<_>>(), expected);
+    assert_eq!(map.find("日本").map(|(k, _)| k).collect::<Vec<_>>(), vec!["日本", "日本語"]);
+    assert_eq!(map.find_one("ç"), Some(("ça".to_string(), &3)));
+}
+
+#[test]
+fn it_lends_keys_from_reused_buffers() {
+    let map = map();
+
+    let mut lent = Vec::new();
+    let mut it = map.iter();
+    while let Some((key, _)) = it.next_ref() {
+        lent.push(key.to_string());
+    }
+    assert_eq!(lent, map.keys().collect::<Vec<_>>());
+
+    let mut matches = map.find("ét");
+    let mut found = Vec::new();
+    while let Some((key, &i)) = matches.next_ref() {
+        assert_eq!(WORDS[i], key);
+        found.push(i);
+    }
+    assert_eq!(found.len(), 3);
+
+    let mut raw = map.raw_iter();
+    let mut count = 0;
+    while let Some((key, _)) = raw.next_entry() {
+        assert!(!key.is_empty());
+        count += 1;
+    }
+    assert_eq!(count, WORDS.len());
+}
+
+#[test]
+fn it_truncates_keys_inside_characters() {
+    let map = map();
+
+    // 1 byte prefixes end in the middle of every character
+    let groups = map.group_count(1);
+    assert_eq!(groups.iter().map(|&(_, n)| n).sum::<usize>(), WORDS.len());
+    assert_eq!(map.ancestors("日本語です").map(|(k, _)| k).collect::<Vec<_>>(), vec!["日本", "日本語"]);
+}
+
+#[test]
+fn it_walks_with_cursors() {
+    let map = map();
+
+    let mut completer = map.completer();
+    assert!(completer.push_char('日'));
+    assert!(completer.push_char('本'));
+    assert_eq!(completer.completions().count(), 2);
+    assert_eq!(completer.pop_char(), Some('本'));
+    assert!(!completer.push_char('x'));
+    assert_eq!(completer.completions().count(), 0);
+    assert_eq!(completer.pop_char(), Some('x'));
+    assert_eq!(completer.completions().count(), 3);
+}
+
+#[test]
+fn it_converts_between_collections() {
+    let set: RadixSet<str> = WORDS.iter().collect();
+    let sorted = set.to_sorted_vec();
+    let mut copy = RadixMap::<str, ()>::new();
+    for key in sorted.iter() {
+        copy.insert(key, ());
+    }
+    assert_eq!(copy.len(), set.len());
+
+    let merged: Vec<_> = RadixMap::merge_iter(&[&map(), &map()]).map(|(k, v)| (k, v.len())).collect();
+    assert_eq!(merged.len(), WORDS.len());
+    assert!(merged.iter().all(|&(_, n)| n == 2));
+}
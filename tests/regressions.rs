@@ -0,0 +1,27 @@
+#![cfg(test)]
+
+extern crate panoradix;
+
+use std::fs;
+use std::path::Path;
+
+use panoradix::replay;
+
+#[test]
+fn it_replays_recorded_traces() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("regressions");
+
+    let mut paths: Vec<_> = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "trace"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let trace = fs::read_to_string(&path).unwrap();
+        if let Err(e) = replay::replay(&trace) {
+            panic!("{}, {}", path.display(), e);
+        }
+    }
+}
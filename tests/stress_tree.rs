@@ -1,4 +1,6 @@
 #![cfg(test)]
+#![allow(clippy::large_const_arrays, clippy::redundant_static_lifetimes)]
+#![allow(clippy::map_clone, clippy::unnecessary_to_owned)]
 
 extern crate panoradix;
 
@@ -12,6 +14,7 @@ fn stress_test() {
     for &(word, n) in STRINGS.iter() {
         map.insert(&word.to_string(), n);
     }
+    map.check_invariants();
 
     for &(word, n) in STRINGS.iter() {
         let found_n = map.get(&word.to_string()).map(|x| *x);